@@ -1,9 +1,15 @@
-use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, Range};
-use tree_sitter::Node;
+use std::collections::{HashMap, HashSet};
+
+use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, DiagnosticTag, Range};
+use tree_sitter::{Node, Tree};
 
 use crate::errors::AnalysisError;
 use crate::helpers;
-use crate::workspace::Workspace;
+use crate::symbol_resolver::{SymbolResolver, name_matches};
+use crate::workspace::{FileEntry, Workspace};
+
+/// Engine classes of the base game, for `Workspace::load_stubs`
+pub const BATTLE_BROTHERS_API: &str = include_str!("bb_api.txt");
 
 /// Pattern: `identifier <- inherit("path/to/parent", { body })`
 #[derive(Debug, Clone)]
@@ -136,6 +142,7 @@ pub struct HookCall<'tree> {
     pub target_path_node: Node<'tree>,
     pub hook_function: Node<'tree>,
     pub hook_param_name: Option<String>,
+    pub hook_param_node: Option<Node<'tree>>,
 }
 
 pub fn find_hook_calls<'tree>(root: Node<'tree>, text: &str) -> Vec<HookCall<'tree>> {
@@ -163,6 +170,18 @@ pub fn find_hook_calls<'tree>(root: Node<'tree>, text: &str) -> Vec<HookCall<'tr
     results
 }
 
+/// Map a `::mods_hook*` function name to its hook type
+pub fn hook_type_from_name(name: &str) -> Option<HookType> {
+    match name {
+        "mods_hookExactClass" => Some(HookType::Exact),
+        "mods_hookBaseClass" => Some(HookType::Base),
+        "mods_hookDescendants" => Some(HookType::Descendants),
+        "mods_hookNewObject" => Some(HookType::NewObject),
+        "mods_hookNewObjectOnce" => Some(HookType::NewObjectOnce),
+        _ => None,
+    }
+}
+
 fn parse_hook_call<'tree>(call: Node<'tree>, text: &str) -> Option<HookCall<'tree>> {
     let mut hook_type = None;
     let mut target_path = String::new();
@@ -174,15 +193,7 @@ fn parse_hook_call<'tree>(call: Node<'tree>, text: &str) -> Option<HookCall<'tre
             "global_variable" => {
                 for gchild in child.children(&mut child.walk()) {
                     if gchild.kind() == "identifier" {
-                        let name = get_node_text(gchild, text);
-                        hook_type = match name {
-                            "mods_hookExactClass" => Some(HookType::Exact),
-                            "mods_hookBaseClass" => Some(HookType::Base),
-                            "mods_hookDescendants" => Some(HookType::Descendants),
-                            "mods_hookNewObject" => Some(HookType::NewObject),
-                            "mods_hookNewObjectOnce" => Some(HookType::NewObjectOnce),
-                            _ => None,
-                        };
+                        hook_type = hook_type_from_name(get_node_text(gchild, text));
                     }
                 }
             },
@@ -210,7 +221,8 @@ fn parse_hook_call<'tree>(call: Node<'tree>, text: &str) -> Option<HookCall<'tre
         && let Some(hf) = hook_function
     {
         // Extract the parameter name from the hook function
-        let hook_param_name = extract_first_param_name(hf, text);
+        let hook_param_node = first_param(hf);
+        let hook_param_name = hook_param_node.map(|name| get_node_text(name, text).to_string());
 
         Some(HookCall {
             node: call,
@@ -219,24 +231,19 @@ fn parse_hook_call<'tree>(call: Node<'tree>, text: &str) -> Option<HookCall<'tre
             target_path_node: pn,
             hook_function: hf,
             hook_param_name,
+            hook_param_node,
         })
     } else {
         None
     }
 }
 
-fn extract_first_param_name(func_node: Node, text: &str) -> Option<String> {
+fn first_param(func_node: Node) -> Option<Node> {
     for child in func_node.children(&mut func_node.walk()) {
         if child.kind() == "parameters" || child.kind() == "function_parameters" {
             for param in child.children(&mut child.walk()) {
-                if param.kind() == "identifier" {
-                    return Some(get_node_text(param, text).to_string());
-                } else if param.kind() == "parameter" {
-                    for p_child in param.children(&mut param.walk()) {
-                        if p_child.kind() == "identifier" {
-                            return Some(get_node_text(p_child, text).to_string());
-                        }
-                    }
+                if let Some(name) = helpers::parameter_name(param) {
+                    return Some(name);
                 }
             }
         }
@@ -246,23 +253,45 @@ fn extract_first_param_name(func_node: Node, text: &str) -> Option<String> {
 
 pub fn analyze_hooks(text: &str, workspace: &Workspace) -> Result<Vec<Diagnostic>, AnalysisError> {
     let tree = helpers::parse_squirrel(text)?;
+    Ok(analyze_hooks_with_tree(&tree, text, workspace))
+}
+
+/// Like `analyze_hooks`, but reuses an already parsed tree.
+pub fn analyze_hooks_with_tree(tree: &Tree, text: &str, workspace: &Workspace) -> Vec<Diagnostic> {
     let root = tree.root_node();
 
     let mut diagnostics = Vec::new();
     let hooks = find_hook_calls(root, text);
+    // Building a resolver walks the whole file, so every hook shares one
+    let mut resolver = hooks
+        .iter()
+        .any(|hook| hook.hook_param_node.is_some())
+        .then(|| SymbolResolver::with_tree("", tree, text));
 
     for hook in hooks {
         diagnostics.extend(validate_hook_path(&hook, workspace, text));
         diagnostics.extend(validate_hook_methods(&hook, workspace, text));
+        diagnostics.extend(validate_hook_m_fields(&hook, workspace, text));
         diagnostics.extend(validate_hook_type(&hook, workspace, text));
+        if let Some(resolver) = &mut resolver {
+            diagnostics.extend(validate_hook_parameter(&hook, resolver, text));
+        }
     }
 
-    Ok(diagnostics)
+    diagnostics
 }
 
 fn validate_hook_path(hook: &HookCall, workspace: &Workspace, text: &str) -> Vec<Diagnostic> {
-    if workspace.get(&hook.target_path).is_some() {
-        return Vec::new();
+    if let Some(entry) = workspace.get_ignoring_case(&hook.target_path) {
+        return path_case_mismatch(
+            &hook.target_path,
+            entry,
+            hook.target_path_node,
+            text,
+            "squirrel-bb-hook",
+        )
+        .into_iter()
+        .collect();
     }
 
     let range = Range::new(
@@ -283,13 +312,75 @@ fn validate_hook_path(hook: &HookCall, workspace: &Workspace, text: &str) -> Vec
         severity: Some(DiagnosticSeverity::ERROR),
         source: Some("squirrel-bb-hook".to_string()),
         message,
-        code: Some(tower_lsp::lsp_types::NumberOrString::String(
-            "hook-path-not-found".to_string(),
-        )),
-        ..Diagnostic::default()
+        ..helpers::coded_diagnostic("hook-path-not-found")
     }]
 }
 
+/// Battle Brothers loads scripts case-sensitively, so a path that only
+/// resolves by ignoring case works on macOS/Windows but breaks elsewhere.
+fn path_case_mismatch(
+    requested: &str,
+    entry: &FileEntry,
+    node: Node,
+    text: &str,
+    source: &str,
+) -> Option<Diagnostic> {
+    let normalized = requested
+        .trim_start_matches("scripts/")
+        .trim_end_matches(".nut");
+    if normalized == entry.script_path {
+        return None;
+    }
+
+    let indexed = if requested.starts_with("scripts/") {
+        format!("scripts/{}", entry.script_path)
+    } else {
+        entry.script_path.clone()
+    };
+    Some(Diagnostic {
+        range: Range::new(
+            helpers::position_at(text, node.start_byte()),
+            helpers::position_at(text, node.end_byte()),
+        ),
+        severity: Some(DiagnosticSeverity::WARNING),
+        source: Some(source.to_string()),
+        message: format!("Path case mismatch: indexed as '{indexed}'"),
+        ..helpers::coded_diagnostic("path-case-mismatch")
+    })
+}
+
+/// A hook whose function never reads its parameter can't modify the hooked
+/// object, which is almost always a mistake.
+fn validate_hook_parameter(
+    hook: &HookCall,
+    resolver: &mut SymbolResolver,
+    text: &str,
+) -> Option<Diagnostic> {
+    let param = hook.hook_param_node?;
+    let name = hook.hook_param_name.as_ref()?;
+    let range = Range::new(
+        helpers::position_at(text, param.start_byte()),
+        helpers::position_at(text, param.end_byte()),
+    );
+
+    let unused = resolver.unused_parameters(hook.hook_function);
+    if !unused.contains(&range) {
+        return None;
+    }
+
+    Some(Diagnostic {
+        range,
+        severity: Some(DiagnosticSeverity::HINT),
+        source: Some("squirrel-bb-hook".to_string()),
+        message: format!(
+            "Hook parameter '{}' is never used, so the hook leaves '{}' unchanged",
+            name, hook.target_path
+        ),
+        tags: Some(vec![DiagnosticTag::UNNECESSARY]),
+        ..helpers::coded_diagnostic("unused-hook-parameter")
+    })
+}
+
 fn validate_hook_methods(hook: &HookCall, workspace: &Workspace, text: &str) -> Vec<Diagnostic> {
     let mut diagnostics = Vec::new();
 
@@ -303,6 +394,16 @@ fn validate_hook_methods(hook: &HookCall, workspace: &Workspace, text: &str) ->
         None => return diagnostics,
     };
 
+    // Bundled stubs resolve paths but don't list every method
+    if target_entry.is_stub()
+        || workspace
+            .get_ancestors(&hook.target_path)
+            .iter()
+            .any(|ancestor| ancestor.is_stub())
+    {
+        return diagnostics;
+    }
+
     let accesses = find_member_accesses(hook.hook_function, text);
 
     for access in accesses {
@@ -335,10 +436,7 @@ fn validate_hook_methods(hook: &HookCall, workspace: &Workspace, text: &str) ->
                     severity: Some(DiagnosticSeverity::ERROR),
                     source: Some("squirrel-bb-hook".to_string()),
                     message,
-                    code: Some(tower_lsp::lsp_types::NumberOrString::String(
-                        "method-not-found".to_string(),
-                    )),
-                    ..Diagnostic::default()
+                    ..helpers::coded_diagnostic("method-not-found")
                 });
             }
         }
@@ -347,6 +445,57 @@ fn validate_hook_methods(hook: &HookCall, workspace: &Workspace, text: &str) ->
     diagnostics
 }
 
+/// Check `o.m.Field` reads against the `m` tables of the hooked class and its
+/// ancestors. Skipped when none of them declares an `m` table.
+fn validate_hook_m_fields(hook: &HookCall, workspace: &Workspace, text: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    let (Some(target_entry), Some(param_name)) =
+        (workspace.get(&hook.target_path), &hook.hook_param_name)
+    else {
+        return diagnostics;
+    };
+    // Fields of an unindexed or stubbed ancestor are unknown
+    if !workspace.has_resolved_ancestry(&hook.target_path) {
+        return diagnostics;
+    }
+
+    let m_fields: HashSet<String> = workspace
+        .get_all_m_fields(&hook.target_path)
+        .into_iter()
+        .map(|m| m.name)
+        .collect();
+    if m_fields.is_empty() {
+        return diagnostics;
+    }
+
+    let mut pending = vec![hook.hook_function];
+    while let Some(node) = pending.pop() {
+        if let Some((base, field)) = helpers::m_field_access(node, text)
+            && get_node_text(base, text) == param_name
+            && !m_fields.contains(get_node_text(field, text))
+        {
+            diagnostics.push(Diagnostic {
+                range: Range::new(
+                    helpers::position_at(text, field.start_byte()),
+                    helpers::position_at(text, field.end_byte()),
+                ),
+                severity: Some(DiagnosticSeverity::WARNING),
+                source: Some("squirrel-bb-hook".to_string()),
+                message: format!(
+                    "Field '{}' not found in 'm' of '{}' or its ancestors",
+                    get_node_text(field, text),
+                    target_entry.name
+                ),
+                ..helpers::coded_diagnostic("unknown-m-field")
+            });
+        }
+        pending.extend(node.children(&mut node.walk()));
+    }
+
+    diagnostics
+}
+
 fn validate_hook_type(hook: &HookCall, workspace: &Workspace, text: &str) -> Vec<Diagnostic> {
     let mut diagnostics = Vec::new();
 
@@ -370,10 +519,7 @@ fn validate_hook_type(hook: &HookCall, workspace: &Workspace, text: &str) -> Vec
                     "Using 'hookExactClass' on '{}' which has {} descendant(s). Consider 'hookBaseClass' to affect all descendants.",
                     target_entry.name, children_count
                 ),
-                code: Some(tower_lsp::lsp_types::NumberOrString::String(
-                    "hook-type-suggestion".to_string(),
-                )),
-                ..Diagnostic::default()
+                ..helpers::coded_diagnostic("hook-type-suggestion")
             });
         },
         HookType::Descendants if !has_children => {
@@ -387,10 +533,7 @@ fn validate_hook_type(hook: &HookCall, workspace: &Workspace, text: &str) -> Vec
                     "Using 'hookDescendants' on '{}' which has no descendants. Consider 'hookExactClass'.",
                     target_entry.name
                 ),
-                code: Some(tower_lsp::lsp_types::NumberOrString::String(
-                    "hook-type-no-descendants".to_string(),
-                )),
-                ..Diagnostic::default()
+                ..helpers::coded_diagnostic("hook-type-no-descendants")
             });
         },
         _ => {},
@@ -399,6 +542,121 @@ fn validate_hook_type(hook: &HookCall, workspace: &Workspace, text: &str) -> Vec
     diagnostics
 }
 
+/// Hint at hooks that replace an existing method, as in
+/// `o.onUpdate = function() {...}`, without calling the original through a
+/// captured `local onUpdate = o.onUpdate` or an `__original` parameter.
+/// Such an override drops whatever the class and other mods did there.
+/// Methods matching `allowlist` (a trailing `*` matches any suffix) are
+/// skipped.
+pub fn analyze_hook_overrides_with_tree(
+    tree: &Tree,
+    text: &str,
+    workspace: &Workspace,
+    allowlist: &[String],
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for hook in find_hook_calls(tree.root_node(), text) {
+        let Some(param_name) = &hook.hook_param_name else {
+            continue;
+        };
+        if workspace.get(&hook.target_path).is_none() {
+            continue;
+        }
+
+        // Locals holding the original of each method, `local x = o.name`
+        let mut originals: HashMap<String, HashSet<String>> = HashMap::new();
+        for access in find_member_accesses(hook.hook_function, text) {
+            if access.base != *param_name {
+                continue;
+            }
+            let Some(deref) = access.member_node.parent() else {
+                continue;
+            };
+            if let Some(eq) = deref.prev_sibling()
+                && eq.kind() == "="
+                && let Some(local) = eq.prev_sibling()
+                && local.kind() == "identifier"
+                && deref
+                    .parent()
+                    .is_some_and(|p| matches!(p.kind(), "local_declaration" | "var_statement"))
+            {
+                originals
+                    .entry(access.member_name)
+                    .or_default()
+                    .insert(get_node_text(local, text).to_string());
+            }
+        }
+
+        for (member, function) in method_replacements(hook.hook_function, param_name, text) {
+            let name = get_node_text(member, text);
+            if !workspace.has_member(&hook.target_path, name)
+                || allowlist.iter().any(|pattern| name_matches(pattern, name))
+            {
+                continue;
+            }
+            let captured = originals.get(name);
+            let calls_original = contains_identifier(function, text, &|ident| {
+                ident == "__original" || captured.is_some_and(|locals| locals.contains(ident))
+            });
+            if calls_original {
+                continue;
+            }
+            diagnostics.push(Diagnostic {
+                range: Range::new(
+                    helpers::position_at(text, member.start_byte()),
+                    helpers::position_at(text, member.end_byte()),
+                ),
+                severity: Some(DiagnosticSeverity::HINT),
+                source: Some("squirrel-bb-hook".to_string()),
+                message: format!(
+                    "'{name}' replaces the original method without calling it, which breaks other mods hooking it"
+                ),
+                ..helpers::coded_diagnostic("unwrapped-override")
+            });
+        }
+    }
+
+    diagnostics
+}
+
+/// `param.name = function ...` assignments under `node`, as the member name
+/// node and the assigned function.
+fn method_replacements<'tree>(
+    node: Node<'tree>,
+    param_name: &str,
+    text: &str,
+) -> Vec<(Node<'tree>, Node<'tree>)> {
+    let mut results = Vec::new();
+    if node.kind() == "assignment_expression"
+        && node.children(&mut node.walk()).any(|c| c.kind() == "=")
+        && let Some(left) = node.child_by_field_name("left")
+        && left.kind() == "deref_expression"
+        && left.named_child_count() == 2
+        && let (Some(base), Some(member)) = (left.named_child(0), left.named_child(1))
+        && get_node_text(base, text) == param_name
+        && member.kind() == "identifier"
+        && let Some(right) = node.child_by_field_name("right")
+        && matches!(right.kind(), "anonymous_function" | "lambda_expression")
+    {
+        results.push((member, right));
+    }
+
+    for child in node.children(&mut node.walk()) {
+        results.extend(method_replacements(child, param_name, text));
+    }
+    results
+}
+
+fn contains_identifier(node: Node, text: &str, matches: &dyn Fn(&str) -> bool) -> bool {
+    if node.kind() == "identifier" && matches(get_node_text(node, text)) {
+        return true;
+    }
+    (0..node.child_count())
+        .filter_map(|idx| node.child(idx))
+        .any(|child| contains_identifier(child, text, matches))
+}
+
 /// Returns a range covering only the first line of the node
 fn first_line_range(node: Node, text: &str) -> Range {
     let start_byte = node.start_byte();
@@ -418,6 +676,15 @@ pub fn analyze_inheritance(
     workspace: &Workspace,
 ) -> Result<Vec<Diagnostic>, AnalysisError> {
     let tree = helpers::parse_squirrel(text)?;
+    Ok(analyze_inheritance_with_tree(&tree, text, workspace))
+}
+
+/// Like `analyze_inheritance`, but reuses an already parsed tree.
+pub fn analyze_inheritance_with_tree(
+    tree: &Tree,
+    text: &str,
+    workspace: &Workspace,
+) -> Vec<Diagnostic> {
     let root = tree.root_node();
 
     let mut diagnostics = Vec::new();
@@ -428,7 +695,7 @@ pub fn analyze_inheritance(
         diagnostics.extend(check_circular_inheritance(&inherit_call, workspace, text));
     }
 
-    Ok(diagnostics)
+    diagnostics
 }
 
 fn validate_parent_path(
@@ -443,7 +710,7 @@ fn validate_parent_path(
         .strip_prefix("scripts/")
         .unwrap_or(&inherit.parent_path);
 
-    if workspace.get(lookup_path).is_none() {
+    let Some(parent_entry) = workspace.get_ignoring_case(lookup_path) else {
         let range = Range::new(
             helpers::position_at(text, inherit.parent_path_node.start_byte()),
             helpers::position_at(text, inherit.parent_path_node.end_byte()),
@@ -462,10 +729,36 @@ fn validate_parent_path(
             severity: Some(DiagnosticSeverity::ERROR),
             source: Some("squirrel-inherit".to_string()),
             message,
-            code: Some(tower_lsp::lsp_types::NumberOrString::String(
-                "parent-path-not-found".to_string(),
-            )),
-            ..Diagnostic::default()
+            ..helpers::coded_diagnostic("parent-path-not-found")
+        });
+        return diagnostics;
+    };
+
+    diagnostics.extend(path_case_mismatch(
+        &inherit.parent_path,
+        parent_entry,
+        inherit.parent_path_node,
+        text,
+        "squirrel-inherit",
+    ));
+
+    // A file indexed as a plain global table with no members is most likely
+    // a data table rather than a class
+    if parent_entry.members.is_empty() && parent_entry.parent_path.is_none() {
+        let range = Range::new(
+            helpers::position_at(text, inherit.parent_path_node.start_byte()),
+            helpers::position_at(text, inherit.parent_path_node.end_byte()),
+        );
+
+        diagnostics.push(Diagnostic {
+            range,
+            severity: Some(DiagnosticSeverity::WARNING),
+            source: Some("squirrel-inherit".to_string()),
+            message: format!(
+                "'{}' may not be a class you can inherit from",
+                inherit.parent_path
+            ),
+            ..helpers::coded_diagnostic("parent-not-a-class")
         });
     }
 
@@ -499,10 +792,7 @@ fn check_circular_inheritance(
                 severity: Some(DiagnosticSeverity::ERROR),
                 source: Some("squirrel-inherit".to_string()),
                 message: format!("'{}' cannot inherit from itself", class_name),
-                code: Some(tower_lsp::lsp_types::NumberOrString::String(
-                    "circular-inheritance".to_string(),
-                )),
-                ..Diagnostic::default()
+                ..helpers::coded_diagnostic("circular-inheritance")
             });
         } else if ancestors.iter().any(|a| a.name == *class_name) {
             let range = Range::new(
@@ -518,10 +808,7 @@ fn check_circular_inheritance(
                     "Circular inheritance detected: '{}' appears in its own ancestor chain",
                     class_name
                 ),
-                code: Some(tower_lsp::lsp_types::NumberOrString::String(
-                    "circular-inheritance".to_string(),
-                )),
-                ..Diagnostic::default()
+                ..helpers::coded_diagnostic("circular-inheritance")
             });
         }
     }
@@ -583,6 +870,14 @@ pub fn find_member_accesses<'tree>(root: Node<'tree>, text: &str) -> Vec<MemberA
     results
 }
 
+/// Whether an assignment target like `::Const.ItemSlot` or
+/// `gt.Const.Tactical` lives under the game's global `Const` table.
+pub fn is_const_path(target: &str) -> bool {
+    let target = target.trim_start_matches("::");
+    let target = target.strip_prefix("gt.").unwrap_or(target);
+    target == "Const" || target.starts_with("Const.")
+}
+
 pub fn get_node_text<'a>(node: Node, text: &'a str) -> &'a str {
     node.utf8_text(text.as_bytes()).unwrap_or("")
 }
@@ -675,6 +970,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_unwrapped_override() {
+        let workspace = create_test_workspace();
+        let code = r#"
+            ::mods_hookExactClass("entity/tactical/actor", function(o) {
+                local onDeath = o.onDeath;
+                o.onDeath = function() {
+                    onDeath();
+                };
+                o.setFatigue = function(_f) {};
+                o.onCustom = function() {};
+            });
+        "#;
+        let tree = helpers::parse_squirrel(code).unwrap();
+
+        let diagnostics = analyze_hook_overrides_with_tree(&tree, code, &workspace, &[]);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].range.start.line, 5);
+        assert!(diagnostics[0].message.contains("'setFatigue'"));
+
+        let allowlist = vec!["set*".to_string()];
+        assert!(analyze_hook_overrides_with_tree(&tree, code, &workspace, &allowlist).is_empty());
+    }
+
     #[test]
     fn test_invalid_hook_path() {
         let workspace = create_test_workspace();
@@ -707,6 +1026,28 @@ mod tests {
         assert!(!method_errors.is_empty());
     }
 
+    #[test]
+    fn test_hook_validated_against_bundled_stubs() {
+        let mut workspace = Workspace::new();
+        workspace.load_stubs(BATTLE_BROTHERS_API);
+        workspace.build_inheritance_graph();
+        let code = r#"
+            ::mods_hookExactClass("skills/skill", function(o) {
+                local onUse = o.onUse;
+                local onUsed = o.onUsed;
+            });
+        "#;
+
+        let diagnostics = analyze_hooks(code, &workspace).unwrap();
+        assert!(
+            !diagnostics
+                .iter()
+                .any(|d| d.message.contains("not found") && d.message.contains("Script path"))
+        );
+        // The stubs don't list every method, so none is reported missing
+        assert!(!diagnostics.iter().any(|d| d.message.starts_with("Method")));
+    }
+
     #[test]
     fn test_hook_type_suggestion() {
         let workspace = create_test_workspace();
@@ -756,6 +1097,135 @@ mod tests {
         assert!(diagnostics[0].message.contains("not found"));
     }
 
+    #[test]
+    fn test_parent_path_case_mismatch() {
+        let workspace = create_test_workspace();
+        let code = r#"
+            this.knight <- this.inherit("scripts/entity/Tactical/actor", {
+                function create() {}
+            });
+        "#;
+
+        let diagnostics = analyze_inheritance(code, &workspace).unwrap();
+        assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+        assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::WARNING));
+        assert_eq!(
+            diagnostics[0].message,
+            "Path case mismatch: indexed as 'scripts/entity/tactical/actor'"
+        );
+    }
+
+    #[test]
+    fn test_hook_path_case_mismatch() {
+        let workspace = create_test_workspace();
+        let code = r#"
+            ::mods_hookBaseClass("Entity/tactical/actor", function(o) {});
+        "#;
+
+        let diagnostics = analyze_hooks(code, &workspace).unwrap();
+        assert!(
+            diagnostics
+                .iter()
+                .any(|d| d.message == "Path case mismatch: indexed as 'entity/tactical/actor'")
+        );
+        assert!(
+            diagnostics
+                .iter()
+                .all(|d| d.severity != Some(DiagnosticSeverity::ERROR))
+        );
+    }
+
+    #[test]
+    fn test_unused_hook_parameter() {
+        let workspace = create_test_workspace();
+        let code = r#"
+            ::mods_hookExactClass("entity/tactical/actor", function(o) {
+                local o = {};
+                o.onDeath <- null;
+            });
+            ::mods_hookExactClass("entity/tactical/human", function(o) {
+                o.onTurnStart = function() {};
+            });
+        "#;
+
+        let diagnostics = analyze_hooks(code, &workspace).unwrap();
+        let unused: Vec<_> = diagnostics
+            .iter()
+            .filter(|d| d.message.contains("never used"))
+            .collect();
+        assert_eq!(unused.len(), 1);
+        assert_eq!(unused[0].range.start.line, 1);
+        assert_eq!(unused[0].severity, Some(DiagnosticSeverity::HINT));
+
+        // The resolver leaves hook parameters to the hook analysis
+        let semantic =
+            crate::symbol_resolver::compute_symbol_diagnostics("test.nut", code).unwrap();
+        assert!(semantic.iter().all(|d| d.message != "Unused variable 'o'"));
+    }
+
+    #[test]
+    fn test_unknown_m_field_in_hook() {
+        let mut workspace = create_test_workspace();
+        let skill_code = r#"
+            skill <- {
+                m = { ID = "", Name = "" },
+                function create() {}
+            };
+        "#;
+        workspace
+            .index_file(Path::new("/test/scripts/skills/skill.nut"), skill_code)
+            .unwrap();
+        workspace.build_inheritance_graph();
+
+        let code = r#"
+            ::mods_hookExactClass("skills/skill", function(o) {
+                o.m.Name = o.m.ID + o.m.Nmae;
+                o.m.Cooldown <- 0;
+            });
+            ::mods_hookExactClass("entity/tactical/actor", function(o) {
+                o.m.Anything = 1;
+            });
+        "#;
+
+        let diagnostics = analyze_hooks(code, &workspace).unwrap();
+        let unknown: Vec<_> = diagnostics
+            .iter()
+            .filter(|d| d.message.contains("not found in 'm'"))
+            .collect();
+        assert_eq!(unknown.len(), 1, "Got: {:?}", diagnostics);
+        assert!(unknown[0].message.contains("'Nmae'"));
+        assert_eq!(unknown[0].severity, Some(DiagnosticSeverity::WARNING));
+    }
+
+    #[test]
+    fn test_unknown_m_field_needs_resolved_ancestry() {
+        let mut workspace = Workspace::new();
+        let slash_code = r#"
+            this.slash <- this.inherit("scripts/skills/skill", {
+                m = { Damage = 10 },
+                function create() {}
+            });
+        "#;
+        workspace
+            .index_file(Path::new("/test/scripts/skills/slash.nut"), slash_code)
+            .unwrap();
+        workspace.build_inheritance_graph();
+
+        let code = r#"
+            ::mods_hookExactClass("skills/slash", function(o) {
+                o.m.Damage = o.m.ID;
+            });
+        "#;
+        let diagnostics = analyze_hooks(code, &workspace).unwrap();
+        assert!(
+            !diagnostics
+                .iter()
+                .any(|d| d.message.contains("not found in 'm'")),
+            "Got: {:?}",
+            diagnostics
+        );
+    }
+
     #[test]
     fn test_inherit_from_self() {
         let mut workspace = Workspace::new();
@@ -782,4 +1252,31 @@ mod tests {
                 .contains("cannot inherit from itself")
         );
     }
+
+    #[test]
+    fn test_inherit_from_data_table() {
+        let mut workspace = Workspace::new();
+        let data_code = r#"
+            names <- {};
+        "#;
+        workspace
+            .index_file(Path::new("/test/scripts/data/names.nut"), data_code)
+            .unwrap();
+        workspace.build_inheritance_graph();
+
+        let code = r#"
+            this.knight <- this.inherit("scripts/data/names", {
+                function create() {}
+            });
+        "#;
+
+        let diagnostics = analyze_inheritance(code, &workspace).unwrap();
+        assert_eq!(diagnostics.len(), 1, "Got: {:?}", diagnostics);
+        assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::WARNING));
+        assert!(
+            diagnostics[0]
+                .message
+                .contains("may not be a class you can inherit from")
+        );
+    }
 }
@@ -4,7 +4,7 @@ use std::io::{self, Read, Write};
 use std::path::PathBuf;
 use std::process;
 
-use squirrel_lsp::formatter::{FormatOptions, IndentStyle, format_document};
+use squirrel_lsp::formatter::{FormatOptions, IndentStyle, LineEnding, format_document};
 
 fn main() {
     if let Err(err) = run() {
@@ -33,6 +33,8 @@ fn run() -> Result<(), Box<dyn std::error::Error>> {
             },
             "--no-final-newline" => options.insert_final_newline = false,
             "--keep-trailing-whitespace" => options.trim_trailing_whitespace = false,
+            "--lf" => options.line_ending = LineEnding::Lf,
+            "--crlf" => options.line_ending = LineEnding::Crlf,
             "--max-width" => {
                 let width = args
                     .next()
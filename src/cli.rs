@@ -0,0 +1,329 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity};
+
+use crate::config::ServerConfig;
+use crate::formatter::{FormatOptions, IndentStyle, format_document};
+use crate::workspace::Workspace;
+
+const FORMAT_USAGE: &str =
+    "Usage: squirrel-lsp format [--check] [--spaces <n>] <file.nut|directory>...";
+const CHECK_USAGE: &str = "Usage: squirrel-lsp check [--format text|json] <file.nut|directory>...";
+
+/// Run the `format` subcommand and return the process exit code.
+///
+/// Files are rewritten in place unless `--check` is given, in which case
+/// nothing is written and the exit code is 1 when any file would change.
+pub fn run_format(args: impl IntoIterator<Item = String>) -> i32 {
+    let mut options = FormatOptions::default();
+    let mut check = false;
+    let mut paths = Vec::new();
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--check" => check = true,
+            "--tabs" => options.indent_style = IndentStyle::Tabs,
+            "--spaces" => match args.next().and_then(|v| v.parse().ok()) {
+                Some(width) if width > 0 => options.indent_style = IndentStyle::Spaces(width),
+                _ => {
+                    eprintln!("Error: --spaces expects a number greater than 0");
+                    return 2;
+                },
+            },
+            other if other.starts_with("--") => {
+                eprintln!("Error: unexpected argument '{other}'");
+                eprintln!("{FORMAT_USAGE}");
+                return 2;
+            },
+            other => paths.push(PathBuf::from(other)),
+        }
+    }
+
+    if paths.is_empty() {
+        eprintln!("{FORMAT_USAGE}");
+        return 2;
+    }
+
+    let mut files = Vec::new();
+    for path in &paths {
+        collect_nut_files(path, &mut files);
+    }
+    files.sort();
+
+    let mut unformatted = 0;
+    let mut failures = 0;
+    for file in &files {
+        let source = match fs::read_to_string(file) {
+            Ok(source) => source,
+            Err(err) => {
+                eprintln!("{}: {err}", file.display());
+                failures += 1;
+                continue;
+            },
+        };
+        let formatted = match format_document(&source, &options) {
+            Ok(formatted) => formatted,
+            Err(err) => {
+                eprintln!("{}: {err}", file.display());
+                failures += 1;
+                continue;
+            },
+        };
+        if formatted == source {
+            continue;
+        }
+
+        unformatted += 1;
+        if check {
+            println!(
+                "{}: would reformat ({})",
+                file.display(),
+                summarize_changes(&source, &formatted)
+            );
+        } else if let Err(err) = fs::write(file, formatted) {
+            eprintln!("{}: {err}", file.display());
+            failures += 1;
+        } else {
+            println!("formatted {}", file.display());
+        }
+    }
+
+    if check && unformatted > 0 {
+        println!(
+            "\n{unformatted} of {} files would be reformatted",
+            files.len()
+        );
+        return 1;
+    }
+
+    i32::from(failures > 0)
+}
+
+/// Run the `check` subcommand and return the process exit code.
+///
+/// All given files are indexed together first so inheritance and hook
+/// checks see the same workspace the server would. The exit code is 1 when
+/// any error-level diagnostic is reported.
+pub fn run_check(args: impl IntoIterator<Item = String>) -> i32 {
+    let mut json = false;
+    let mut paths = Vec::new();
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => match args.next().as_deref() {
+                Some("text") => json = false,
+                Some("json") => json = true,
+                _ => {
+                    eprintln!("Error: --format expects 'text' or 'json'");
+                    return 2;
+                },
+            },
+            other if other.starts_with("--") => {
+                eprintln!("Error: unexpected argument '{other}'");
+                eprintln!("{CHECK_USAGE}");
+                return 2;
+            },
+            other => paths.push(PathBuf::from(other)),
+        }
+    }
+
+    if paths.is_empty() {
+        eprintln!("{CHECK_USAGE}");
+        return 2;
+    }
+
+    let mut files = Vec::new();
+    for path in &paths {
+        collect_nut_files(path, &mut files);
+    }
+    files.sort();
+
+    let mut sources = Vec::new();
+    let mut workspace = Workspace::new();
+    let config = ServerConfig::default();
+    for name in config.known_globals() {
+        workspace.register_global(name);
+    }
+    workspace.load_stubs(config.game.api_stubs());
+    for file in files {
+        match fs::read_to_string(&file) {
+            Ok(source) => {
+                if let Err(err) = workspace.index_file(&file, &source) {
+                    eprintln!("{}: failed to index: {err}", file.display());
+                }
+                sources.push((file, source));
+            },
+            Err(err) => eprintln!("{}: {err}", file.display()),
+        }
+    }
+    workspace.build_inheritance_graph();
+
+    let mut errors = 0;
+    let mut warnings = 0;
+    let mut reports = Vec::new();
+    for (file, source) in &sources {
+        let diagnostics = file_diagnostics(file, source, &workspace);
+        for diag in &diagnostics {
+            match diag.severity {
+                Some(DiagnosticSeverity::ERROR) | None => errors += 1,
+                Some(DiagnosticSeverity::WARNING) => warnings += 1,
+                _ => {},
+            }
+        }
+
+        if json {
+            if !diagnostics.is_empty() {
+                reports.push(serde_json::json!({
+                    "path": file.display().to_string(),
+                    "diagnostics": diagnostics,
+                }));
+            }
+        } else {
+            for diag in &diagnostics {
+                if let Some(line) = format_diagnostic(file, diag) {
+                    println!("{line}");
+                }
+            }
+        }
+    }
+
+    if json {
+        match serde_json::to_string_pretty(&reports) {
+            Ok(output) => println!("{output}"),
+            Err(err) => {
+                eprintln!("Error: failed to serialize diagnostics: {err}");
+                return 2;
+            },
+        }
+    } else {
+        println!(
+            "\nChecked {} files: {errors} errors, {warnings} warnings",
+            sources.len()
+        );
+    }
+
+    i32::from(errors > 0)
+}
+
+/// Run the same analyses the server publishes for an open document.
+fn file_diagnostics(path: &Path, text: &str, workspace: &Workspace) -> Vec<Diagnostic> {
+    let (mut diags, errors) = crate::analyze_document(&path.to_string_lossy(), text, workspace);
+    for err in errors {
+        eprintln!("Error: {}: {err}", path.display());
+    }
+    diags.sort_by_key(|d| (d.range.start.line, d.range.start.character));
+    diags
+}
+
+/// Render a diagnostic as `file:line:col: severity: message`, skipping hints.
+fn format_diagnostic(path: &Path, diag: &Diagnostic) -> Option<String> {
+    let severity = match diag.severity {
+        Some(DiagnosticSeverity::ERROR) | None => "error",
+        Some(DiagnosticSeverity::WARNING) => "warning",
+        Some(DiagnosticSeverity::INFORMATION) => "info",
+        _ => return None,
+    };
+    Some(format!(
+        "{}:{}:{}: {severity}: {}",
+        path.display(),
+        diag.range.start.line + 1,
+        diag.range.start.character + 1,
+        diag.message
+    ))
+}
+
+/// Recursively gather `.nut` files under `path`.
+pub fn collect_nut_files(path: &Path, files: &mut Vec<PathBuf>) {
+    if path.is_file() && path.extension().is_some_and(|e| e == "nut") {
+        files.push(path.to_path_buf());
+    } else if path.is_dir()
+        && let Ok(entries) = fs::read_dir(path)
+    {
+        for entry in entries.flatten() {
+            collect_nut_files(&entry.path(), files);
+        }
+    }
+}
+
+/// Describe how far `formatted` strays from `original`, line by line.
+fn summarize_changes(original: &str, formatted: &str) -> String {
+    let before: Vec<&str> = original.lines().collect();
+    let after: Vec<&str> = formatted.lines().collect();
+    let total = before.len().max(after.len());
+
+    let differing: Vec<usize> = (0..total)
+        .filter(|&i| before.get(i) != after.get(i))
+        .collect();
+
+    match differing.first() {
+        Some(first) => {
+            let plural = if differing.len() == 1 { "" } else { "s" };
+            format!(
+                "{} changed line{plural}, first at line {}",
+                differing.len(),
+                first + 1
+            )
+        },
+        // Only line endings or the trailing newline changed
+        None => "whitespace at end of file".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tower_lsp::lsp_types::{Position, Range};
+
+    #[test]
+    fn test_summarize_changes_reports_first_line() {
+        let summary =
+            summarize_changes("local a=1;\nlocal b = 2;\n", "local a = 1;\nlocal b = 2;\n");
+        assert_eq!(summary, "1 changed line, first at line 1");
+
+        let summary = summarize_changes("a;\nb;\nc ;\nd ;\n", "a;\nb;\nc;\nd;\n");
+        assert_eq!(summary, "2 changed lines, first at line 3");
+    }
+
+    #[test]
+    fn test_summarize_changes_final_newline_only() {
+        assert_eq!(
+            summarize_changes("local a = 1;", "local a = 1;\n"),
+            "whitespace at end of file"
+        );
+    }
+
+    #[test]
+    fn test_format_diagnostic_line() {
+        let diag = Diagnostic {
+            range: Range::new(Position::new(4, 2), Position::new(4, 7)),
+            severity: Some(DiagnosticSeverity::WARNING),
+            message: "Unused variable 'x'".to_string(),
+            ..Diagnostic::default()
+        };
+        assert_eq!(
+            format_diagnostic(Path::new("scripts/a.nut"), &diag).as_deref(),
+            Some("scripts/a.nut:5:3: warning: Unused variable 'x'")
+        );
+
+        let hint = Diagnostic {
+            severity: Some(DiagnosticSeverity::HINT),
+            ..diag
+        };
+        assert_eq!(format_diagnostic(Path::new("scripts/a.nut"), &hint), None);
+    }
+
+    #[test]
+    fn test_check_rejects_unknown_format() {
+        let args = ["--format", "xml", "scripts"].map(String::from);
+        assert_eq!(run_check(args), 2);
+    }
+
+    #[test]
+    fn test_format_requires_paths() {
+        assert_eq!(run_format(Vec::new()), 2);
+        assert_eq!(run_format(vec!["--bogus".to_string()]), 2);
+    }
+}
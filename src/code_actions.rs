@@ -2,6 +2,7 @@ use std::collections::HashMap;
 use tower_lsp::lsp_types::{
     CodeAction, CodeActionKind, Diagnostic, Range, TextEdit, Url, WorkspaceEdit,
 };
+use tree_sitter::{Node, Tree};
 
 use crate::helpers;
 
@@ -82,6 +83,53 @@ fn create_delete_unused_variable_edit(
     })
 }
 
+/// Create a WorkspaceEdit replacing the `=` that follows the member name with `<-`
+fn create_new_slot_edit(text: &str, range: Range, uri: &Url) -> Option<WorkspaceEdit> {
+    let member_end = helpers::byte_offset_at(text, range.end)?;
+    let rest = text.get(member_end..)?;
+    let operator_offset = member_end + (rest.len() - rest.trim_start().len());
+
+    if !text[operator_offset..].starts_with('=') || text[operator_offset..].starts_with("==") {
+        return None;
+    }
+
+    let operator_range = Range::new(
+        helpers::position_at(text, operator_offset),
+        helpers::position_at(text, operator_offset + 1),
+    );
+
+    let mut changes = HashMap::new();
+    changes.insert(
+        uri.clone(),
+        vec![TextEdit {
+            range: operator_range,
+            new_text: "<-".to_string(),
+        }],
+    );
+
+    Some(WorkspaceEdit {
+        changes: Some(changes),
+        ..Default::default()
+    })
+}
+
+/// Create a WorkspaceEdit replacing the diagnostic range with `replacement`
+fn create_rename_edit(range: Range, replacement: &str, uri: &Url) -> WorkspaceEdit {
+    let mut changes = HashMap::new();
+    changes.insert(
+        uri.clone(),
+        vec![TextEdit {
+            range,
+            new_text: replacement.to_string(),
+        }],
+    );
+
+    WorkspaceEdit {
+        changes: Some(changes),
+        ..Default::default()
+    }
+}
+
 /// Generate code actions for the given diagnostics
 pub fn generate_code_actions(text: &str, diagnostics: &[Diagnostic], uri: &Url) -> Vec<CodeAction> {
     let mut actions = Vec::new();
@@ -107,11 +155,345 @@ pub fn generate_code_actions(text: &str, diagnostics: &[Diagnostic], uri: &Url)
                 }
             }
         }
+
+        if diagnostic.source.as_deref() == Some("squirrel-semantic")
+            && diagnostic
+                .message
+                .starts_with("Assignment to unknown member")
+            && let Some(member_name) = extract_variable_name(text, diagnostic.range)
+            && let Some(edit) = create_new_slot_edit(text, diagnostic.range, uri)
+        {
+            actions.push(CodeAction {
+                title: format!("Use '<-' to create slot '{}'", member_name),
+                kind: Some(CodeActionKind::QUICKFIX),
+                edit: Some(edit),
+                diagnostics: Some(vec![diagnostic.clone()]),
+                is_preferred: Some(true),
+                ..Default::default()
+            });
+        }
+
+        if diagnostic.source.as_deref() == Some("squirrel-semantic")
+            && diagnostic.message.starts_with("Undeclared variable")
+            && let Some(suggestions) = diagnostic
+                .data
+                .as_ref()
+                .and_then(|data| data.get("suggestions"))
+                .and_then(|s| s.as_array())
+        {
+            for (i, suggestion) in suggestions.iter().filter_map(|s| s.as_str()).enumerate() {
+                actions.push(CodeAction {
+                    title: format!("Change to '{}'", suggestion),
+                    kind: Some(CodeActionKind::QUICKFIX),
+                    edit: Some(create_rename_edit(diagnostic.range, suggestion, uri)),
+                    diagnostics: Some(vec![diagnostic.clone()]),
+                    is_preferred: Some(i == 0),
+                    ..Default::default()
+                });
+            }
+        }
     }
 
     actions
 }
 
+/// Generate refactoring actions for the selected range. `functions_separately`
+/// makes "Sort members" put data slots before functions.
+pub fn generate_refactor_actions(
+    tree: &Tree,
+    text: &str,
+    range: Range,
+    uri: &Url,
+    functions_separately: bool,
+) -> Vec<CodeAction> {
+    let Some(offset) = helpers::byte_offset_at(text, range.start) else {
+        return Vec::new();
+    };
+    let root = tree.root_node();
+
+    let toggle_braces = find_enclosing_if(root, offset)
+        .and_then(|if_node| create_toggle_braces_edit(if_node, text));
+    let sort_members = find_enclosing_body(root, offset)
+        .and_then(|body| create_sort_members_edit(body, text, functions_separately))
+        .map(|edit| ("Sort members alphabetically", edit));
+
+    toggle_braces
+        .into_iter()
+        .chain(sort_members)
+        .map(|(title, edit)| {
+            let mut changes = HashMap::new();
+            changes.insert(uri.clone(), vec![edit]);
+
+            CodeAction {
+                title: title.to_string(),
+                kind: Some(CodeActionKind::REFACTOR_REWRITE),
+                edit: Some(WorkspaceEdit {
+                    changes: Some(changes),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+/// Find the innermost `if` statement containing the byte offset
+fn find_enclosing_if(root: Node, offset: usize) -> Option<Node> {
+    let mut node = root.descendant_for_byte_range(offset, offset)?;
+    loop {
+        if node.kind() == "if_statement" {
+            return Some(node);
+        }
+        node = node.parent()?;
+    }
+}
+
+/// Create the edit that adds braces around a single-statement `if` body, or
+/// removes them from a block that holds exactly one statement
+fn create_toggle_braces_edit(if_node: Node, text: &str) -> Option<(&'static str, TextEdit)> {
+    let mut children = if_node
+        .children(&mut if_node.walk())
+        .collect::<Vec<_>>()
+        .into_iter();
+    let close_paren = children.by_ref().find(|c| c.kind() == ")")?;
+    let body = children.by_ref().find(|c| c.is_named())?;
+    let has_else = children.any(|c| c.kind() == "else" || c.kind() == "else_statement");
+
+    let line_start = text[..if_node.start_byte()]
+        .rfind('\n')
+        .map_or(0, |i| i + 1);
+    let indent: String = text[line_start..]
+        .chars()
+        .take_while(|c| *c == ' ' || *c == '\t')
+        .collect();
+    let indent_unit = if indent.contains('\t') { "\t" } else { "    " };
+
+    let (title, new_text) = if body.kind() == "block" {
+        let statements: Vec<Node> = body
+            .children(&mut body.walk())
+            .filter(|c| c.is_named())
+            .collect();
+        let [statement] = statements.as_slice() else {
+            return None;
+        };
+        // Unbracing a declaration would leak it into the enclosing scope, and
+        // unbracing an inner if would capture our else branch
+        if matches!(
+            statement.kind(),
+            "local_declaration" | "var_statement" | "const_declaration"
+        ) || (has_else && statement.kind() == "if_statement")
+        {
+            return None;
+        }
+        let new_text = format!(
+            "\n{}{}{}",
+            indent,
+            indent_unit,
+            helpers::node_text(*statement, text)
+        );
+        ("Remove braces", new_text)
+    } else {
+        let new_text = format!(
+            " {{\n{}{}{}\n{}}}",
+            indent,
+            indent_unit,
+            helpers::node_text(body, text),
+            indent
+        );
+        ("Add braces", new_text)
+    };
+
+    let edit = TextEdit {
+        range: Range::new(
+            helpers::position_at(text, close_paren.end_byte()),
+            helpers::position_at(text, body.end_byte()),
+        ),
+        new_text,
+    };
+    Some((title, edit))
+}
+
+/// Find the innermost table or class body containing the byte offset
+fn find_enclosing_body(root: Node, offset: usize) -> Option<Node> {
+    let mut node = root.descendant_for_byte_range(offset, offset)?;
+    loop {
+        if matches!(node.kind(), "table" | "class_body") {
+            return Some(node);
+        }
+        node = node.parent()?;
+    }
+}
+
+/// A table slot or class member being sorted, as byte offsets into the
+/// source. Comments directly above the member and a comment trailing it on
+/// the same line move with it; the `,` or `;` after it stays in place so
+/// the separators remain valid wherever the member ends up.
+struct SortEntry {
+    key: String,
+    is_function: bool,
+    /// Start of the leading comments, or of the member itself
+    start: usize,
+    /// End of the separator and trailing comment after the member, which
+    /// move along with it
+    end: usize,
+}
+
+/// Create the edit that sorts the members of a table or class body by name,
+/// within each run of members not separated by a blank line or a detached
+/// comment. Returns `None` when the members are already in order.
+fn create_sort_members_edit(
+    body: Node,
+    text: &str,
+    functions_separately: bool,
+) -> Option<TextEdit> {
+    // Some grammar versions wrap the slots of a table in `table_slots`
+    let container = body
+        .children(&mut body.walk())
+        .find(|c| c.kind() == "table_slots")
+        .unwrap_or(body);
+    if container.has_error() {
+        return None;
+    }
+    let children: Vec<Node> = container
+        .children(&mut container.walk())
+        .filter(|c| !matches!(c.kind(), "{" | "}"))
+        .collect();
+
+    let mut entries: Vec<SortEntry> = Vec::new();
+    // Start of the run of own-line comments right above the next member
+    let mut leading_comments: Option<usize> = None;
+    let mut previous_end = container.start_byte();
+    let mut previous_end_row = None;
+    let mut idx = 0;
+    while idx < children.len() {
+        let child = children[idx];
+        idx += 1;
+        let attached = !has_blank_line(&text[previous_end..child.start_byte()]);
+        let starts_own_line = previous_end_row != Some(child.start_position().row);
+        previous_end = child.end_byte();
+        previous_end_row = Some(child.end_position().row);
+
+        if child.kind() == "comment" {
+            leading_comments = match leading_comments {
+                Some(start) if attached => Some(start),
+                _ if starts_own_line => Some(child.start_byte()),
+                _ => None,
+            };
+            continue;
+        }
+        let leading = leading_comments.take();
+        if !child.is_named() {
+            // A separator that doesn't follow a member
+            continue;
+        }
+
+        let start = match leading {
+            Some(comment_start) if attached => comment_start,
+            _ => child.start_byte(),
+        };
+        let mut end = child.end_byte();
+        if let Some(next) = children.get(idx)
+            && matches!(next.kind(), "," | ";")
+        {
+            end = next.end_byte();
+            previous_end = end;
+            idx += 1;
+        }
+        if let Some(next) = children.get(idx)
+            && next.kind() == "comment"
+            && next.start_position().row == child.end_position().row
+        {
+            end = next.end_byte();
+            previous_end = end;
+            idx += 1;
+        }
+        entries.push(SortEntry {
+            key: member_sort_key(child, text),
+            is_function: is_function_member(child),
+            start,
+            end,
+        });
+    }
+    if entries.len() < 2 {
+        return None;
+    }
+
+    // Sort each group in place, where groups are split by blank lines or
+    // comments that don't belong to a member
+    let mut order: Vec<usize> = (0..entries.len()).collect();
+    let mut group_start = 0;
+    for boundary in 1..=entries.len() {
+        let splits = boundary == entries.len() || {
+            let gap = &text[entries[boundary - 1].end..entries[boundary].start];
+            has_blank_line(gap) || !gap.trim().is_empty()
+        };
+        if splits {
+            order[group_start..boundary].sort_by_cached_key(|&i| {
+                let entry = &entries[i];
+                (
+                    functions_separately && entry.is_function,
+                    entry.key.to_lowercase(),
+                    entry.key.clone(),
+                )
+            });
+            group_start = boundary;
+        }
+    }
+    if order.iter().enumerate().all(|(position, &i)| position == i) {
+        return None;
+    }
+
+    let mut new_text = String::new();
+    for (position, &i) in order.iter().enumerate() {
+        if position > 0 {
+            new_text.push_str(&text[entries[position - 1].end..entries[position].start]);
+        }
+        new_text.push_str(&text[entries[i].start..entries[i].end]);
+    }
+
+    let first = entries.first()?;
+    let last = entries.last()?;
+    Some(TextEdit {
+        range: Range::new(
+            helpers::position_at(text, first.start),
+            helpers::position_at(text, last.end),
+        ),
+        new_text,
+    })
+}
+
+fn has_blank_line(gap: &str) -> bool {
+    gap.matches('\n').count() >= 2
+}
+
+/// The name a member sorts by: its key or function name
+fn member_sort_key(member: Node, text: &str) -> String {
+    let name = match member.kind() {
+        "function_declaration" => member.child_by_field_name("name").or_else(|| {
+            member
+                .children(&mut member.walk())
+                .find(|c| c.kind() == "identifier")
+        }),
+        _ => member.children(&mut member.walk()).find(|c| c.is_named()),
+    };
+    match name {
+        Some(name) if name.kind() == "identifier" => helpers::node_text(name, text).to_string(),
+        Some(name) if name.kind() == "string" => helpers::extract_string_content(name, text),
+        Some(name) if name.kind() == "function_declaration" => member_sort_key(name, text),
+        _ => helpers::node_text(member, text).to_string(),
+    }
+}
+
+fn is_function_member(member: Node) -> bool {
+    member.kind() == "function_declaration"
+        || member.children(&mut member.walk()).any(|c| {
+            matches!(
+                c.kind(),
+                "function_declaration" | "lambda_expression" | "anonymous_function"
+            )
+        })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -186,4 +568,148 @@ mod tests {
             "Variable name should be extracted from source text, not message"
         );
     }
+
+    #[test]
+    fn test_new_slot_code_action() {
+        let code = "this.Target = null;";
+        let uri = Url::parse("file:///test.nut").unwrap();
+        let diagnostics = vec![Diagnostic {
+            range: Range::new(Position::new(0, 5), Position::new(0, 11)),
+            severity: Some(DiagnosticSeverity::WARNING),
+            source: Some("squirrel-semantic".to_string()),
+            message: "Assignment to unknown member 'Target'. Use '<-' to create a new slot"
+                .to_string(),
+            ..Default::default()
+        }];
+
+        let actions = generate_code_actions(code, &diagnostics, &uri);
+
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0].title, "Use '<-' to create slot 'Target'");
+        let changes = actions[0].edit.as_ref().unwrap().changes.as_ref().unwrap();
+        let edits = &changes[&uri];
+        assert_eq!(edits[0].new_text, "<-");
+        assert_eq!(
+            edits[0].range,
+            Range::new(Position::new(0, 12), Position::new(0, 13))
+        );
+    }
+
+    #[test]
+    fn test_change_to_similar_name_code_action() {
+        let code = "return countr;";
+        let uri = Url::parse("file:///test.nut").unwrap();
+        let diagnostics = vec![Diagnostic {
+            range: Range::new(Position::new(0, 7), Position::new(0, 13)),
+            severity: Some(DiagnosticSeverity::ERROR),
+            source: Some("squirrel-semantic".to_string()),
+            message: "Undeclared variable 'countr'".to_string(),
+            data: Some(serde_json::json!({ "suggestions": ["counter", "count"] })),
+            ..Default::default()
+        }];
+
+        let actions = generate_code_actions(code, &diagnostics, &uri);
+
+        let titles: Vec<_> = actions.iter().map(|a| a.title.as_str()).collect();
+        assert_eq!(titles, ["Change to 'counter'", "Change to 'count'"]);
+        assert_eq!(actions[0].is_preferred, Some(true));
+        let changes = actions[0].edit.as_ref().unwrap().changes.as_ref().unwrap();
+        assert_eq!(changes[&uri][0].new_text, "counter");
+        assert_eq!(changes[&uri][0].range, diagnostics[0].range);
+    }
+
+    fn apply_refactor(code: &str, position: Position) -> Vec<(String, String)> {
+        apply_refactor_with(code, position, false)
+    }
+
+    fn apply_refactor_with(
+        code: &str,
+        position: Position,
+        functions_separately: bool,
+    ) -> Vec<(String, String)> {
+        let uri = Url::parse("file:///test.nut").unwrap();
+        let tree = helpers::parse_squirrel(code).unwrap();
+        generate_refactor_actions(
+            &tree,
+            code,
+            Range::new(position, position),
+            &uri,
+            functions_separately,
+        )
+        .into_iter()
+        .map(|action| {
+            let edit = &action.edit.unwrap().changes.unwrap()[&uri][0];
+            let start = helpers::byte_offset_at(code, edit.range.start).unwrap();
+            let end = helpers::byte_offset_at(code, edit.range.end).unwrap();
+            let result = format!("{}{}{}", &code[..start], edit.new_text, &code[end..]);
+            (action.title, result)
+        })
+        .collect()
+    }
+
+    #[test]
+    fn test_add_braces() {
+        let code = "function f(x) {\n    if (x)\n        return 1;\n    return 0;\n}\n";
+        let actions = apply_refactor(code, Position::new(1, 5));
+
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0].0, "Add braces");
+        assert_eq!(
+            actions[0].1,
+            "function f(x) {\n    if (x) {\n        return 1;\n    }\n    return 0;\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_remove_braces() {
+        let code = "function f(x) {\n    if (x) {\n        return 1;\n    }\n    return 0;\n}\n";
+        let actions = apply_refactor(code, Position::new(1, 5));
+
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0].0, "Remove braces");
+        assert_eq!(
+            actions[0].1,
+            "function f(x) {\n    if (x)\n        return 1;\n    return 0;\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_remove_braces_refuses_local_or_multiple_statements() {
+        let with_local = "if (x) {\n    local y = 1;\n}\n";
+        assert!(apply_refactor(with_local, Position::new(0, 1)).is_empty());
+
+        let with_two = "if (x) {\n    a();\n    b();\n}\n";
+        assert!(apply_refactor(with_two, Position::new(0, 1)).is_empty());
+    }
+
+    #[test]
+    fn test_sort_table_members() {
+        let code = "local t = {\n    // Top speed\n    Speed = 3,\n    Armor = 10, // base\n    Name = \"x\",\n\n    Zeta = 1,\n    Alpha = 2\n};\n";
+        let actions = apply_refactor(code, Position::new(2, 5));
+
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0].0, "Sort members alphabetically");
+        assert_eq!(
+            actions[0].1,
+            "local t = {\n    Armor = 10, // base\n    Name = \"x\",\n    // Top speed\n    Speed = 3,\n\n    Alpha = 2\n    Zeta = 1,\n};\n"
+        );
+        // Already sorted: nothing to offer
+        assert!(apply_refactor(&actions[0].1, Position::new(2, 5)).is_empty());
+    }
+
+    #[test]
+    fn test_sort_members_functions_separately() {
+        let code = "this.skill <- this.inherit(\"scripts/skills/skill\", {\n    function onUse() {}\n    m = {},\n    function create() {}\n});\n";
+        let grouped = apply_refactor_with(code, Position::new(2, 5), true);
+        assert_eq!(
+            grouped[0].1,
+            "this.skill <- this.inherit(\"scripts/skills/skill\", {\n    m = {},\n    function create() {}\n    function onUse() {}\n});\n"
+        );
+
+        let mixed = apply_refactor_with(code, Position::new(2, 5), false);
+        assert_eq!(
+            mixed[0].1,
+            "this.skill <- this.inherit(\"scripts/skills/skill\", {\n    function create() {}\n    m = {},\n    function onUse() {}\n});\n"
+        );
+    }
 }
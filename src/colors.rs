@@ -0,0 +1,132 @@
+//! Color swatches for hex color string literals like `"#ff8800"`.
+
+use tower_lsp::lsp_types::{Color, ColorInformation, ColorPresentation, Range, TextEdit};
+use tree_sitter::{Node, Tree};
+
+use crate::helpers;
+
+/// Every string literal whose whole content is `#rrggbb` or `#rrggbbaa`.
+/// The range covers the content, not the quotes.
+pub fn compute_document_colors(tree: &Tree, text: &str) -> Vec<ColorInformation> {
+    let mut colors = Vec::new();
+    collect_colors(tree.root_node(), text, &mut colors);
+    colors
+}
+
+fn collect_colors(node: Node, text: &str, colors: &mut Vec<ColorInformation>) {
+    if node.kind() == "string" {
+        let literal = helpers::node_text(node, text);
+        if literal.len() >= 2
+            && literal.starts_with('"')
+            && literal.ends_with('"')
+            && let Some(color) = parse_hex_color(&literal[1..literal.len() - 1])
+        {
+            colors.push(ColorInformation {
+                range: Range::new(
+                    helpers::position_at(text, node.start_byte() + 1),
+                    helpers::position_at(text, node.end_byte() - 1),
+                ),
+                color,
+            });
+        }
+        return;
+    }
+
+    for child in node.children(&mut node.walk()) {
+        collect_colors(child, text, colors);
+    }
+}
+
+/// Present a picked color as lowercase hex, keeping the alpha channel only
+/// when the color isn't opaque.
+pub fn color_presentations(color: Color, range: Range) -> Vec<ColorPresentation> {
+    let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    let mut label = format!(
+        "#{:02x}{:02x}{:02x}",
+        channel(color.red),
+        channel(color.green),
+        channel(color.blue)
+    );
+    if channel(color.alpha) != 255 {
+        label.push_str(&format!("{:02x}", channel(color.alpha)));
+    }
+
+    vec![ColorPresentation {
+        text_edit: Some(TextEdit::new(range, label.clone())),
+        label,
+        additional_text_edits: None,
+    }]
+}
+
+fn parse_hex_color(content: &str) -> Option<Color> {
+    let hex = content.strip_prefix('#')?;
+    if !matches!(hex.len(), 6 | 8) || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let channel = |i: usize| {
+        hex.get(i..i + 2)
+            .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+            .map(|value| f32::from(value) / 255.0)
+    };
+    Some(Color {
+        red: channel(0)?,
+        green: channel(2)?,
+        blue: channel(4)?,
+        alpha: if hex.len() == 8 { channel(6)? } else { 1.0 },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tower_lsp::lsp_types::Position;
+
+    #[test]
+    fn test_parse_hex_color() {
+        let color = parse_hex_color("#FF8000").unwrap();
+        assert_eq!((color.red, color.blue, color.alpha), (1.0, 0.0, 1.0));
+        assert!((color.green - 128.0 / 255.0).abs() < f32::EPSILON);
+
+        assert_eq!(parse_hex_color("#ff800080").unwrap().alpha, 128.0 / 255.0);
+        assert!(parse_hex_color("#ff80").is_none());
+        assert!(parse_hex_color("ff8000").is_none());
+        assert!(parse_hex_color("#gg8000").is_none());
+    }
+
+    #[test]
+    fn test_document_colors_cover_string_content() {
+        let text = "local c = \"#ff8000\";\nlocal s = \"not #ff8000\";\n";
+        let tree = helpers::parse_squirrel(text).unwrap();
+        let colors = compute_document_colors(&tree, text);
+
+        assert_eq!(colors.len(), 1);
+        assert_eq!(
+            colors[0].range,
+            Range::new(Position::new(0, 11), Position::new(0, 18))
+        );
+    }
+
+    #[test]
+    fn test_color_presentation_normalizes_hex() {
+        let range = Range::new(Position::new(0, 11), Position::new(0, 18));
+        let opaque = Color {
+            red: 1.0,
+            green: 0.5,
+            blue: 0.0,
+            alpha: 1.0,
+        };
+        let presentations = color_presentations(opaque, range);
+        assert_eq!(presentations[0].label, "#ff8000");
+        assert_eq!(presentations[0].text_edit.as_ref().unwrap().range, range);
+
+        let translucent = Color {
+            alpha: 0.5,
+            ..opaque
+        };
+        assert_eq!(
+            color_presentations(translucent, range)[0].label,
+            "#ff800080"
+        );
+    }
+}
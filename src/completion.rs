@@ -0,0 +1,196 @@
+//! Completion support.
+//!
+//! Currently completes script paths inside the first string argument of
+//! `inherit(...)` and `::mods_hook*(...)` calls.
+
+use tower_lsp::lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionTextEdit, Position, Range, TextEdit,
+};
+use tree_sitter::{Node, Tree};
+
+use crate::bb_support::{get_node_text, hook_type_from_name};
+use crate::helpers;
+use crate::navigation::{find_deepest_node_at, is_inside_inherit_call};
+use crate::workspace::Workspace;
+
+/// Complete script paths when the cursor is inside an inherit or hook path string
+pub fn script_path_completions(
+    tree: &Tree,
+    text: &str,
+    position: Position,
+    workspace: &Workspace,
+) -> Vec<CompletionItem> {
+    let Some(offset) = helpers::byte_offset_at(text, position) else {
+        return Vec::new();
+    };
+    let Some(node) = find_deepest_node_at(tree.root_node(), offset) else {
+        return Vec::new();
+    };
+    let Some(string) = enclosing_string(node) else {
+        return Vec::new();
+    };
+    if !is_path_argument(string, text) {
+        return Vec::new();
+    }
+
+    // Skip the opening quote; the cursor must be inside the literal
+    let content_start = string.start_byte() + 1;
+    if offset < content_start || offset >= string.end_byte() {
+        return Vec::new();
+    }
+
+    let typed = &text[content_start..offset];
+    // Both "scripts/entity/actor" and "entity/actor" are accepted paths, so
+    // keep whichever form the user started typing
+    let (prefix, typed_path) = if let Some(rest) = typed.strip_prefix("scripts/") {
+        ("scripts/", rest)
+    } else if !typed.is_empty() && "scripts/".starts_with(typed) {
+        ("scripts/", "")
+    } else {
+        ("", typed)
+    };
+    let replace_range = Range::new(helpers::position_at(text, content_start), position);
+
+    let mut paths: Vec<(&String, &str)> = workspace
+        .files()
+        .iter()
+        .filter(|(path, _)| path.starts_with(typed_path))
+        .map(|(path, entry)| (path, entry.name.as_str()))
+        .collect();
+    paths.sort();
+
+    paths
+        .into_iter()
+        .map(|(path, name)| {
+            let full_path = format!("{}{}", prefix, path);
+            CompletionItem {
+                label: full_path.clone(),
+                kind: Some(CompletionItemKind::FILE),
+                detail: Some(name.to_string()),
+                filter_text: Some(full_path.clone()),
+                text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                    range: replace_range,
+                    new_text: full_path,
+                })),
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+fn enclosing_string(node: Node) -> Option<Node> {
+    let mut current = Some(node);
+    while let Some(n) = current {
+        if n.kind() == "string" {
+            return Some(n);
+        }
+        current = n.parent();
+    }
+    None
+}
+
+/// Whether a string node is the first argument of an inherit or hook call
+fn is_path_argument(string: Node, text: &str) -> bool {
+    let Some(args) = string.parent() else {
+        return false;
+    };
+    if args.kind() != "call_args" || args.named_child(0) != Some(string) {
+        return false;
+    }
+    let Some(call) = args.parent() else {
+        return false;
+    };
+
+    if is_inside_inherit_call(string, text) {
+        return true;
+    }
+
+    call.children(&mut call.walk())
+        .filter(|child| child.kind() == "global_variable")
+        .flat_map(|child| {
+            child
+                .children(&mut child.walk())
+                .filter(|c| c.kind() == "identifier")
+                .collect::<Vec<_>>()
+        })
+        .any(|ident| hook_type_from_name(get_node_text(ident, text)).is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn create_test_workspace() -> Workspace {
+        let mut workspace = Workspace::new();
+        let actor_code = r#"
+            actor <- {
+                function onDeath() {}
+            };
+        "#;
+        workspace
+            .index_file(
+                Path::new("/test/scripts/entity/tactical/actor.nut"),
+                actor_code,
+            )
+            .unwrap();
+        let skill_code = r#"
+            skill <- {
+                function getContainer() {}
+            };
+        "#;
+        workspace
+            .index_file(Path::new("/test/scripts/skills/skill.nut"), skill_code)
+            .unwrap();
+        workspace
+    }
+
+    #[test]
+    fn test_complete_inherit_path() {
+        let workspace = create_test_workspace();
+        let code = r#"this.knight <- this.inherit("scripts/entity/", {});"#;
+        let position = Position::new(0, 44); // After "scripts/entity/"
+
+        let items = script_path_completions(
+            &helpers::parse_squirrel(code).unwrap(),
+            code,
+            position,
+            &workspace,
+        );
+        let labels: Vec<&str> = items.iter().map(|i| i.label.as_str()).collect();
+        assert_eq!(labels, vec!["scripts/entity/tactical/actor"]);
+    }
+
+    #[test]
+    fn test_complete_bare_hook_path() {
+        let workspace = create_test_workspace();
+        let code = r#"::mods_hookExactClass("sk", function(o) {});"#;
+        let position = Position::new(0, 25); // After "sk"
+
+        let items = script_path_completions(
+            &helpers::parse_squirrel(code).unwrap(),
+            code,
+            position,
+            &workspace,
+        );
+        let labels: Vec<&str> = items.iter().map(|i| i.label.as_str()).collect();
+        assert_eq!(labels, vec!["skills/skill"]);
+    }
+
+    #[test]
+    fn test_no_completion_in_other_strings() {
+        let workspace = create_test_workspace();
+        let code = r#"print("scripts/");"#;
+        let position = Position::new(0, 15);
+
+        assert!(
+            script_path_completions(
+                &helpers::parse_squirrel(code).unwrap(),
+                code,
+                position,
+                &workspace
+            )
+            .is_empty()
+        );
+    }
+}
@@ -0,0 +1,658 @@
+use std::path::Path;
+
+use tower_lsp::lsp_types::LSPAny;
+
+use crate::bb_support::BATTLE_BROTHERS_API;
+use crate::formatter::BraceStyle;
+
+/// Language flavour the server should accept.
+///
+/// Quirrel is a Squirrel fork that adds operators such as `?.` (null-safe
+/// member access) and `??` (null coalescing).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Dialect {
+    #[default]
+    Squirrel,
+    Quirrel,
+}
+
+impl Dialect {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "squirrel" => Some(Self::Squirrel),
+            "quirrel" => Some(Self::Quirrel),
+            _ => None,
+        }
+    }
+
+    /// Binary operators the dialect adds to Squirrel's, spaced like `+`. The
+    /// Squirrel grammar splits each into `?` and the character after it.
+    pub fn binary_operators(self) -> &'static [&'static str] {
+        match self {
+            Self::Squirrel => &[],
+            Self::Quirrel => &["??"],
+        }
+    }
+
+    /// Member access operators the dialect adds, written without spaces like
+    /// `.` and split by the grammar the same way.
+    pub fn access_operators(self) -> &'static [&'static str] {
+        match self {
+            Self::Squirrel => &[],
+            Self::Quirrel => &["?."],
+        }
+    }
+
+    /// Whether `op` creates a slot, as in `name <- value`. Both dialects
+    /// share Squirrel's `<-`; Quirrel has no declaration operator of its own.
+    pub fn is_new_slot_operator(self, op: &str) -> bool {
+        match self {
+            Self::Squirrel | Self::Quirrel => op == "<-",
+        }
+    }
+}
+
+/// Game whose engine globals are known without being defined in the
+/// workspace.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Game {
+    #[default]
+    BattleBrothers,
+    /// Plain Squirrel, with no engine globals
+    None,
+}
+
+impl Game {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "battlebrothers" => Some(Self::BattleBrothers),
+            "none" => Some(Self::None),
+            _ => None,
+        }
+    }
+
+    fn globals(self) -> &'static [&'static str] {
+        match self {
+            Self::BattleBrothers => BATTLE_BROTHERS_GLOBALS,
+            Self::None => &[],
+        }
+    }
+
+    /// Manifest of the engine's classes, for `Workspace::load_stubs`
+    pub fn api_stubs(self) -> &'static str {
+        match self {
+            Self::BattleBrothers => BATTLE_BROTHERS_API,
+            Self::None => "",
+        }
+    }
+}
+
+/// When the server runs the full analysis of a document.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DiagnosticsMode {
+    /// On every change
+    #[default]
+    Full,
+    /// Only syntax errors while typing; everything else when the document
+    /// is saved
+    SyntaxOnSave,
+}
+
+/// Size above which files are neither indexed nor analyzed, unless
+/// `maxFileSizeKb` says otherwise
+const DEFAULT_MAX_FILE_SIZE_KB: u64 = 1024;
+
+/// Root table entries the Battle Brothers engine sets up before any script
+/// runs.
+const BATTLE_BROTHERS_GLOBALS: &[&str] = &[
+    "Const", "Cursor", "Music", "Settings", "Sound", "Tactical", "Time", "World",
+];
+
+/// Engine callbacks Battle Brothers invokes by name, never reported as
+/// unreferenced. A trailing `*` matches any suffix.
+const DEFAULT_UNUSED_SYMBOL_ALLOWLIST: &[&str] = &[
+    "on*",
+    "create",
+    "destroy",
+    "constructor",
+    "getTooltip",
+    "getDescription",
+    "getName",
+    "isHidden",
+    "isUsable",
+    "queryModule",
+    "main",
+];
+
+/// Keys `ServerConfig::from_options` reads.
+const OPTION_KEYS: &[&str] = &[
+    "dialect",
+    "game",
+    "globals",
+    "sharedGlobals",
+    "shadowingHints",
+    "recursionHints",
+    "argumentCountChecks",
+    "unusedUnderscoreExempt",
+    "overrideHints",
+    "overrideAllowlist",
+    "diagnostics",
+    "braceStyle",
+    "scriptRoot",
+    "unusedSymbolHints",
+    "unusedSymbolAllowlist",
+    "documentColors",
+    "sortFunctionsSeparately",
+    "requireFunction",
+    "fileExtensions",
+    "maxFileSizeKb",
+    "exclude",
+];
+
+/// Server settings supplied by the client through `initializationOptions`.
+#[derive(Debug, Clone, Default)]
+pub struct ServerConfig {
+    pub dialect: Dialect,
+    pub game: Game,
+    /// Extra global names that are defined outside the workspace
+    pub globals: Vec<String>,
+    /// Namespaces several files deliberately define or extend, such as
+    /// `Legends`, exempt from duplicate global warnings
+    pub shared_globals: Vec<String>,
+    /// Hint when a local reuses a name from an enclosing scope
+    pub shadowing_hints: bool,
+    /// Hint at methods whose whole body calls the method itself
+    pub recursion_hints: bool,
+    /// Warn when a same-file or workspace function is called with the wrong
+    /// number of arguments
+    pub argument_count_checks: bool,
+    /// Report unused variables and parameters whose name starts with `_`
+    pub report_underscore_unused: bool,
+    /// Hint when a hook replaces a method without calling the original
+    pub override_hints: bool,
+    /// Method names for `override_hints` to skip
+    pub override_allowlist: Vec<String>,
+    pub diagnostics_mode: DiagnosticsMode,
+    /// Gitignore-style globs skipped during workspace indexing
+    pub exclude: Vec<String>,
+    pub brace_style: BraceStyle,
+    /// Directories script paths are relative to, instead of `scripts/`.
+    /// Relative entries are resolved against each workspace folder.
+    pub script_roots: Vec<String>,
+    /// Function scripts call to load other files, e.g. `require`
+    pub require_function: Option<String>,
+    /// Show color swatches for `"#rrggbb"` string literals
+    pub document_colors: bool,
+    /// Have "Sort members alphabetically" put data slots before functions
+    pub sort_functions_separately: bool,
+    /// Hint at functions and methods nothing in the workspace refers to
+    pub unused_symbol_hints: bool,
+    /// Extra names for `unused_symbol_hints` to skip
+    pub unused_symbol_allowlist: Vec<String>,
+    /// Extensions, without the dot, of files to index; empty means `nut`
+    pub file_extensions: Vec<String>,
+    /// Larger files are skipped by indexing and diagnostics; `None` means
+    /// `DEFAULT_MAX_FILE_SIZE_KB`
+    pub max_file_size_kb: Option<u64>,
+}
+
+impl ServerConfig {
+    /// Read settings from the client's options, ignoring unknown or malformed keys.
+    pub fn from_options(options: Option<&LSPAny>) -> Self {
+        let mut config = Self::default();
+        if let Some(options) = options {
+            config.merge_options(options);
+        }
+        config
+    }
+
+    /// Overwrite the settings `options` has keys for, keeping the rest.
+    fn merge_options(&mut self, options: &LSPAny) {
+        if let Some(dialect) = options
+            .get("dialect")
+            .and_then(LSPAny::as_str)
+            .and_then(Dialect::from_name)
+        {
+            self.dialect = dialect;
+        }
+        if let Some(game) = options
+            .get("game")
+            .and_then(LSPAny::as_str)
+            .and_then(Game::from_name)
+        {
+            self.game = game;
+        }
+        if let Some(names) = options.get("globals").and_then(LSPAny::as_array) {
+            self.globals = names
+                .iter()
+                .filter_map(LSPAny::as_str)
+                .map(|name| name.trim_start_matches("::").to_string())
+                .filter(|name| !name.is_empty())
+                .collect();
+        }
+        if let Some(names) = options.get("sharedGlobals").and_then(LSPAny::as_array) {
+            self.shared_globals = names
+                .iter()
+                .filter_map(LSPAny::as_str)
+                .map(|name| name.trim_start_matches("::").to_string())
+                .filter(|name| !name.is_empty())
+                .collect();
+        }
+        if let Some(enabled) = options.get("shadowingHints").and_then(LSPAny::as_bool) {
+            self.shadowing_hints = enabled;
+        }
+        if let Some(enabled) = options.get("recursionHints").and_then(LSPAny::as_bool) {
+            self.recursion_hints = enabled;
+        }
+        if let Some(enabled) = options.get("argumentCountChecks").and_then(LSPAny::as_bool) {
+            self.argument_count_checks = enabled;
+        }
+        if let Some(exempt) = options
+            .get("unusedUnderscoreExempt")
+            .and_then(LSPAny::as_bool)
+        {
+            self.report_underscore_unused = !exempt;
+        }
+        if let Some(enabled) = options.get("overrideHints").and_then(LSPAny::as_bool) {
+            self.override_hints = enabled;
+        }
+        if let Some(names) = options.get("overrideAllowlist").and_then(LSPAny::as_array) {
+            self.override_allowlist = names
+                .iter()
+                .filter_map(LSPAny::as_str)
+                .map(str::to_string)
+                .collect();
+        }
+        match options
+            .get("diagnostics")
+            .and_then(|diagnostics| diagnostics.get("mode"))
+            .and_then(LSPAny::as_str)
+        {
+            Some("full") => self.diagnostics_mode = DiagnosticsMode::Full,
+            Some("syntaxOnSave") => self.diagnostics_mode = DiagnosticsMode::SyntaxOnSave,
+            _ => {},
+        }
+        match options.get("braceStyle").and_then(LSPAny::as_str) {
+            Some("sameLine") => self.brace_style = BraceStyle::SameLine,
+            Some("nextLine") => self.brace_style = BraceStyle::NextLine,
+            _ => {},
+        }
+        match options.get("scriptRoot") {
+            Some(LSPAny::String(root)) => self.script_roots = vec![root.clone()],
+            Some(LSPAny::Array(roots)) => {
+                self.script_roots = roots
+                    .iter()
+                    .filter_map(LSPAny::as_str)
+                    .map(str::to_string)
+                    .collect();
+            },
+            _ => {},
+        }
+        if let Some(enabled) = options.get("unusedSymbolHints").and_then(LSPAny::as_bool) {
+            self.unused_symbol_hints = enabled;
+        }
+        if let Some(names) = options
+            .get("unusedSymbolAllowlist")
+            .and_then(LSPAny::as_array)
+        {
+            self.unused_symbol_allowlist = names
+                .iter()
+                .filter_map(LSPAny::as_str)
+                .map(str::to_string)
+                .collect();
+        }
+        if let Some(enabled) = options.get("documentColors").and_then(LSPAny::as_bool) {
+            self.document_colors = enabled;
+        }
+        if let Some(enabled) = options
+            .get("sortFunctionsSeparately")
+            .and_then(LSPAny::as_bool)
+        {
+            self.sort_functions_separately = enabled;
+        }
+        if let Some(name) = options.get("requireFunction").and_then(LSPAny::as_str)
+            && !name.is_empty()
+        {
+            self.require_function = Some(name.to_string());
+        }
+        if let Some(extensions) = options.get("fileExtensions").and_then(LSPAny::as_array) {
+            self.file_extensions = extensions
+                .iter()
+                .filter_map(LSPAny::as_str)
+                .map(|ext| ext.trim_start_matches('.').to_string())
+                .filter(|ext| !ext.is_empty())
+                .collect();
+        }
+        if let Some(size) = options.get("maxFileSizeKb").and_then(LSPAny::as_u64) {
+            self.max_file_size_kb = Some(size);
+        }
+        if let Some(patterns) = options.get("exclude").and_then(LSPAny::as_array) {
+            self.exclude = patterns
+                .iter()
+                .filter_map(LSPAny::as_str)
+                .map(str::to_string)
+                .collect();
+        }
+    }
+
+    /// Apply the settings a client pushes with `workspace/didChangeConfiguration`,
+    /// either the options themselves or nested under `squirrelLsp`, on top of
+    /// this self. `None` when they name none of the server's options, e.g.
+    /// `null`, `{}` or only another server's section.
+    pub fn with_settings(&self, settings: &LSPAny) -> Option<Self> {
+        let options = settings.get("squirrelLsp").unwrap_or(settings);
+        if !options
+            .as_object()?
+            .keys()
+            .any(|key| OPTION_KEYS.contains(&key.as_str()))
+        {
+            return None;
+        }
+        let mut config = self.clone();
+        config.merge_options(options);
+        Some(config)
+    }
+
+    /// Whether moving to `other` changes which files are indexed or what
+    /// indexing registers, so the workspace has to be rebuilt.
+    pub fn needs_reindex(&self, other: &ServerConfig) -> bool {
+        self.game != other.game
+            || self.globals != other.globals
+            || self.exclude != other.exclude
+            || self.script_roots != other.script_roots
+            || self.require_function != other.require_function
+            || self.file_extensions != other.file_extensions
+            || self.max_file_size_kb != other.max_file_size_kb
+            || self.unused_symbol_hints != other.unused_symbol_hints
+    }
+
+    /// Whether `path` has one of the configured Squirrel file extensions.
+    pub fn is_script_file(&self, path: &Path) -> bool {
+        let Some(ext) = path.extension().and_then(|ext| ext.to_str()) else {
+            return false;
+        };
+        if self.file_extensions.is_empty() {
+            return ext.eq_ignore_ascii_case("nut");
+        }
+        self.file_extensions
+            .iter()
+            .any(|configured| configured.eq_ignore_ascii_case(ext))
+    }
+
+    /// Whether a file of `len` bytes is too large to index or analyze.
+    pub fn exceeds_max_file_size(&self, len: usize) -> bool {
+        let max_kb = self.max_file_size_kb.unwrap_or(DEFAULT_MAX_FILE_SIZE_KB);
+        len as u64 > max_kb.saturating_mul(1024)
+    }
+
+    /// Globals to register before indexing: the game's engine globals
+    /// followed by the configured ones.
+    pub fn known_globals(&self) -> Vec<String> {
+        self.game
+            .globals()
+            .iter()
+            .map(|name| name.to_string())
+            .chain(self.globals.iter().cloned())
+            .collect()
+    }
+
+    /// The full allowlist for unreferenced symbol hints, or `None` when
+    /// they are disabled.
+    pub fn unused_symbol_allowlist(&self) -> Option<Vec<String>> {
+        self.unused_symbol_hints.then(|| {
+            DEFAULT_UNUSED_SYMBOL_ALLOWLIST
+                .iter()
+                .map(|name| name.to_string())
+                .chain(self.unused_symbol_allowlist.iter().cloned())
+                .collect()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults_without_options() {
+        let config = ServerConfig::from_options(None);
+        assert_eq!(config.dialect, Dialect::Squirrel);
+    }
+
+    #[test]
+    fn test_dialect_from_options() {
+        let options: LSPAny = [("dialect".to_string(), LSPAny::from("Quirrel"))]
+            .into_iter()
+            .collect::<tower_lsp::lsp_types::LSPObject>()
+            .into();
+        let config = ServerConfig::from_options(Some(&options));
+        assert_eq!(config.dialect, Dialect::Quirrel);
+        assert_eq!(config.dialect.binary_operators(), ["??"]);
+        assert_eq!(config.dialect.access_operators(), ["?."]);
+        assert!(Dialect::Squirrel.binary_operators().is_empty());
+        assert!(Dialect::Squirrel.access_operators().is_empty());
+    }
+
+    #[test]
+    fn test_unknown_dialect_falls_back() {
+        let options: LSPAny = [("dialect".to_string(), LSPAny::from("lua"))]
+            .into_iter()
+            .collect::<tower_lsp::lsp_types::LSPObject>()
+            .into();
+        let config = ServerConfig::from_options(Some(&options));
+        assert_eq!(config.dialect, Dialect::Squirrel);
+    }
+
+    #[test]
+    fn test_recursion_hints_from_options() {
+        assert!(!ServerConfig::from_options(None).recursion_hints);
+        let options: LSPAny = [("recursionHints".to_string(), LSPAny::from(true))]
+            .into_iter()
+            .collect::<tower_lsp::lsp_types::LSPObject>()
+            .into();
+        assert!(ServerConfig::from_options(Some(&options)).recursion_hints);
+    }
+
+    #[test]
+    fn test_argument_count_checks_from_options() {
+        assert!(!ServerConfig::from_options(None).argument_count_checks);
+        let options = serde_json::json!({ "argumentCountChecks": true });
+        assert!(ServerConfig::from_options(Some(&options)).argument_count_checks);
+    }
+
+    #[test]
+    fn test_override_hints_from_options() {
+        let options: LSPAny = [
+            ("overrideHints".to_string(), LSPAny::from(true)),
+            (
+                "overrideAllowlist".to_string(),
+                LSPAny::from(vec![LSPAny::from("onUpdate"), LSPAny::from(false)]),
+            ),
+        ]
+        .into_iter()
+        .collect::<tower_lsp::lsp_types::LSPObject>()
+        .into();
+        let config = ServerConfig::from_options(Some(&options));
+        assert!(config.override_hints);
+        assert_eq!(config.override_allowlist, vec!["onUpdate".to_string()]);
+    }
+
+    #[test]
+    fn test_diagnostics_mode_from_options() {
+        assert_eq!(
+            ServerConfig::from_options(None).diagnostics_mode,
+            DiagnosticsMode::Full
+        );
+        let options = serde_json::json!({ "diagnostics": { "mode": "syntaxOnSave" } });
+        assert_eq!(
+            ServerConfig::from_options(Some(&options)).diagnostics_mode,
+            DiagnosticsMode::SyntaxOnSave
+        );
+    }
+
+    #[test]
+    fn test_max_file_size_from_options() {
+        let config = ServerConfig::from_options(None);
+        assert!(!config.exceeds_max_file_size(1024 * 1024));
+        assert!(config.exceeds_max_file_size(1024 * 1024 + 1));
+
+        let options = serde_json::json!({ "maxFileSizeKb": 2 });
+        let config = ServerConfig::from_options(Some(&options));
+        assert!(!config.exceeds_max_file_size(2048));
+        assert!(config.exceeds_max_file_size(2049));
+    }
+
+    #[test]
+    fn test_unused_underscore_exempt_from_options() {
+        assert!(!ServerConfig::from_options(None).report_underscore_unused);
+        let options = serde_json::json!({ "unusedUnderscoreExempt": false });
+        assert!(ServerConfig::from_options(Some(&options)).report_underscore_unused);
+    }
+
+    #[test]
+    fn test_exclude_from_options() {
+        let options: LSPAny = [(
+            "exclude".to_string(),
+            LSPAny::from(vec![LSPAny::from("generated/"), LSPAny::from(3)]),
+        )]
+        .into_iter()
+        .collect::<tower_lsp::lsp_types::LSPObject>()
+        .into();
+        let config = ServerConfig::from_options(Some(&options));
+        assert_eq!(config.exclude, vec!["generated/".to_string()]);
+    }
+
+    #[test]
+    fn test_file_extensions_from_options() {
+        let config = ServerConfig::from_options(None);
+        assert!(config.is_script_file(Path::new("/mod/scripts/skill.nut")));
+        assert!(!config.is_script_file(Path::new("/mod/scripts/skill.gnut")));
+
+        let options: LSPAny = [(
+            "fileExtensions".to_string(),
+            LSPAny::from(vec![LSPAny::from("nut"), LSPAny::from(".gnut")]),
+        )]
+        .into_iter()
+        .collect::<tower_lsp::lsp_types::LSPObject>()
+        .into();
+        let config = ServerConfig::from_options(Some(&options));
+        assert_eq!(config.file_extensions, vec!["nut", "gnut"]);
+        assert!(config.is_script_file(Path::new("/mod/scripts/skill.GNUT")));
+        assert!(!config.is_script_file(Path::new("/mod/scripts/readme.txt")));
+    }
+
+    #[test]
+    fn test_known_globals_from_options() {
+        let defaults = ServerConfig::from_options(None).known_globals();
+        assert!(defaults.contains(&"World".to_string()));
+
+        let options: LSPAny = [
+            ("game".to_string(), LSPAny::from("none")),
+            (
+                "globals".to_string(),
+                LSPAny::from(vec![LSPAny::from("::Legends"), LSPAny::from("MSU")]),
+            ),
+        ]
+        .into_iter()
+        .collect::<tower_lsp::lsp_types::LSPObject>()
+        .into();
+        let config = ServerConfig::from_options(Some(&options));
+        assert_eq!(config.game, Game::None);
+        assert_eq!(config.known_globals(), vec!["Legends", "MSU"]);
+    }
+
+    #[test]
+    fn test_sort_functions_separately_from_options() {
+        assert!(!ServerConfig::from_options(None).sort_functions_separately);
+
+        let options: LSPAny = [("sortFunctionsSeparately".to_string(), LSPAny::from(true))]
+            .into_iter()
+            .collect::<tower_lsp::lsp_types::LSPObject>()
+            .into();
+        assert!(ServerConfig::from_options(Some(&options)).sort_functions_separately);
+    }
+
+    #[test]
+    fn test_shared_globals_from_options() {
+        let options: LSPAny = [(
+            "sharedGlobals".to_string(),
+            LSPAny::from(vec![LSPAny::from("::Legends"), LSPAny::from("")]),
+        )]
+        .into_iter()
+        .collect::<tower_lsp::lsp_types::LSPObject>()
+        .into();
+        let config = ServerConfig::from_options(Some(&options));
+        assert_eq!(config.shared_globals, vec!["Legends"]);
+    }
+
+    #[test]
+    fn test_script_root_from_options() {
+        let single: LSPAny = [("scriptRoot".to_string(), LSPAny::from("mod/src"))]
+            .into_iter()
+            .collect::<tower_lsp::lsp_types::LSPObject>()
+            .into();
+        assert_eq!(
+            ServerConfig::from_options(Some(&single)).script_roots,
+            vec!["mod/src".to_string()]
+        );
+
+        let list: LSPAny = [(
+            "scriptRoot".to_string(),
+            LSPAny::from(vec![LSPAny::from("a"), LSPAny::from("b")]),
+        )]
+        .into_iter()
+        .collect::<tower_lsp::lsp_types::LSPObject>()
+        .into();
+        assert_eq!(
+            ServerConfig::from_options(Some(&list)).script_roots,
+            vec!["a".to_string(), "b".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_with_settings() {
+        let options: LSPAny = [("braceStyle".to_string(), LSPAny::from("nextLine"))]
+            .into_iter()
+            .collect::<tower_lsp::lsp_types::LSPObject>()
+            .into();
+        let nested: LSPAny = [("squirrelLsp".to_string(), options.clone())]
+            .into_iter()
+            .collect::<tower_lsp::lsp_types::LSPObject>()
+            .into();
+
+        let current = ServerConfig {
+            globals: vec!["Legends".to_string()],
+            ..ServerConfig::default()
+        };
+
+        for settings in [&options, &nested] {
+            let config = current.with_settings(settings).unwrap();
+            assert_eq!(config.brace_style, BraceStyle::NextLine);
+            assert_eq!(config.globals, current.globals);
+            assert!(!current.needs_reindex(&config));
+        }
+        assert!(current.with_settings(&LSPAny::Null).is_none());
+    }
+
+    #[test]
+    fn test_settings_without_known_keys_are_ignored() {
+        let empty: LSPAny = tower_lsp::lsp_types::LSPObject::new().into();
+        let foreign: LSPAny = [("settings".to_string(), empty.clone())]
+            .into_iter()
+            .collect::<tower_lsp::lsp_types::LSPObject>()
+            .into();
+
+        let current = ServerConfig::from_options(None);
+        assert!(current.with_settings(&empty).is_none());
+        assert!(current.with_settings(&foreign).is_none());
+    }
+
+    #[test]
+    fn test_needs_reindex_on_globals_change() {
+        let config = ServerConfig {
+            globals: vec!["Legends".to_string()],
+            ..ServerConfig::default()
+        };
+        assert!(ServerConfig::default().needs_reindex(&config));
+    }
+}
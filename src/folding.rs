@@ -0,0 +1,166 @@
+//! Folding ranges for blocks, tables, arrays and block comments.
+//!
+//! Enums and `::Const` tables can run to hundreds of entries, so their
+//! members are also folded in groups: each run of entries not broken by a
+//! blank line or comment folds on its own.
+
+use tower_lsp::lsp_types::{FoldingRange, FoldingRangeKind};
+use tree_sitter::{Node, Tree};
+
+use crate::bb_support::is_const_path;
+use crate::helpers;
+
+pub fn compute_folding_ranges(tree: &Tree, text: &str) -> Vec<FoldingRange> {
+    let mut ranges = Vec::new();
+    collect_folds(tree.root_node(), text, false, &mut ranges);
+    ranges.sort_by_key(|r| (r.start_line, r.end_line));
+    ranges.dedup();
+    ranges
+}
+
+fn collect_folds(node: Node, text: &str, in_const: bool, ranges: &mut Vec<FoldingRange>) {
+    match node.kind() {
+        "block" | "table" | "class_body" | "array" | "switch_statement" => {
+            // Keep the closing bracket visible
+            push_fold(
+                ranges,
+                node.start_position().row,
+                node.end_position().row.saturating_sub(1),
+                None,
+            );
+        },
+        "enum_declaration" => {
+            push_fold(
+                ranges,
+                node.start_position().row,
+                node.end_position().row.saturating_sub(1),
+                None,
+            );
+            push_member_groups(ranges, enum_members(node));
+        },
+        "comment" => {
+            push_fold(
+                ranges,
+                node.start_position().row,
+                node.end_position().row,
+                Some(FoldingRangeKind::Comment),
+            );
+        },
+        _ => {},
+    }
+
+    if in_const && node.kind() == "table" {
+        let mut slots = Vec::new();
+        for child in node.children(&mut node.walk()) {
+            match child.kind() {
+                "table_slot" => slots.push(child),
+                "table_slots" => slots.extend(
+                    child
+                        .children(&mut child.walk())
+                        .filter(|c| c.kind() == "table_slot"),
+                ),
+                _ => {},
+            }
+        }
+        push_member_groups(ranges, slots);
+    }
+
+    let in_const = in_const
+        || (node.kind() == "update_expression"
+            && node
+                .named_child(0)
+                .is_some_and(|target| is_const_path(helpers::node_text(target, text))));
+    for child in node.children(&mut node.walk()) {
+        collect_folds(child, text, in_const, ranges);
+    }
+}
+
+fn enum_members(node: Node) -> Vec<Node> {
+    let name = node.child_by_field_name("name").or_else(|| {
+        node.children(&mut node.walk())
+            .find(|c| c.kind() == "identifier")
+    });
+    node.children(&mut node.walk())
+        .filter(|c| match c.kind() {
+            "enum_member" => true,
+            "identifier" => name.is_none_or(|n| n.id() != c.id()),
+            _ => false,
+        })
+        .collect()
+}
+
+/// Fold each run of members on consecutive lines. A single run covering
+/// every member would only repeat the fold of its container.
+fn push_member_groups(ranges: &mut Vec<FoldingRange>, members: Vec<Node>) {
+    let mut groups: Vec<(usize, usize)> = Vec::new();
+    for member in members {
+        let (start, end) = (member.start_position().row, member.end_position().row);
+        match groups.last_mut() {
+            Some(group) if start <= group.1 + 1 => group.1 = end,
+            _ => groups.push((start, end)),
+        }
+    }
+
+    if groups.len() > 1 {
+        for (start, end) in groups {
+            push_fold(ranges, start, end, Some(FoldingRangeKind::Region));
+        }
+    }
+}
+
+fn push_fold(
+    ranges: &mut Vec<FoldingRange>,
+    start: usize,
+    end: usize,
+    kind: Option<FoldingRangeKind>,
+) {
+    if end > start {
+        ranges.push(FoldingRange {
+            start_line: start as u32,
+            end_line: end as u32,
+            kind,
+            ..FoldingRange::default()
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_folds_member_groups_of_enums_and_const_tables() {
+        let code = r#"enum ItemSlot {
+    Mainhand,
+    Offhand,
+    Body,
+
+    Head,
+    Accessory
+}
+::Const.Tactical <- {
+    Actor = {
+        Ally = 0,
+        Enemy = 1,
+
+        Neutral = 2,
+        Wildlife = 3
+    }
+};
+"#;
+        let tree = helpers::parse_squirrel(code).unwrap();
+        let folds: Vec<(u32, u32)> = compute_folding_ranges(&tree, code)
+            .iter()
+            .map(|f| (f.start_line, f.end_line))
+            .collect();
+
+        // The enum body and its two groups of members
+        assert!(folds.contains(&(0, 6)));
+        assert!(folds.contains(&(1, 3)));
+        assert!(folds.contains(&(5, 6)));
+        // The nested `Actor` table and its groups
+        assert!(folds.contains(&(9, 14)));
+        assert!(folds.contains(&(10, 11)));
+        assert!(folds.contains(&(13, 14)));
+    }
+}
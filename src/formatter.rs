@@ -1,6 +1,9 @@
+use std::ops::Range;
+
 use thiserror::Error;
 use tree_sitter::Node;
 
+use crate::config::Dialect;
 use crate::helpers;
 
 #[derive(Debug, Clone)]
@@ -9,6 +12,27 @@ pub struct FormatOptions {
     pub insert_final_newline: bool,
     pub trim_trailing_whitespace: bool,
     pub max_width: usize,
+    /// Indent levels added for lines that continue an expression: wrapped
+    /// operands, multiline ternaries, arrays and call arguments
+    pub continuation_indent: usize,
+    /// Blank lines kept in a row inside switch case bodies
+    pub max_consecutive_blank_lines: usize,
+    /// Line up `//` comments that trail consecutive lines of code
+    pub align_trailing_comments: bool,
+    pub brace_style: BraceStyle,
+    /// Keep a braceless `if (cond) stmt;` on one line when it fits `max_width`
+    pub collapse_short_if: bool,
+    /// Put each call of a `.a().b()` chain on its own continuation line when
+    /// the chain would run past this width. `None` keeps chains inline.
+    pub chain_break_width: Option<usize>,
+    /// Re-indent the `*` lines of multiline `/* */` comments to sit under the
+    /// opening `/*` at the current level. Other lines are left as written.
+    pub reindent_block_comments: bool,
+    /// Copy class attribute blocks `</ key = value />` as written. When off,
+    /// their spacing is normalized like any other expression.
+    pub preserve_attributes: bool,
+    pub line_ending: LineEnding,
+    pub dialect: Dialect,
 }
 
 impl Default for FormatOptions {
@@ -18,6 +42,16 @@ impl Default for FormatOptions {
             insert_final_newline: true,
             trim_trailing_whitespace: true,
             max_width: 100,
+            continuation_indent: 1,
+            max_consecutive_blank_lines: 1,
+            align_trailing_comments: false,
+            brace_style: BraceStyle::SameLine,
+            collapse_short_if: false,
+            chain_break_width: None,
+            reindent_block_comments: false,
+            preserve_attributes: true,
+            line_ending: LineEnding::Auto,
+            dialect: Dialect::Squirrel,
         }
     }
 }
@@ -30,6 +64,13 @@ impl FormatOptions {
         }
     }
 
+    fn tab_width(&self) -> usize {
+        match self.indent_style {
+            IndentStyle::Spaces(width) => width,
+            IndentStyle::Tabs => 4,
+        }
+    }
+
     fn push_indent(&self, buffer: &mut String, level: usize) {
         match self.indent_style {
             IndentStyle::Spaces(width) => {
@@ -52,6 +93,42 @@ pub enum IndentStyle {
     Tabs,
 }
 
+/// Where the `{` of a function, class or control-flow block goes.
+/// Table literals always keep their brace on the same line.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BraceStyle {
+    #[default]
+    SameLine,
+    /// Allman style: the brace starts its own line at the current indent
+    NextLine,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LineEnding {
+    /// Keep whichever ending is most common in the source
+    #[default]
+    Auto,
+    Lf,
+    Crlf,
+}
+
+impl LineEnding {
+    fn resolve(self, source: &str) -> Self {
+        match self {
+            LineEnding::Auto => {
+                let crlf = source.matches("\r\n").count();
+                let lf = source.matches('\n').count() - crlf;
+                if crlf > lf {
+                    LineEnding::Crlf
+                } else {
+                    LineEnding::Lf
+                }
+            },
+            other => other,
+        }
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum FormatError {
     #[error("failed to configure squirrel parser: {0}")]
@@ -68,6 +145,8 @@ struct Token {
     kind: TokenKind,
     preceded_by_newline: bool,
     preceding_whitespace: String,
+    /// Byte range in the source, used to copy `// fmt: off` regions verbatim
+    range: Range<usize>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -80,6 +159,8 @@ enum TokenKind {
     Symbol,
     Other,
     Blankline,
+    /// A whole `</ ... />` attribute block
+    Attribute,
 }
 
 #[derive(Debug, Clone)]
@@ -119,11 +200,18 @@ struct BraceContext {
     case_body_indented: bool,
     // True if this brace was auto-inserted for single-statement if/else
     is_synthetic: bool,
+    // True for an auto-inserted brace around an `else` body, which unlike an
+    // `if` body can't be continued by a following `else`
+    is_else_body: bool,
+    // True for a braced `if` body, which a following `else` continues
+    is_if_body: bool,
 }
 
 #[derive(Debug, Clone, Copy)]
 enum ParenKind {
     For,
+    /// `foreach (index, value in container)`, always kept on one line
+    Foreach,
     If,
     Switch,
     Function,
@@ -140,10 +228,20 @@ struct ParenContext {
 #[derive(Clone, Copy)]
 struct BracketContext {
     pretty_print: bool,
+    /// `foo[...]` rather than an array literal
+    is_subscript: bool,
     /// Output position where the '[' was written
     start_output_pos: usize,
 }
 
+#[derive(Clone, Copy)]
+struct ChainContext {
+    /// Total depth (paren + bracket) of the chain's `.` tokens
+    depth: usize,
+    /// `remaining.len()` as of the chain's last token
+    remaining_at_end: usize,
+}
+
 #[derive(Clone, Copy)]
 struct TernaryContext {
     /// Total depth (paren + bracket) when the ternary started
@@ -160,9 +258,20 @@ pub fn format_document(source: &str, options: &FormatOptions) -> Result<String,
     // while the grammar is extended to support lenient variants.
     // if root.has_error() { return Err(FormatError::ParseError); }
 
-    let tokens = collect_tokens(root, source)?;
+    let mut tokens = collect_tokens(root, source)?;
+    tokens = merge_attribute_blocks(tokens, source, options.preserve_attributes);
+    let dialect_operators: Vec<&str> = options
+        .dialect
+        .binary_operators()
+        .iter()
+        .chain(options.dialect.access_operators())
+        .copied()
+        .collect();
+    if !dialect_operators.is_empty() {
+        tokens = merge_dialect_operators(tokens, &dialect_operators);
+    }
 
-    let mut formatter = Formatter::new(options);
+    let mut formatter = Formatter::new(options, source);
     for (idx, token) in tokens.iter().enumerate() {
         let next = tokens.get(idx + 1);
         let remaining = &tokens[idx + 1..];
@@ -174,11 +283,37 @@ pub fn format_document(source: &str, options: &FormatOptions) -> Result<String,
         output.push('\n');
     }
 
+    // Newlines are written as '\n' throughout; settle on one ending at the end
+    output = convert_line_endings(&output, LineEnding::Lf);
+    if options.align_trailing_comments {
+        output = align_trailing_comments(&output, options.tab_width());
+    }
+    if options.line_ending.resolve(source) == LineEnding::Crlf {
+        output = convert_line_endings(&output, LineEnding::Crlf);
+    }
+
+    Ok(output)
+}
+
+/// The tokens the formatter works from, one per line as their kind and
+/// quoted text, with `newline` after tokens that start a new line. Meant for
+/// tracking down surprising formatter output.
+pub fn debug_tokens(source: &str) -> Result<String, FormatError> {
+    let tree = helpers::parse_squirrel(source).map_err(|_| FormatError::ParseError)?;
+    let mut output = String::new();
+    for token in collect_tokens(tree.root_node(), source)? {
+        output.push_str(&format!("{:?}\t{:?}", token.kind, token.text));
+        if token.preceded_by_newline {
+            output.push_str("\tnewline");
+        }
+        output.push('\n');
+    }
     Ok(output)
 }
 
 struct Formatter<'a> {
     options: &'a FormatOptions,
+    source: &'a str,
     output: String,
     indent_level: usize,
     paren_depth: usize,
@@ -190,16 +325,27 @@ struct Formatter<'a> {
     parens: Vec<ParenContext>,
     brackets: Vec<BracketContext>,
     ternaries: Vec<TernaryContext>,
+    chains: Vec<ChainContext>,
     // Track the kind of the last closed paren (used to detect switch blocks before '{')
     last_closed_paren_kind: Option<ParenKind>,
     // Track the paren_depth at which we started breaking logical operators
     breaking_logical_at_depth: Option<usize>,
+    // Set after a `class` keyword until its body brace is written
+    in_class_header: bool,
+    // Inside `// fmt: off`: the output length and source offset where the
+    // region began. Tokens are still formatted to keep the nesting state
+    // right, but their output is replaced with the original source.
+    verbatim: Option<(usize, usize)>,
+    // Set when a statement ending an auto-opened block has a trailing
+    // comment, so the block is closed once the comment is written
+    close_synthetic_after_comment: bool,
 }
 
 impl<'a> Formatter<'a> {
-    fn new(options: &'a FormatOptions) -> Self {
+    fn new(options: &'a FormatOptions, source: &'a str) -> Self {
         Self {
             options,
+            source,
             output: String::new(),
             indent_level: 0,
             paren_depth: 0,
@@ -211,12 +357,21 @@ impl<'a> Formatter<'a> {
             parens: Vec::new(),
             brackets: Vec::new(),
             ternaries: Vec::new(),
+            chains: Vec::new(),
             last_closed_paren_kind: None,
             breaking_logical_at_depth: None,
+            in_class_header: false,
+            verbatim: None,
+            close_synthetic_after_comment: false,
         }
     }
 
     fn finish(mut self) -> String {
+        // An unclosed `// fmt: off` runs to the end of the file
+        if let Some((mark, from)) = self.verbatim.take() {
+            self.output.truncate(mark);
+            self.output.push_str(&self.source[from..]);
+        }
         if self.options.trim_trailing_whitespace {
             trim_trailing_whitespace(&mut self.output);
         }
@@ -228,6 +383,25 @@ impl<'a> Formatter<'a> {
     }
 
     fn write_token(&mut self, token: &Token, next: Option<&Token>, remaining: &[Token]) {
+        // Leave the continuation indent of any broken call chain we've moved past
+        while self
+            .chains
+            .last()
+            .is_some_and(|c| remaining.len() < c.remaining_at_end)
+        {
+            self.chains.pop();
+            self.indent_level = self
+                .indent_level
+                .saturating_sub(self.options.continuation_indent);
+        }
+
+        if token.kind == TokenKind::Comment
+            && let Some(directive) = fmt_directive(&token.text)
+        {
+            self.write_fmt_directive(token, directive);
+            return;
+        }
+
         // Handle case/default in switch blocks before other processing
         if self.in_switch_block() && matches!(token.text.as_str(), "case" | "default") {
             self.write_case_label(token);
@@ -237,27 +411,67 @@ impl<'a> Formatter<'a> {
         let is_symbol = token.kind == TokenKind::Symbol;
         match token.text.as_str() {
             "{" if is_symbol => self.write_open_brace(token, next),
-            "}" if is_symbol => self.write_close_brace(token, next),
+            "}" if is_symbol => self.write_close_brace(token, remaining),
             ";" if is_symbol => self.write_semicolon(token, next),
             "," if is_symbol => self.write_comma(token, next),
             "(" if is_symbol => self.write_open_paren(token, remaining),
             ")" if is_symbol => self.write_close_paren(token, remaining),
             "[" if is_symbol => self.write_open_bracket(token, next, remaining),
             "]" if is_symbol => self.write_close_bracket(token),
-            "." | "::" => self.write_member_access(token),
+            "." | "::" | "?." => self.write_member_access(token, remaining),
             "?" => self.write_ternary(token, remaining),
             ":" => self.write_colon(token, next),
             "++" | "--" => self.write_increment(token),
+            "in" if self.in_foreach_header() => self.write_foreach_in(token),
             "else" if token.kind == TokenKind::Keyword => self.write_else(token, remaining),
-            _ if token.kind == TokenKind::Comment => self.write_comment(token),
-            _ if token.kind == TokenKind::Blankline => self.write_blankline(),
-            _ if token.kind != TokenKind::String && is_operator(token.text.as_str()) => {
+            "class" if token.kind == TokenKind::Keyword => {
+                self.in_class_header = true;
+                self.write_default(token);
+            },
+            _ if token.kind == TokenKind::Comment => {
+                self.write_comment(token);
+                if std::mem::take(&mut self.close_synthetic_after_comment) {
+                    self.close_synthetic_blocks(next);
+                }
+            },
+            _ if token.kind == TokenKind::Attribute => self.write_attribute(token, next),
+            _ if token.kind == TokenKind::Blankline => self.write_blankline(token),
+            _ if token.kind != TokenKind::String
+                && is_operator(token.text.as_str(), self.options.dialect) =>
+            {
                 self.write_operator(token, remaining)
             },
             _ => self.write_default(token),
         }
     }
 
+    /// Enter or leave a verbatim region. The `off` comment is formatted and
+    /// the region starts on the following line; on `on`, everything written
+    /// since is swapped for the source up to the `on` comment.
+    fn write_fmt_directive(&mut self, token: &Token, directive: FmtDirective) {
+        match (directive, self.verbatim) {
+            (FmtDirective::Off, None) => {
+                self.write_comment(token);
+                let from = self.source[token.range.end..]
+                    .find('\n')
+                    .map_or(self.source.len(), |i| token.range.end + i + 1);
+                self.verbatim = Some((self.output.len(), from));
+            },
+            (FmtDirective::On, Some((mark, from))) => {
+                self.verbatim = None;
+                self.output.truncate(mark);
+                self.output
+                    .push_str(&self.source[from..token.range.start.max(from)]);
+                trim_trailing_whitespace_line(&mut self.output);
+                self.needs_indent = self.output.ends_with('\n');
+                self.pending_space = false;
+                self.write_comment(token);
+            },
+            // A stray `on`, or a repeated `off` inside a region
+            _ => self.write_comment(token),
+        }
+    }
+
     /// Helper function to find the next non-comment token
     fn next_non_comment(remaining: &[Token]) -> Option<&Token> {
         remaining
@@ -295,6 +509,12 @@ impl<'a> Formatter<'a> {
                 .is_some_and(|f| matches!(f.kind, ParenKind::For))
     }
 
+    fn in_foreach_header(&self) -> bool {
+        self.parens.last().is_some_and(|f| {
+            matches!(f.kind, ParenKind::Foreach) && f.bracket_depth_at_open == self.bracket_depth
+        })
+    }
+
     fn in_function_params(&self) -> bool {
         self.paren_depth > 0
             && self
@@ -398,14 +618,18 @@ impl<'a> Formatter<'a> {
         self.ensure_indent();
         self.apply_pending_space();
         let prev_was_unary = self.prev().is_some_and(|p| p.was_unary);
-        if !prev_was_unary && needs_space(self.prev(), token) && !self.ends_with_whitespace() {
+        // `- -x` keeps its space, since `--x` would be a decrement
+        let would_merge = self.prev().is_some_and(|p| {
+            p.was_unary && matches!(p.text.as_str(), "-" | "+") && token.text.starts_with(&p.text)
+        });
+        if (would_merge || !prev_was_unary && needs_space(self.prev(), token, self.options.dialect))
+            && !self.ends_with_whitespace()
+        {
             self.output.push(' ');
         }
     }
 
     fn write_open_brace(&mut self, token: &Token, next: Option<&Token>) {
-        self.prepare_token(token);
-
         // Determine brace kind (object literal vs code block, inline vs multiline)
         // Check if the previous closing paren was for a switch statement
         let is_switch = matches!(self.last_closed_paren_kind, Some(ParenKind::Switch));
@@ -414,6 +638,7 @@ impl<'a> Formatter<'a> {
             .is_some_and(|p| p.text == ")" || is_block_introducing_keyword(p.text.as_str()));
         let is_empty = matches!(next.map(|n| n.text.as_str()), Some("}"));
         let is_do = self.prev().is_some_and(|p| p.text == "do");
+        let is_if_body = is_block && matches!(self.last_closed_paren_kind, Some(ParenKind::If));
 
         let kind = if is_switch {
             BraceKind::Switch
@@ -421,7 +646,8 @@ impl<'a> Formatter<'a> {
             BraceKind::DoBlock
         } else if is_empty && !is_block {
             BraceKind::ObjectInline
-        } else if is_empty && matches!(self.last_closed_paren_kind, Some(ParenKind::Function)) {
+        } else if is_empty {
+            // Empty function, control-flow and try/catch bodies stay `{}`
             BraceKind::BlockInline
         } else if is_block {
             BraceKind::Block
@@ -429,6 +655,16 @@ impl<'a> Formatter<'a> {
             BraceKind::ObjectMultiline
         };
 
+        let is_class_body = std::mem::take(&mut self.in_class_header);
+        let own_line = matches!(
+            kind,
+            BraceKind::Block | BraceKind::Switch | BraceKind::DoBlock
+        ) || (is_class_body && !kind.is_inline());
+        if own_line && self.next_line_braces() && !self.output.ends_with('\n') {
+            self.push_newline();
+        }
+
+        self.prepare_token(token);
         self.output.push('{');
 
         self.braces.push(BraceContext {
@@ -438,6 +674,8 @@ impl<'a> Formatter<'a> {
             in_case_label: false,
             case_body_indented: false,
             is_synthetic: false,
+            is_else_body: false,
+            is_if_body,
         });
 
         // Clear the last closed paren after consuming it
@@ -453,7 +691,36 @@ impl<'a> Formatter<'a> {
         self.push_newline();
     }
 
-    fn write_close_brace(&mut self, token: &Token, next: Option<&Token>) {
+    fn next_line_braces(&self) -> bool {
+        self.options.brace_style == BraceStyle::NextLine
+    }
+
+    /// Open a block the source left without braces (single-statement if/else)
+    fn write_synthetic_open_brace(&mut self, is_else_body: bool) {
+        if self.next_line_braces() {
+            self.push_newline();
+            self.ensure_indent();
+        } else {
+            self.output.push(' ');
+        }
+        self.output.push('{');
+        self.indent_level += 1;
+        self.push_newline();
+
+        self.braces.push(BraceContext {
+            kind: BraceKind::Block,
+            paren_depth_at_open: self.paren_depth,
+            bracket_depth_at_open: self.bracket_depth,
+            in_case_label: false,
+            case_body_indented: false,
+            is_synthetic: true,
+            is_else_body,
+            is_if_body: false,
+        });
+    }
+
+    fn write_close_brace(&mut self, token: &Token, remaining: &[Token]) {
+        let next = remaining.first();
         let frame = self.braces.pop();
         let kind = frame.map(|f| f.kind);
         let inline = kind.is_some_and(|k| k.is_inline());
@@ -474,8 +741,13 @@ impl<'a> Formatter<'a> {
             self.push_newline();
         }
 
-        // Extra indent for objects in non-pretty-printed arrays (aligns } with ])
-        let next_is_bracket = matches!(next.map(|t| t.text.as_str()), Some("]"));
+        // Extra indent for objects in non-pretty-printed arrays (aligns } with ]).
+        // A trailing comma before the `]` still ends the array.
+        let next_is_bracket = match remaining {
+            [close, ..] if close.text == "]" => true,
+            [comma, close, ..] => comma.text == "," && close.text == "]",
+            _ => false,
+        };
         let in_pretty_array = self
             .brackets
             .last()
@@ -495,14 +767,21 @@ impl<'a> Formatter<'a> {
         }
         self.set_prev(token);
 
+        // Only an `if` body is continued by an `else`; after any other block
+        // it belongs to an enclosing braceless `if`
+        let next_is_else = next.is_some_and(|t| t.text == "else");
+        let continues_if = frame.is_none_or(|f| f.is_synthetic || f.is_if_body);
+
         // Determine what follows the brace
         if let Some(next_token) = next {
             match next_token.text.as_str() {
-                ")" | ";" | "," | "." => {
+                ")" | ";" | "," | "." | "?." => {
                     self.needs_indent = false;
                     return;
                 },
-                "else" | "catch" | "finally" => {
+                "else" | "catch" | "finally"
+                    if !self.next_line_braces() && (!next_is_else || continues_if) =>
+                {
                     self.output.push(' ');
                     self.needs_indent = false;
                     self.prev.clear();
@@ -525,6 +804,18 @@ impl<'a> Formatter<'a> {
         if !inline || kind == Some(BraceKind::BlockInline) {
             self.push_newline();
         }
+
+        // A braced statement can be the whole body of a braceless if/else
+        if frame.is_some_and(|f| {
+            !f.is_synthetic
+                && matches!(
+                    f.kind,
+                    BraceKind::Block | BraceKind::BlockInline | BraceKind::Switch
+                )
+        }) && !(next_is_else && continues_if)
+        {
+            self.close_synthetic_blocks(next);
+        }
     }
 
     fn write_semicolon(&mut self, token: &Token, next: Option<&Token>) {
@@ -537,13 +828,16 @@ impl<'a> Formatter<'a> {
 
         // If we're in a multiline ternary, dedent back
         if !self.ternaries.is_empty() {
-            self.indent_level = self.indent_level.saturating_sub(1);
+            self.indent_level = self
+                .indent_level
+                .saturating_sub(self.options.continuation_indent);
             self.ternaries.pop();
         }
 
         // If a line comment follows on the same line (not preceded by newline),
-        // keep it on the same line.
+        // keep it on the same line. An auto-opened block closes after it.
         if next.is_some_and(Self::is_inline_comment) {
+            self.close_synthetic_after_comment = self.braces.last().is_some_and(|b| b.is_synthetic);
             self.set_prev(token);
             return;
         }
@@ -553,17 +847,31 @@ impl<'a> Formatter<'a> {
             self.set_prev(token);
         } else {
             self.push_newline();
+            self.close_synthetic_blocks(next);
         }
+    }
 
-        // If we auto-opened a block for a single-statement if/else, close it now
-        if self.braces.last().is_some_and(|b| b.is_synthetic) {
+    /// Close the blocks auto-opened for single-statement if/else bodies that
+    /// end with the current statement. An `else` after an `if` body continues
+    /// that `if`; otherwise every enclosing braceless body ends here too, as
+    /// in `if (a) if (b) x(); else y(); else z();`.
+    fn close_synthetic_blocks(&mut self, next: Option<&Token>) {
+        let next_is_else = next.is_some_and(|t| t.text == "else");
+        while let Some(frame) = self.braces.last().copied().filter(|b| b.is_synthetic) {
             let synthetic = Token {
                 text: "}".to_string(),
                 kind: TokenKind::Symbol,
                 preceded_by_newline: false,
                 preceding_whitespace: String::new(),
+                range: 0..0,
             };
-            self.write_close_brace(&synthetic, next);
+            self.write_close_brace(
+                &synthetic,
+                next.map(std::slice::from_ref).unwrap_or_default(),
+            );
+            if next_is_else && !frame.is_else_body {
+                break;
+            }
         }
     }
 
@@ -586,7 +894,7 @@ impl<'a> Formatter<'a> {
 
         if in_object_top_level && !in_function_params {
             match next {
-                Some(t) if t.text.as_str() == "function" => self.write_blankline(),
+                Some(t) if t.text.as_str() == "function" => self.write_single_blankline(),
                 _ => self.push_newline(),
             }
         } else if in_multiline_call {
@@ -610,6 +918,7 @@ impl<'a> Formatter<'a> {
 
         let kind = match self.prev().map(|p| p.text.as_str()) {
             Some("for") => ParenKind::For,
+            Some("foreach") => ParenKind::Foreach,
             Some("if") => ParenKind::If,
             Some("switch") => ParenKind::Switch,
             Some("function") => ParenKind::Function,
@@ -627,7 +936,7 @@ impl<'a> Formatter<'a> {
         let should_multiline = match kind {
             ParenKind::Regular => next_breaks_line,
             ParenKind::If | ParenKind::For | ParenKind::Switch => next_breaks_line,
-            ParenKind::Function => false,
+            ParenKind::Function | ParenKind::Foreach => false,
         };
 
         self.parens.push(ParenContext {
@@ -637,7 +946,7 @@ impl<'a> Formatter<'a> {
         });
 
         if should_multiline {
-            self.indent_level += 1;
+            self.indent_level += self.options.continuation_indent;
             self.push_newline();
         }
 
@@ -650,7 +959,9 @@ impl<'a> Formatter<'a> {
         // If we're closing a paren and a ternary indent is active, reset it
         while let Some(ctx) = self.ternaries.last() {
             if self.total_depth() < ctx.depth_at_start {
-                self.indent_level = self.indent_level.saturating_sub(1);
+                self.indent_level = self
+                    .indent_level
+                    .saturating_sub(self.options.continuation_indent);
                 self.ternaries.pop();
             } else {
                 break;
@@ -668,7 +979,9 @@ impl<'a> Formatter<'a> {
         if was_multiline {
             // For multiline function calls, close paren on its own line based on prior indent
             if matches!(frame_kind, Some(ParenKind::Regular)) {
-                self.indent_level = self.indent_level.saturating_sub(1);
+                self.indent_level = self
+                    .indent_level
+                    .saturating_sub(self.options.continuation_indent);
                 if !self.output.ends_with('\n') {
                     self.push_newline();
                 }
@@ -689,30 +1002,66 @@ impl<'a> Formatter<'a> {
             // Place opening brace on a new line if the condition was multiline
             if was_multiline {
                 self.push_newline();
-            } else if !next_is_inline_comment {
+            } else if !next_is_inline_comment && !self.next_line_braces() {
                 self.output.push(' ');
                 self.needs_indent = false;
             }
+        } else if is_if_header && !was_multiline && self.fits_collapsed_if(remaining) {
+            self.output.push(' ');
+            self.needs_indent = false;
         } else if is_if_header {
             // Auto-insert a block for single-statement ifs
-            self.output.push(' ');
-            self.output.push('{');
-            self.indent_level += 1;
-            self.push_newline();
-
-            // Push synthetic brace context
-            self.braces.push(BraceContext {
-                kind: BraceKind::Block,
-                paren_depth_at_open: self.paren_depth,
-                bracket_depth_at_open: self.bracket_depth,
-                in_case_label: false,
-                case_body_indented: false,
-                is_synthetic: true,
-            });
+            self.write_synthetic_open_brace(false);
         }
         self.set_prev(token);
     }
 
+    /// Whether the single statement after an `if (...)` header can stay on the
+    /// header's line: it must be a plain statement with no block, comment or
+    /// trailing `else`, and the whole line must fit within `max_width`.
+    fn fits_collapsed_if(&self, remaining: &[Token]) -> bool {
+        if !self.options.collapse_short_if {
+            return false;
+        }
+        let Some(first) = remaining.first() else {
+            return false;
+        };
+        if first.kind == TokenKind::Keyword && is_block_introducing_keyword(&first.text) {
+            return false;
+        }
+
+        let mut depth = 0usize;
+        let mut end = None;
+        for (idx, token) in remaining.iter().enumerate() {
+            if matches!(token.kind, TokenKind::Comment | TokenKind::Blankline) {
+                return false;
+            }
+            match token.text.as_str() {
+                "{" | "}" if token.kind == TokenKind::Symbol => return false,
+                "(" | "[" if token.kind == TokenKind::Symbol => depth += 1,
+                ")" | "]" if token.kind == TokenKind::Symbol => {
+                    depth = depth.saturating_sub(1);
+                },
+                ";" if depth == 0 => {
+                    end = Some(idx);
+                    break;
+                },
+                _ => {},
+            }
+        }
+        let Some(end) = end else {
+            return false;
+        };
+        if Self::next_non_comment(&remaining[end + 1..]).is_some_and(|t| t.text == "else") {
+            return false;
+        }
+
+        // Space after `)` plus the trailing semicolon
+        let length =
+            self.get_current_line_length() + 1 + self.estimate_statement_length(remaining) + 1;
+        length <= self.options.max_width
+    }
+
     fn write_open_bracket(&mut self, token: &Token, next: Option<&Token>, remaining: &[Token]) {
         self.prepare_token(token);
         self.output.push('[');
@@ -732,6 +1081,7 @@ impl<'a> Formatter<'a> {
         if is_subscript || is_empty {
             self.brackets.push(BracketContext {
                 pretty_print: false,
+                is_subscript,
                 start_output_pos: self.output.len(),
             });
             self.set_prev(token);
@@ -772,6 +1122,7 @@ impl<'a> Formatter<'a> {
 
         self.brackets.push(BracketContext {
             pretty_print: should_pretty_print,
+            is_subscript: false,
             start_output_pos: self.output.len(),
         });
 
@@ -779,7 +1130,7 @@ impl<'a> Formatter<'a> {
             if !remaining.first().is_some_and(Self::is_inline_comment) {
                 self.push_newline();
             }
-            self.indent_level += 1;
+            self.indent_level += self.options.continuation_indent;
         }
         self.set_prev(token);
     }
@@ -792,7 +1143,9 @@ impl<'a> Formatter<'a> {
         let start_idx = ctx.map(|c| c.start_output_pos).unwrap_or(self.output.len());
 
         if was_pretty {
-            self.indent_level = self.indent_level.saturating_sub(1);
+            self.indent_level = self
+                .indent_level
+                .saturating_sub(self.options.continuation_indent);
             if !self.output.ends_with('\n') {
                 self.push_newline();
             }
@@ -808,13 +1161,21 @@ impl<'a> Formatter<'a> {
         self.set_prev(token);
     }
 
-    fn write_member_access(&mut self, token: &Token) {
+    fn write_member_access(&mut self, token: &Token, remaining: &[Token]) {
+        if token.text != "::" && self.should_break_chain(token, remaining) {
+            self.push_newline();
+            self.ensure_indent();
+            self.output.push_str(&token.text);
+            self.set_prev(token);
+            return;
+        }
+
         self.prepare_token(token);
 
         let keep_space = token.text == "::"
             || self.prev().is_some_and(|p| {
                 p.kind == TokenKind::Keyword
-                    || is_operator(&p.text)
+                    || is_operator(&p.text, self.options.dialect)
                     || p.text == ","
                     || p.text == ":"
                     || p.text == "?"
@@ -826,6 +1187,33 @@ impl<'a> Formatter<'a> {
         self.set_prev(token);
     }
 
+    /// Whether to start a new line before this `.`: either it continues a
+    /// chain that was already broken, or it starts a chain of at least two
+    /// calls that would run past `chain_break_width`.
+    fn should_break_chain(&mut self, token: &Token, remaining: &[Token]) -> bool {
+        let Some(width) = self.options.chain_break_width else {
+            return false;
+        };
+        let depth = self.total_depth();
+        if self.chains.last().is_some_and(|c| c.depth == depth) {
+            return true;
+        }
+
+        let Some((chain_tokens, length)) = self.estimate_chain(token, remaining) else {
+            return false;
+        };
+        if self.get_current_line_length() + length <= width {
+            return false;
+        }
+
+        self.indent_level += self.options.continuation_indent;
+        self.chains.push(ChainContext {
+            depth,
+            remaining_at_end: remaining.len() - chain_tokens,
+        });
+        true
+    }
+
     fn write_ternary(&mut self, token: &Token, remaining: &[Token]) {
         let line_length = self.get_current_line_length();
         let estimated_length = self.estimate_ternary_length(remaining);
@@ -836,7 +1224,7 @@ impl<'a> Formatter<'a> {
         if would_exceed {
             // Break to new line and indent
             self.push_newline();
-            self.indent_level += 1;
+            self.indent_level += self.options.continuation_indent;
             self.ternaries.push(TernaryContext {
                 depth_at_start: self.total_depth(),
             });
@@ -941,7 +1329,9 @@ impl<'a> Formatter<'a> {
     }
 
     fn write_operator(&mut self, token: &Token, remaining: &[Token]) {
-        if is_unary_operator(token.text.as_str()) && is_unary_context(self.prev()) {
+        if is_unary_operator(token.text.as_str())
+            && is_unary_context(self.prev(), self.options.dialect)
+        {
             self.write_unary_operator(token);
             return;
         }
@@ -967,6 +1357,18 @@ impl<'a> Formatter<'a> {
         self.write_operator_default(token);
     }
 
+    /// The `in` separating a foreach's variables from its container. It is
+    /// a keyword here, not a membership test, so it never starts a new line.
+    fn write_foreach_in(&mut self, token: &Token) {
+        self.prepare_token(token);
+        if !self.ends_with_whitespace() {
+            self.output.push(' ');
+        }
+        self.output.push_str(&token.text);
+        self.pending_space = true;
+        self.set_prev(token);
+    }
+
     fn write_unary_operator(&mut self, token: &Token) {
         self.prepare_token(token);
         self.output.push_str(&token.text);
@@ -995,6 +1397,11 @@ impl<'a> Formatter<'a> {
         remaining: &[Token],
         is_logical_op: bool,
     ) -> bool {
+        // A subscript reads as one unit, however long its index expression
+        if is_logical_op && self.brackets.iter().any(|b| b.is_subscript) {
+            return false;
+        }
+
         let line_length = self.get_current_line_length();
         let in_condition = self.is_in_condition();
         let at_condition_top_level = self.is_at_condition_top_level();
@@ -1080,7 +1487,7 @@ impl<'a> Formatter<'a> {
         let in_condition = self.is_in_condition();
 
         // Calculate extra indentation:
-        // - Base: +1 for continuation line
+        // - Base: `continuation_indent` for the continuation line
         // - If we're inside parens deeper than where we started: +1 for each extra level
         let breaking_depth = self.breaking_logical_at_depth.unwrap_or(0);
         let extra_paren_indent =
@@ -1090,7 +1497,7 @@ impl<'a> Formatter<'a> {
                 0
             };
 
-        1 + extra_paren_indent
+        self.options.continuation_indent + extra_paren_indent
     }
 
     fn write_operator_default(&mut self, token: &Token) {
@@ -1142,10 +1549,17 @@ impl<'a> Formatter<'a> {
                     self.push_newline();
                 }
                 // Only indent the first line; preserve internal formatting
+                let star_line = line.trim_start();
                 if idx == 0 {
                     self.ensure_indent();
+                    self.output.push_str(line);
+                } else if self.options.reindent_block_comments && star_line.starts_with('*') {
+                    self.ensure_indent();
+                    self.output.push(' ');
+                    self.output.push_str(star_line);
+                } else {
+                    self.output.push_str(line);
                 }
-                self.output.push_str(line);
             }
             self.push_newline();
             return;
@@ -1159,6 +1573,24 @@ impl<'a> Formatter<'a> {
         self.set_prev(token);
     }
 
+    /// Attributes of a class stay after its name; a member's attributes go
+    /// on their own line above it.
+    fn write_attribute(&mut self, token: &Token, next: Option<&Token>) {
+        if self.in_class_header {
+            self.pending_space = true;
+            self.write_default(token);
+            return;
+        }
+
+        if !self.needs_indent {
+            self.push_newline();
+        }
+        self.write_default(token);
+        if !next.is_some_and(Self::is_inline_comment) {
+            self.push_newline();
+        }
+    }
+
     fn write_default(&mut self, token: &Token) {
         self.prepare_token(token);
         self.output.push_str(&token.text);
@@ -1179,20 +1611,8 @@ impl<'a> Formatter<'a> {
 
         if !next_is_brace && !next_is_if {
             // Auto-insert block for single-statement else
-            self.output.push(' ');
-            self.output.push('{');
-            self.indent_level += 1;
-            self.push_newline();
-
-            self.braces.push(BraceContext {
-                kind: BraceKind::Block,
-                paren_depth_at_open: self.paren_depth,
-                bracket_depth_at_open: self.bracket_depth,
-                in_case_label: false,
-                case_body_indented: false,
-                is_synthetic: true,
-            });
-        } else if !next_is_inline_comment {
+            self.write_synthetic_open_brace(true);
+        } else if !next_is_inline_comment && (!next_is_brace || !self.next_line_braces()) {
             self.output.push(' ');
             self.needs_indent = false;
         }
@@ -1216,7 +1636,44 @@ impl<'a> Formatter<'a> {
         self.set_prev(token);
     }
 
-    fn write_blankline(&mut self) {
+    fn write_blankline(&mut self, token: &Token) {
+        if !self.in_switch_block() || !self.brackets.is_empty() {
+            self.write_single_blankline();
+            return;
+        }
+
+        // Labels sharing a body (`case 1:` `case 2:`) stay together
+        let after_case_label = self
+            .output
+            .trim_end_matches(['\n', ' ', '\t'])
+            .ends_with(':');
+        if after_case_label {
+            return;
+        }
+
+        let blank_lines = token
+            .preceding_whitespace
+            .matches('\n')
+            .count()
+            .saturating_sub(1);
+        let wanted = blank_lines.min(self.options.max_consecutive_blank_lines);
+        if wanted == 0 {
+            return;
+        }
+
+        if !self.output.ends_with('\n') {
+            self.push_newline();
+        }
+        let trailing_newlines = self.output.len() - self.output.trim_end_matches('\n').len();
+        for _ in trailing_newlines..=wanted {
+            self.output.push('\n');
+        }
+        self.needs_indent = true;
+        self.pending_space = false;
+        self.prev.clear();
+    }
+
+    fn write_single_blankline(&mut self) {
         // Skip blank lines inside array literals
         if !self.brackets.is_empty() {
             return;
@@ -1235,17 +1692,20 @@ impl<'a> Formatter<'a> {
 
     fn estimate_token_spacing(&self, prev_text: &str, token: &Token) -> usize {
         // No space before closers or punctuation that doesn't take a leading space
-        if matches!(token.text.as_str(), "]" | ")" | "}" | "," | "." | "::") {
+        if matches!(
+            token.text.as_str(),
+            "]" | ")" | "}" | "," | "." | "::" | "?."
+        ) {
             return 0;
         }
 
         // No space right after openers or member access
-        if matches!(prev_text, "[" | "(" | "{" | "." | "::") {
+        if matches!(prev_text, "[" | "(" | "{" | "." | "::" | "?.") {
             return 0;
         }
 
         // Space before operator tokens
-        if is_operator(&token.text) {
+        if is_operator(&token.text, self.options.dialect) {
             return 1;
         }
 
@@ -1255,7 +1715,7 @@ impl<'a> Formatter<'a> {
         }
 
         // Space after operator
-        if is_operator(prev_text) {
+        if is_operator(prev_text, self.options.dialect) {
             return 1;
         }
 
@@ -1361,6 +1821,62 @@ impl<'a> Formatter<'a> {
         length
     }
 
+    /// Token count and length of the `.a().b[0].c()` chain starting at this
+    /// `.`, or `None` unless it starts with a call and makes at least two.
+    fn estimate_chain(&self, token: &Token, remaining: &[Token]) -> Option<(usize, usize)> {
+        let starts_with_call = matches!(
+            remaining,
+            [name, open, ..] if name.kind == TokenKind::Identifier && open.text == "("
+        );
+        if !starts_with_call {
+            return None;
+        }
+
+        let mut length = token.text.len();
+        let mut prev_text = token.text.as_str();
+        let mut depth = 0;
+        let mut calls = 0;
+        let mut expect_name = true;
+        let mut chain_tokens = 0;
+
+        for (idx, token) in remaining.iter().enumerate() {
+            if token.kind == TokenKind::Blankline || token.kind == TokenKind::Comment {
+                continue;
+            }
+
+            if depth == 0 {
+                match token.text.as_str() {
+                    _ if expect_name => {
+                        if token.kind != TokenKind::Identifier {
+                            break;
+                        }
+                        expect_name = false;
+                    },
+                    "." | "?." => expect_name = true,
+                    "(" => {
+                        calls += 1;
+                        depth += 1;
+                    },
+                    "[" => depth += 1,
+                    _ => break,
+                }
+            } else {
+                match token.text.as_str() {
+                    "(" | "[" | "{" => depth += 1,
+                    ")" | "]" | "}" => depth -= 1,
+                    _ => {},
+                }
+            }
+
+            length += token.text.len();
+            length += self.estimate_token_spacing(prev_text, token);
+            prev_text = &token.text;
+            chain_tokens = idx + 1;
+        }
+
+        (calls >= 2).then_some((chain_tokens, length))
+    }
+
     fn estimate_ternary_length(&self, remaining: &[Token]) -> usize {
         let mut length = 0;
         let mut prev_text = "?";
@@ -1415,6 +1931,171 @@ impl<'a> Formatter<'a> {
     }
 }
 
+/// Pad trailing `//` comments so that each run of consecutive commented
+/// lines has its comments starting in the same column.
+fn align_trailing_comments(output: &str, tab_width: usize) -> String {
+    let lines: Vec<&str> = output.split('\n').collect();
+    // Lines inside `// fmt: off` regions are left as written
+    let mut frozen = false;
+    let mut state = LineState::Code;
+    let splits: Vec<Option<(&str, &str)>> = lines
+        .iter()
+        .map(|line| {
+            let starts_in_code = state == LineState::Code;
+            let split = split_trailing_comment(line, &mut state);
+            let directive = line
+                .find("//")
+                .filter(|_| starts_in_code)
+                .and_then(|i| fmt_directive(&line[i..]));
+            match directive {
+                Some(FmtDirective::On) => {
+                    frozen = false;
+                    None
+                },
+                _ if frozen => None,
+                Some(FmtDirective::Off) => {
+                    frozen = true;
+                    None
+                },
+                None => split,
+            }
+        })
+        .collect();
+
+    let mut result = Vec::with_capacity(lines.len());
+    let mut idx = 0;
+    while idx < lines.len() {
+        let run_end = (idx..lines.len())
+            .find(|&i| splits[i].is_none())
+            .unwrap_or(lines.len());
+        if run_end - idx < 2 {
+            result.push(lines[idx].to_string());
+            idx += 1;
+            continue;
+        }
+
+        let column = splits[idx..run_end]
+            .iter()
+            .flatten()
+            .map(|(code, _)| display_width(code, tab_width))
+            .max()
+            .unwrap_or(0)
+            + 1;
+        for (code, comment) in splits[idx..run_end].iter().flatten() {
+            let padding = column - display_width(code, tab_width);
+            result.push(format!("{code}{}{comment}", " ".repeat(padding)));
+        }
+        idx = run_end;
+    }
+
+    result.join("\n")
+}
+
+/// What a line of formatted output continues from the line before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LineState {
+    Code,
+    /// A verbatim `@"..."` string spanning lines
+    VerbatimString,
+    /// A `/* */` comment spanning lines
+    BlockComment,
+}
+
+/// Split a line into its code (without trailing whitespace) and a trailing
+/// `//` comment, updating `state` for the next line. Lines without code
+/// before the comment, lines holding a block comment and lines that start
+/// inside a multiline string or comment are not split.
+fn split_trailing_comment<'a>(line: &'a str, state: &mut LineState) -> Option<(&'a str, &'a str)> {
+    let mut splittable = *state == LineState::Code;
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+    let mut chars = line.char_indices().peekable();
+    while let Some((idx, ch)) = chars.next() {
+        match *state {
+            LineState::VerbatimString => {
+                // `""` is an escaped quote; backslashes have no meaning
+                if ch == '"' && chars.next_if(|&(_, next)| next == '"').is_none() {
+                    *state = LineState::Code;
+                }
+                continue;
+            },
+            LineState::BlockComment => {
+                if ch == '*' && chars.next_if(|&(_, next)| next == '/').is_some() {
+                    *state = LineState::Code;
+                }
+                continue;
+            },
+            LineState::Code => {},
+        }
+        if let Some(q) = quote {
+            if escaped {
+                escaped = false;
+            } else if ch == '\\' {
+                escaped = true;
+            } else if ch == q {
+                quote = None;
+            }
+            continue;
+        }
+        match ch {
+            '@' if chars.next_if(|&(_, next)| next == '"').is_some() => {
+                *state = LineState::VerbatimString;
+            },
+            '"' | '\'' => quote = Some(ch),
+            '/' if chars.peek().is_some_and(|&(_, next)| next == '/') => {
+                let code = line[..idx].trim_end();
+                if !splittable || code.trim_start().is_empty() {
+                    return None;
+                }
+                return Some((code, &line[idx..]));
+            },
+            '/' if chars.next_if(|&(_, next)| next == '*').is_some() => {
+                splittable = false;
+                *state = LineState::BlockComment;
+            },
+            _ => {},
+        }
+    }
+    None
+}
+
+/// End every line with `ending`, except lines inside a verbatim string,
+/// whose endings are part of the string's value.
+fn convert_line_endings(output: &str, ending: LineEnding) -> String {
+    let ending = if ending == LineEnding::Crlf {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let mut state = LineState::Code;
+    let mut result = String::with_capacity(output.len());
+    for line in output.split_inclusive('\n') {
+        let Some(content) = line.strip_suffix('\n') else {
+            result.push_str(line);
+            continue;
+        };
+        let content = content.strip_suffix('\r').unwrap_or(content);
+        split_trailing_comment(content, &mut state);
+        if state == LineState::VerbatimString {
+            result.push_str(line);
+        } else {
+            result.push_str(content);
+            result.push_str(ending);
+        }
+    }
+    result
+}
+
+fn display_width(text: &str, tab_width: usize) -> usize {
+    text.chars().fold(0, |width, ch| {
+        if ch == '\t' {
+            width + tab_width
+        } else {
+            width + 1
+        }
+    })
+}
+
 fn trim_trailing_whitespace(buffer: &mut String) {
     while matches!(buffer.chars().last(), Some(' ') | Some('\t') | Some('\r')) {
         buffer.pop();
@@ -1436,8 +2117,12 @@ fn collect_tokens(root: Node, source: &str) -> Result<Vec<Token>, FormatError> {
 
     loop {
         let node = cursor.node();
+        // Strings are emitted whole so the whitespace and blank lines between
+        // their parts (e.g. inside a multiline `@"..."`) survive byte-for-byte.
+        // Numbers too, in case a grammar splits `1.5` into parts.
+        let is_atomic = is_string_node_kind(node.kind()) || is_number_node_kind(node.kind());
 
-        if !visited_children && node.child_count() == 0 {
+        if !visited_children && (node.child_count() == 0 || is_atomic) {
             let start = node.start_byte();
             let mut preceded_by_newline = false;
             let mut preceding_whitespace = String::new();
@@ -1449,11 +2134,13 @@ fn collect_tokens(root: Node, source: &str) -> Result<Vec<Token>, FormatError> {
                     .count();
                 preceded_by_newline = newline_count > 0;
                 if newline_count >= 2 {
+                    // Keep the whitespace so the writer knows how many lines were blank
                     tokens.push(Token {
                         text: String::new(),
                         kind: TokenKind::Blankline,
                         preceded_by_newline: true,
-                        preceding_whitespace: String::new(),
+                        preceding_whitespace: preceding_whitespace.clone(),
+                        range: prev_end..start,
                     });
                 }
             }
@@ -1469,19 +2156,20 @@ fn collect_tokens(root: Node, source: &str) -> Result<Vec<Token>, FormatError> {
                 {
                     TokenKind::String
                 } else {
-                    classify_token(&node)
+                    classify_token(&node, &text)
                 };
                 tokens.push(Token {
                     kind,
                     text,
                     preceded_by_newline,
                     preceding_whitespace,
+                    range: start..node.end_byte(),
                 });
             }
             prev_end = node.end_byte();
         }
 
-        if !visited_children && cursor.goto_first_child() {
+        if !visited_children && !is_atomic && cursor.goto_first_child() {
             visited_children = false;
             continue;
         }
@@ -1501,7 +2189,111 @@ fn collect_tokens(root: Node, source: &str) -> Result<Vec<Token>, FormatError> {
     Ok(tokens)
 }
 
-fn classify_token(node: &Node) -> TokenKind {
+/// Join the token pairs that the Squirrel grammar splits a dialect's own
+/// operators into, such as `?` `.` for Quirrel's `?.`, back together.
+fn merge_dialect_operators(tokens: Vec<Token>, operators: &[&str]) -> Vec<Token> {
+    let mut merged: Vec<Token> = Vec::with_capacity(tokens.len());
+    for token in tokens {
+        if let Some(last) = merged.last_mut()
+            && token.preceding_whitespace.is_empty()
+            && operators
+                .iter()
+                .any(|op| op.strip_prefix(last.text.as_str()) == Some(token.text.as_str()))
+        {
+            last.text.push_str(&token.text);
+            last.kind = TokenKind::Symbol;
+            last.range.end = token.range.end;
+            continue;
+        }
+        if operators.contains(&token.text.as_str()) {
+            merged.push(Token {
+                kind: TokenKind::Symbol,
+                ..token
+            });
+            continue;
+        }
+        merged.push(token);
+    }
+    merged
+}
+
+/// Fold each `</ ... />` attribute block into a single token, either copied
+/// from the source or respaced. Blocks holding a comment, or missing their
+/// `/>`, are always copied.
+fn merge_attribute_blocks(tokens: Vec<Token>, source: &str, preserve: bool) -> Vec<Token> {
+    let mut merged = Vec::with_capacity(tokens.len());
+    let mut rest = tokens.as_slice();
+    while let Some((token, tail)) = rest.split_first() {
+        let close = (token.text == "</")
+            .then(|| tail.iter().position(|t| t.text == "/>"))
+            .flatten();
+        let Some(close) = close else {
+            merged.push(token.clone());
+            rest = tail;
+            continue;
+        };
+
+        let inner = &tail[..close];
+        let range = token.range.start..tail[close].range.end;
+        let verbatim = preserve
+            || inner
+                .iter()
+                .any(|t| matches!(t.kind, TokenKind::Comment | TokenKind::Blankline));
+        merged.push(Token {
+            text: if verbatim {
+                source[range.clone()].to_string()
+            } else {
+                respace_attribute_block(inner)
+            },
+            kind: TokenKind::Attribute,
+            range,
+            ..token.clone()
+        });
+        rest = &tail[close + 1..];
+    }
+    merged
+}
+
+/// `</ key = value, ... />` with single spaces between the parts, none
+/// inside brackets or before commas, and none after a unary minus or `!`.
+fn respace_attribute_block(inner: &[Token]) -> String {
+    let mut text = String::from("</");
+    let mut prev = "</";
+    let mut glued = false;
+    for token in inner {
+        let current = token.text.as_str();
+        let spaced =
+            !glued && !matches!(current, "," | ")" | "]" | ".") && !matches!(prev, "(" | "[" | ".");
+        if spaced {
+            text.push(' ');
+        }
+        text.push_str(current);
+        glued = matches!(current, "-" | "!") && matches!(prev, "</" | "=" | "," | "(" | "[");
+        prev = current;
+    }
+    text.push_str(" />");
+    text
+}
+
+#[derive(Clone, Copy)]
+enum FmtDirective {
+    Off,
+    On,
+}
+
+/// `// fmt: off` or `// fmt: on`, ignoring the spacing inside the comment.
+fn fmt_directive(comment: &str) -> Option<FmtDirective> {
+    let body = comment.trim().strip_prefix("//")?;
+    let compact: String = body.chars().filter(|c| !c.is_whitespace()).collect();
+    match compact.as_str() {
+        "fmt:off" => Some(FmtDirective::Off),
+        "fmt:on" => Some(FmtDirective::On),
+        _ => None,
+    }
+}
+
+/// Kind of a leaf node whose source is `text`.
+fn classify_token(node: &Node, text: &str) -> TokenKind {
     let kind = node.kind();
 
     if node.is_extra() || kind.contains("comment") {
@@ -1514,15 +2306,30 @@ fn classify_token(node: &Node) -> TokenKind {
 
     match kind {
         "identifier" => TokenKind::Identifier,
-        "number" | "integer" | "float" | "float_literal" | "integer_literal" => TokenKind::Number,
-        "string" | "string_literal" | "raw_string" => TokenKind::String,
+        _ if is_number_node_kind(kind) => TokenKind::Number,
+        "string" | "string_literal" | "raw_string" | "verbatim_string" => TokenKind::String,
+        // Grammar versions disagree on what to call hex, octal and exponent
+        // literals, but every numeric literal starts with a digit
+        _ if node.child_count() == 0 && text.starts_with(|c: char| c.is_ascii_digit()) => {
+            TokenKind::Number
+        },
         _ if node.is_named() => TokenKind::Other,
         _ => TokenKind::Symbol,
     }
 }
 
+fn is_number_node_kind(kind: &str) -> bool {
+    matches!(
+        kind,
+        "number" | "integer" | "float" | "float_literal" | "integer_literal"
+    )
+}
+
 fn is_string_node_kind(kind: &str) -> bool {
-    matches!(kind, "string" | "string_literal" | "raw_string")
+    matches!(
+        kind,
+        "string" | "string_literal" | "raw_string" | "verbatim_string"
+    )
 }
 
 fn is_keyword_kind(kind: &str) -> bool {
@@ -1575,7 +2382,7 @@ fn is_block_introducing_keyword(text: &str) -> bool {
     )
 }
 
-fn needs_space(prev: Option<&PrevToken>, current: &Token) -> bool {
+fn needs_space(prev: Option<&PrevToken>, current: &Token, dialect: Dialect) -> bool {
     let prev = match prev {
         Some(prev) => prev,
         None => return false,
@@ -1591,7 +2398,7 @@ fn needs_space(prev: Option<&PrevToken>, current: &Token) -> bool {
         return false;
     }
 
-    if matches!(prev_text, "(" | "[" | "{" | "." | "::") {
+    if matches!(prev_text, "(" | "[" | "{" | "." | "::" | "?.") {
         return false;
     }
 
@@ -1603,7 +2410,7 @@ fn needs_space(prev: Option<&PrevToken>, current: &Token) -> bool {
         return true;
     }
 
-    if curr_text == "." {
+    if curr_text == "." || curr_text == "?." {
         return prev.kind == TokenKind::Keyword;
     }
 
@@ -1614,7 +2421,7 @@ fn needs_space(prev: Option<&PrevToken>, current: &Token) -> bool {
     if curr_text == "{" {
         return matches!(
             prev.kind,
-            TokenKind::Identifier | TokenKind::Other | TokenKind::Keyword
+            TokenKind::Identifier | TokenKind::Other | TokenKind::Keyword | TokenKind::Attribute
         ) || prev_text == ")";
     }
 
@@ -1622,7 +2429,7 @@ fn needs_space(prev: Option<&PrevToken>, current: &Token) -> bool {
         return false;
     }
 
-    if is_operator(curr_text) || is_operator(prev_text) {
+    if is_operator(curr_text, dialect) || is_operator(prev_text, dialect) {
         return true;
     }
 
@@ -1672,7 +2479,9 @@ fn keyword_requires_space_before_paren(text: &str) -> bool {
     )
 }
 
-fn is_operator(text: &str) -> bool {
+/// Whether `text` is an operator spaced on both sides, including the
+/// dialect's binary operators.
+fn is_operator(text: &str, dialect: Dialect) -> bool {
     matches!(
         text,
         "=" | "+"
@@ -1709,14 +2518,14 @@ fn is_operator(text: &str) -> bool {
             | "^="
             | "in"
             | "instanceof"
-    )
+    ) || dialect.binary_operators().contains(&text)
 }
 
 fn is_unary_operator(text: &str) -> bool {
     matches!(text, "-" | "+" | "!" | "~")
 }
 
-fn is_unary_context(prev: Option<&PrevToken>) -> bool {
+fn is_unary_context(prev: Option<&PrevToken>, dialect: Dialect) -> bool {
     match prev {
         None => true,
         Some(prev) => {
@@ -1746,7 +2555,7 @@ fn is_unary_context(prev: Option<&PrevToken>) -> bool {
                     | "^"
                     | "?"
                     | ":"
-            ) || is_operator(text)
+            ) || is_operator(text, dialect)
                 || matches!(prev.kind, TokenKind::Keyword)
         },
     }
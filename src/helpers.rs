@@ -1,4 +1,6 @@
-use tower_lsp::lsp_types::Position;
+use std::path::PathBuf;
+
+use tower_lsp::lsp_types::{CodeDescription, Diagnostic, NumberOrString, Position, Url};
 use tree_sitter::{Node, Parser, Tree};
 
 use crate::errors::AnalysisError;
@@ -67,30 +69,172 @@ pub(crate) fn byte_offset_at(text: &str, position: Position) -> Option<usize> {
     }
 }
 
+/// Page explaining every diagnostic code, with one anchor per code
+const RULES_URL: &str = "https://github.com/mnshdw/squirrel-lsp/blob/main/docs/rules.md";
+
+/// An otherwise empty diagnostic carrying `code` and a link to its
+/// explanation, meant to be completed with `..coded_diagnostic(code)`.
+pub fn coded_diagnostic(code: &str) -> Diagnostic {
+    Diagnostic {
+        code: Some(NumberOrString::String(code.to_string())),
+        code_description: Url::parse(&format!("{RULES_URL}#{code}"))
+            .ok()
+            .map(|href| CodeDescription { href }),
+        ..Diagnostic::default()
+    }
+}
+
+/// The file behind a `file:` URI. Other schemes, such as `untitled:` buffers,
+/// have no path to derive a script path from and yield `None`.
+pub fn resolve_file_path(uri: &Url) -> Option<PathBuf> {
+    if uri.scheme() != "file" {
+        return None;
+    }
+    uri.to_file_path().ok()
+}
+
 /// Get the text content of a tree-sitter node
 pub fn node_text<'a>(node: Node, text: &'a str) -> &'a str {
     node.utf8_text(text.as_bytes()).unwrap_or("")
 }
 
-/// Extract string content from a string literal node.
+/// Extract the value of a string literal node, with its quotes removed and
+/// escape sequences resolved.
 ///
-/// Tries to find a `string_content` child first, then falls back to
-/// trimming quotes from the node text.
+/// `"scripts\\skills"` yields `scripts\skills`, and a verbatim `@"a""b"`
+/// yields `a"b`. Text that isn't a quoted literal is returned as is.
 pub fn extract_string_content(node: Node, text: &str) -> String {
-    for child in node.children(&mut node.walk()) {
-        if child.kind() == "string_content" {
-            return node_text(child, text).to_string();
+    let s = node_text(node, text);
+    if let Some(body) = s
+        .strip_prefix("@\"")
+        .and_then(|rest| rest.strip_suffix('"'))
+    {
+        return body.replace("\"\"", "\"");
+    }
+    match s.chars().next() {
+        Some(quote @ ('"' | '\'')) if s.len() >= 2 && s.ends_with(quote) => {
+            unescape(&s[1..s.len() - 1])
+        },
+        _ => s.to_string(),
+    }
+}
+
+/// Resolve the backslash escapes of a quoted string body. `\x` takes up to
+/// two hex digits; unknown escapes keep the escaped character.
+fn unescape(body: &str) -> String {
+    let mut result = String::with_capacity(body.len());
+    let mut chars = body.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            result.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('r') => result.push('\r'),
+            Some('0') => result.push('\0'),
+            Some('a') => result.push('\u{7}'),
+            Some('b') => result.push('\u{8}'),
+            Some('f') => result.push('\u{c}'),
+            Some('v') => result.push('\u{b}'),
+            Some('x') => {
+                let digits: String = chars
+                    .clone()
+                    .take(2)
+                    .take_while(|c| c.is_ascii_hexdigit())
+                    .collect();
+                match u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                {
+                    Some(decoded) => {
+                        result.push(decoded);
+                        chars.nth(digits.len() - 1);
+                    },
+                    None => result.push('x'),
+                }
+            },
+            Some(escaped) => result.push(escaped),
+            None => result.push('\\'),
         }
     }
-    // Fallback: trim quotes
-    let s = node_text(node, text);
-    if s.starts_with('"') && s.ends_with('"') && s.len() >= 2 {
-        s[1..s.len() - 1].to_string()
-    } else {
-        s.to_string()
+    result
+}
+
+/// Find the name of a `parameter` node, skipping any default value.
+///
+/// `function f(x = y)` has the parameter `x`; `y` belongs to the default.
+pub fn parameter_name(param: Node) -> Option<Node> {
+    match param.kind() {
+        "identifier" => Some(param),
+        "parameter" => param
+            .children(&mut param.walk())
+            .take_while(|c| c.kind() != "=")
+            .find(|c| c.kind() == "identifier"),
+        _ => None,
     }
 }
 
+/// The default value expression of a `parameter` node, if it has one.
+pub fn parameter_default(param: Node) -> Option<Node> {
+    param
+        .children(&mut param.walk())
+        .skip_while(|c| c.kind() != "=")
+        .find(|c| c.is_named())
+}
+
+/// Whether a `parameters` node declares varargs (`function f(a, ...)`).
+pub fn has_varargs(params: Node) -> bool {
+    params.children(&mut params.walk()).any(|c| {
+        c.kind() == "..."
+            || (c.kind() == "parameter" && c.children(&mut c.walk()).any(|p| p.kind() == "..."))
+    })
+}
+
+/// The key of a `table_slot` written as `name = value` or `"name" = value`;
+/// `extract_string_content` turns either into the slot name.
+///
+/// Computed `[expr]` keys have no static name, and `function name()` slots
+/// carry their name inside the function node, so neither has a key here.
+pub fn table_slot_key(slot: Node) -> Option<Node> {
+    slot.child(0)
+        .filter(|key| matches!(key.kind(), "identifier" | "string"))
+}
+
+/// Split a `base.m.field` read into its `base` expression and `field`.
+///
+/// `base.m.field <- value` creates the slot rather than reading it, so its
+/// left side is not matched.
+pub fn m_field_access<'tree>(node: Node<'tree>, text: &str) -> Option<(Node<'tree>, Node<'tree>)> {
+    if node.kind() != "deref_expression" || node.named_child_count() != 2 {
+        return None;
+    }
+    let inner = node.named_child(0)?;
+    let field = node.named_child(1)?;
+    if field.kind() != "identifier"
+        || inner.kind() != "deref_expression"
+        || inner.named_child_count() != 2
+        || inner
+            .named_child(1)
+            .is_none_or(|m| node_text(m, text) != "m")
+    {
+        return None;
+    }
+    if let Some(parent) = node.parent()
+        && parent.kind() == "update_expression"
+        && parent
+            .named_child(0)
+            .is_some_and(|left| left.id() == node.id())
+        && parent
+            .children(&mut parent.walk())
+            .any(|c| c.kind() == "<-")
+    {
+        return None;
+    }
+    Some((inner.named_child(0)?, field))
+}
+
 /// Extract the name from an identifier or deref_expression node.
 ///
 /// For `identifier` nodes, returns the identifier text directly.
@@ -124,3 +268,58 @@ pub fn find_last_identifier(node: Node) -> Option<Node> {
         _ => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn first_string_value(code: &str) -> String {
+        let tree = parse_squirrel(code).unwrap();
+        let mut cursor = tree.root_node().walk();
+        let mut pending = vec![tree.root_node()];
+        while let Some(node) = pending.pop() {
+            if matches!(node.kind(), "string" | "verbatim_string") {
+                return extract_string_content(node, code);
+            }
+            pending.extend(
+                node.children(&mut cursor)
+                    .collect::<Vec<_>>()
+                    .into_iter()
+                    .rev(),
+            );
+        }
+        panic!("no string literal in {code}");
+    }
+
+    #[test]
+    fn test_resolve_file_path() {
+        let file = Url::parse("file:///mod/scripts/skills/skill.nut").unwrap();
+        assert_eq!(
+            resolve_file_path(&file),
+            Some(PathBuf::from("/mod/scripts/skills/skill.nut"))
+        );
+        let untitled = Url::parse("untitled:Untitled-1").unwrap();
+        assert_eq!(resolve_file_path(&untitled), None);
+    }
+
+    #[test]
+    fn test_extract_string_content_unescapes() {
+        assert_eq!(
+            first_string_value(r#"local p = "scripts/skills/skill";"#),
+            "scripts/skills/skill"
+        );
+        assert_eq!(
+            first_string_value(r#"local p = "skills\\skill";"#),
+            "skills\\skill"
+        );
+        assert_eq!(
+            first_string_value(r#"local p = "say \"hi\"\n";"#),
+            "say \"hi\"\n"
+        );
+        assert_eq!(first_string_value(r#"local p = "\x41b";"#), "Ab");
+        assert_eq!(
+            first_string_value(r#"local p = @"C:\mods\""x""";"#),
+            "C:\\mods\\\"x\""
+        );
+    }
+}
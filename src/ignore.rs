@@ -0,0 +1,145 @@
+//! Gitignore-style path exclusion for workspace indexing.
+//!
+//! Patterns come from a `.squirrelignore` file at a workspace folder root
+//! and from the `exclude` server option. Supported syntax: `#` comments,
+//! `!` negation, a trailing `/` for directory-only rules, a leading or
+//! inner `/` to anchor to the root, and the `*`, `?` and `**` wildcards.
+
+use std::path::Path;
+
+/// Name of the ignore file read from each workspace folder root.
+pub const IGNORE_FILE: &str = ".squirrelignore";
+
+#[derive(Debug, Clone)]
+struct Rule {
+    pattern: Vec<char>,
+    negated: bool,
+    dir_only: bool,
+    /// Match against the whole relative path rather than just the file name
+    anchored: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreRules {
+    rules: Vec<Rule>,
+}
+
+impl IgnoreRules {
+    /// Parse the contents of an ignore file, one pattern per line.
+    pub fn parse(source: &str) -> Self {
+        let mut rules = Self::default();
+        for line in source.lines() {
+            rules.add(line);
+        }
+        rules
+    }
+
+    /// Add a single pattern. Blank lines and comments are ignored.
+    pub fn add(&mut self, line: &str) {
+        let mut pattern = line.trim();
+        if pattern.is_empty() || pattern.starts_with('#') {
+            return;
+        }
+
+        let negated = pattern.starts_with('!');
+        if negated {
+            pattern = &pattern[1..];
+        }
+        let dir_only = pattern.ends_with('/');
+        let pattern = pattern.trim_end_matches('/');
+        let anchored = pattern.contains('/');
+        let pattern = pattern.trim_start_matches('/');
+        if pattern.is_empty() {
+            return;
+        }
+
+        self.rules.push(Rule {
+            pattern: pattern.chars().collect(),
+            negated,
+            dir_only,
+            anchored,
+        });
+    }
+
+    /// Whether `relative` (a path below the workspace folder root) should be
+    /// skipped. The last matching rule wins, so `!` can re-include a path.
+    pub fn is_ignored(&self, relative: &Path, is_dir: bool) -> bool {
+        let path: Vec<char> = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/")
+            .chars()
+            .collect();
+        let name: Vec<char> = relative
+            .file_name()
+            .map(|n| n.to_string_lossy().chars().collect())
+            .unwrap_or_default();
+
+        let mut ignored = false;
+        for rule in &self.rules {
+            if rule.dir_only && !is_dir {
+                continue;
+            }
+            let target = if rule.anchored { &path } else { &name };
+            if glob_match(&rule.pattern, target) {
+                ignored = !rule.negated;
+            }
+        }
+        ignored
+    }
+}
+
+/// Match `text` against a glob where `*` and `?` stay within one path
+/// segment and `**` spans any number of segments.
+fn glob_match(pattern: &[char], text: &[char]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        ['*', '*', '/', rest @ ..] => {
+            // `**/` also matches zero directories
+            glob_match(rest, text)
+                || text
+                    .iter()
+                    .enumerate()
+                    .any(|(i, &c)| c == '/' && glob_match(rest, &text[i + 1..]))
+        },
+        ['*', '*', rest @ ..] => (0..=text.len()).any(|i| glob_match(rest, &text[i..])),
+        ['*', rest @ ..] => {
+            let segment_end = text.iter().position(|&c| c == '/').unwrap_or(text.len());
+            (0..=segment_end).any(|i| glob_match(rest, &text[i..]))
+        },
+        ['?', rest @ ..] => text.first().is_some_and(|&c| c != '/') && glob_match(rest, &text[1..]),
+        [c, rest @ ..] => text.first() == Some(c) && glob_match(rest, &text[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unanchored_pattern_matches_any_depth() {
+        let rules = IgnoreRules::parse("# generated\n*.gen.nut\nvendor/\n");
+        assert!(rules.is_ignored(Path::new("scripts/a.gen.nut"), false));
+        assert!(rules.is_ignored(Path::new("mod/vendor"), true));
+        assert!(!rules.is_ignored(Path::new("mod/vendor"), false));
+        assert!(!rules.is_ignored(Path::new("scripts/a.nut"), false));
+    }
+
+    #[test]
+    fn test_anchored_and_double_star_patterns() {
+        let rules = IgnoreRules::parse("/build\nscripts/**/test_*.nut\n");
+        assert!(rules.is_ignored(Path::new("build"), true));
+        assert!(!rules.is_ignored(Path::new("mod/build"), true));
+        assert!(rules.is_ignored(Path::new("scripts/test_a.nut"), false));
+        assert!(rules.is_ignored(Path::new("scripts/x/y/test_a.nut"), false));
+        assert!(!rules.is_ignored(Path::new("scripts/x/a.nut"), false));
+    }
+
+    #[test]
+    fn test_negation_reincludes_path() {
+        let rules = IgnoreRules::parse("*.nut\n!keep.nut\n");
+        assert!(rules.is_ignored(Path::new("drop.nut"), false));
+        assert!(!rules.is_ignored(Path::new("keep.nut"), false));
+    }
+}
@@ -1,12 +1,152 @@
+//! Squirrel language analysis and formatting, as used by the `squirrel-lsp`
+//! server and CLI.
+//!
+//! [`analyze_document`] runs every check the server publishes for a file;
+//! the modules expose the individual analyses, the formatter and the
+//! workspace index.
+
 pub mod bb_support;
+pub mod cli;
 pub mod code_actions;
+pub mod colors;
+pub mod completion;
+pub mod config;
 pub mod errors;
+pub mod folding;
 pub mod formatter;
 pub mod helpers;
+pub mod ignore;
 pub mod navigation;
+pub mod rename;
 pub mod semantic_analyzer;
 pub mod symbol_extractor;
 pub mod symbol_resolver;
 pub mod symbols;
 pub mod syntax_analyzer;
 pub mod workspace;
+
+use tower_lsp::lsp_types::Diagnostic;
+use tree_sitter::Tree;
+
+use crate::bb_support::{
+    analyze_hook_overrides_with_tree, analyze_hooks_with_tree, analyze_inheritance_with_tree,
+};
+use crate::config::ServerConfig;
+use crate::errors::AnalysisError;
+use crate::symbol_resolver::compute_symbol_diagnostics_with_tree;
+use crate::syntax_analyzer::compute_syntax_diagnostics_with_tree;
+use crate::workspace::Workspace;
+
+/// Every diagnostic for one document, with default settings: syntax
+/// errors, semantic analysis, hook validation and inheritance checks.
+///
+/// `workspace` should already be indexed, with its inheritance graph built,
+/// for the hook and inheritance checks to find the files they refer to.
+///
+/// Analyses that fail are reported next to the diagnostics the others found.
+pub fn analyze_document(
+    file_path: &str,
+    text: &str,
+    workspace: &Workspace,
+) -> (Vec<Diagnostic>, Vec<AnalysisError>) {
+    match helpers::parse_squirrel(text) {
+        Ok(tree) => {
+            analyze_document_with_tree(file_path, &tree, text, workspace, &ServerConfig::default())
+        },
+        Err(err) => (Vec::new(), vec![err]),
+    }
+}
+
+/// Like `analyze_document`, but reuses an already parsed tree and applies
+/// the server's settings.
+pub fn analyze_document_with_tree(
+    file_path: &str,
+    tree: &Tree,
+    text: &str,
+    workspace: &Workspace,
+    config: &ServerConfig,
+) -> (Vec<Diagnostic>, Vec<AnalysisError>) {
+    let mut diags = compute_syntax_diagnostics_with_tree(tree, text);
+    let mut errors = Vec::new();
+    match compute_symbol_diagnostics_with_tree(file_path, tree, text, workspace, config) {
+        Ok(symbol_diags) => diags.extend(symbol_diags),
+        Err(err) => errors.push(err),
+    }
+    diags.extend(analyze_hooks_with_tree(tree, text, workspace));
+    if config.override_hints {
+        diags.extend(analyze_hook_overrides_with_tree(
+            tree,
+            text,
+            workspace,
+            &config.override_allowlist,
+        ));
+    }
+    diags.extend(analyze_inheritance_with_tree(tree, text, workspace));
+    (diags, errors)
+}
+
+/// Diagnostics for a document that isn't a file, such as an `untitled:`
+/// buffer: syntax errors and semantic analysis against the workspace's
+/// globals. Hook and inheritance checks need a script path and are skipped.
+pub fn analyze_standalone_document(
+    tree: &Tree,
+    text: &str,
+    workspace: &Workspace,
+    config: &ServerConfig,
+) -> (Vec<Diagnostic>, Vec<AnalysisError>) {
+    let diags = compute_syntax_diagnostics_with_tree(tree, text);
+    match compute_symbol_diagnostics_with_tree("", tree, text, workspace, config) {
+        Ok(symbol_diags) => (diags.into_iter().chain(symbol_diags).collect(), Vec::new()),
+        Err(err) => (diags, vec![err]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    #[test]
+    fn test_analyze_document_runs_every_analysis() {
+        let mut workspace = Workspace::new();
+        workspace
+            .index_file(
+                Path::new("/test/scripts/skills/skill.nut"),
+                "this.skill <- this.inherit(\"scripts/skills/base\", {\n    function onUse() {}\n});\n",
+            )
+            .unwrap();
+        workspace.build_inheritance_graph();
+
+        let text = "::mods_hookExactClass(\"skills/skill\", function(o) {\n    local use = o.onUsed;\n    print(missing);\n});\nlocal broken = (;\n";
+        let (diagnostics, errors) =
+            analyze_document("/test/scripts/mods/hook.nut", text, &workspace);
+        assert!(errors.is_empty(), "{errors:?}");
+        let mut sources: Vec<_> = diagnostics
+            .iter()
+            .filter_map(|d| d.source.as_deref())
+            .collect();
+        sources.sort_unstable();
+        sources.dedup();
+        assert_eq!(
+            sources,
+            vec!["squirrel-bb-hook", "squirrel-parser", "squirrel-semantic"]
+        );
+    }
+
+    #[test]
+    fn test_standalone_document_skips_workspace_checks() {
+        let text = "::mods_hookExactClass(\"skills/missing\", function(o) {\n    print(missing);\n});\nlocal broken = (;\n";
+        let tree = helpers::parse_squirrel(text).unwrap();
+        let (diagnostics, errors) =
+            analyze_standalone_document(&tree, text, &Workspace::new(), &ServerConfig::default());
+        assert!(errors.is_empty(), "{errors:?}");
+        let mut sources: Vec<_> = diagnostics
+            .iter()
+            .filter_map(|d| d.source.as_deref())
+            .collect();
+        sources.sort_unstable();
+        sources.dedup();
+        assert_eq!(sources, vec!["squirrel-parser", "squirrel-semantic"]);
+    }
+}
@@ -1,51 +1,116 @@
-mod bb_support;
-mod code_actions;
-mod errors;
-mod formatter;
-mod helpers;
-mod navigation;
-mod semantic_analyzer;
-mod symbol_extractor;
-mod symbol_resolver;
-mod symbols;
-mod syntax_analyzer;
-mod workspace;
-
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use bb_support::{analyze_hooks, analyze_inheritance};
-use code_actions::generate_code_actions;
-use formatter::{FormatError, FormatOptions, IndentStyle, format_document};
-use symbol_resolver::compute_symbol_diagnostics_with_globals;
+use serde_json::{Value, json};
+use tokio::net::{TcpListener, TcpStream};
 use tokio::sync::RwLock;
 use tower_lsp::jsonrpc::Result;
+use tower_lsp::lsp_types::notification::Progress;
+use tower_lsp::lsp_types::request::{
+    GotoDeclarationParams, GotoDeclarationResponse, GotoImplementationParams,
+    GotoImplementationResponse, WorkDoneProgressCreate,
+};
 use tower_lsp::lsp_types::{
     CodeActionKind, CodeActionOptions, CodeActionOrCommand, CodeActionParams,
-    CodeActionProviderCapability, CodeActionResponse, DidChangeTextDocumentParams,
-    DidCloseTextDocumentParams, DidOpenTextDocumentParams, DocumentFormattingParams,
-    DocumentSymbolParams, DocumentSymbolResponse, GotoDefinitionParams, GotoDefinitionResponse,
-    InitializeParams, InitializeResult, MessageType, OneOf, Position, Range, SemanticTokenModifier,
-    SemanticTokenType, SemanticTokens, SemanticTokensFullOptions, SemanticTokensLegend,
-    SemanticTokensOptions, SemanticTokensParams, SemanticTokensResult,
+    CodeActionProviderCapability, CodeActionResponse, ColorInformation, ColorPresentation,
+    ColorPresentationParams, ColorProviderCapability, CompletionOptions, CompletionParams,
+    CompletionResponse, DeclarationCapability, DidChangeConfigurationParams,
+    DidChangeTextDocumentParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams,
+    DidSaveTextDocumentParams, DocumentColorParams, DocumentFormattingParams, DocumentLink,
+    DocumentLinkOptions, DocumentLinkParams, DocumentSymbolParams, DocumentSymbolResponse,
+    ExecuteCommandOptions, ExecuteCommandParams, FoldingRange, FoldingRangeParams,
+    FoldingRangeProviderCapability, GotoDefinitionParams, GotoDefinitionResponse, Hover,
+    HoverContents, HoverParams, HoverProviderCapability, ImplementationProviderCapability,
+    InitializeParams, InitializeResult, MarkupContent, MarkupKind, MessageType, NumberOrString,
+    OneOf, Position, ProgressParams, ProgressParamsValue, Range, RenameParams, SemanticToken,
+    SemanticTokenModifier, SemanticTokenType, SemanticTokens, SemanticTokensDelta,
+    SemanticTokensDeltaParams, SemanticTokensFullDeltaResult, SemanticTokensFullOptions,
+    SemanticTokensLegend, SemanticTokensOptions, SemanticTokensParams, SemanticTokensResult,
     SemanticTokensServerCapabilities, ServerCapabilities, ServerInfo, SymbolInformation,
     TextDocumentContentChangeEvent, TextDocumentSyncCapability, TextDocumentSyncKind,
-    TextDocumentSyncOptions, TextEdit, Url, WorkspaceSymbolParams,
+    TextDocumentSyncOptions, TextDocumentSyncSaveOptions, TextEdit, Url, WorkDoneProgress,
+    WorkDoneProgressBegin, WorkDoneProgressCreateParams, WorkDoneProgressEnd,
+    WorkDoneProgressOptions, WorkDoneProgressReport, WorkspaceEdit, WorkspaceSymbolParams,
 };
 use tower_lsp::{Client, LanguageServer, LspService, Server, async_trait};
-use workspace::Workspace;
+use tree_sitter::Tree;
 
-use crate::semantic_analyzer::compute_semantic_tokens;
-use crate::syntax_analyzer::compute_syntax_diagnostics;
+use squirrel_lsp::code_actions::{generate_code_actions, generate_refactor_actions};
+use squirrel_lsp::colors::{color_presentations, compute_document_colors};
+use squirrel_lsp::completion::script_path_completions;
+use squirrel_lsp::config::{DiagnosticsMode, ServerConfig};
+use squirrel_lsp::folding::compute_folding_ranges;
+use squirrel_lsp::formatter::{
+    FormatError, FormatOptions, IndentStyle, debug_tokens, format_document,
+};
+use squirrel_lsp::helpers::resolve_file_path;
+use squirrel_lsp::ignore::{IGNORE_FILE, IgnoreRules};
+use squirrel_lsp::semantic_analyzer::{compute_semantic_tokens_with_tree, diff_semantic_tokens};
+use squirrel_lsp::syntax_analyzer::compute_syntax_diagnostics_with_tree;
+use squirrel_lsp::workspace::Workspace;
+use squirrel_lsp::{
+    analyze_document_with_tree, analyze_standalone_document, cli, helpers, navigation, rename,
+};
 
 struct Backend {
     client: Client,
-    documents: Arc<RwLock<HashMap<Url, String>>>,
+    /// Open documents with the tree parsed from their current text
+    documents: Arc<RwLock<HashMap<Url, (String, Tree)>>>,
     workspace: Arc<RwLock<Workspace>>,
     workspace_folders: Arc<RwLock<Vec<PathBuf>>>,
+    config: Arc<RwLock<ServerConfig>>,
+    /// Whether the client accepts server-initiated `window/workDoneProgress`
+    progress_supported: Arc<RwLock<bool>>,
+    /// Counts from the most recent workspace index
+    last_index: Arc<RwLock<IndexStats>>,
+    /// Semantic tokens last sent for each document, for delta requests
+    semantic_tokens: Arc<RwLock<SemanticTokensCache>>,
+}
+
+#[derive(Debug, Default)]
+struct SemanticTokensCache {
+    next_id: u64,
+    /// Document -> result id and the tokens sent under it
+    results: HashMap<Url, (String, Vec<SemanticToken>)>,
+}
+
+impl SemanticTokensCache {
+    /// Remember `tokens` as the latest result for `uri` and return its id.
+    fn store(&mut self, uri: Url, tokens: Vec<SemanticToken>) -> String {
+        self.next_id += 1;
+        let result_id = self.next_id.to_string();
+        self.results.insert(uri, (result_id.clone(), tokens));
+        result_id
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct IndexStats {
+    /// Script files found under the workspace folders
+    found: usize,
+    indexed: usize,
+    /// Files that failed to parse
+    errors: usize,
+    /// Files skipped for exceeding `maxFileSizeKb`
+    oversized: usize,
 }
 
+/// Files indexed between two progress reports
+const PROGRESS_INTERVAL: usize = 50;
+
+/// `workspace/executeCommand` id that rebuilds the index from scratch
+const REINDEX_COMMAND: &str = "squirrel-lsp.reindex";
+/// `workspace/executeCommand` id describing a class given its script path
+const CLASS_INFO_COMMAND: &str = "squirrel-lsp.classInfo";
+/// `workspace/executeCommand` id reporting index size and errors
+const STATUS_COMMAND: &str = "squirrel-lsp.status";
+/// `workspace/executeCommand` id listing the formatter's tokens for a document
+const DEBUG_TOKENS_COMMAND: &str = "squirrel-lsp.debugTokens";
+/// `workspace/executeCommand` id locating the parent class's version of the
+/// method at a position
+const GO_TO_SUPER_COMMAND: &str = "squirrel-lsp.goToSuper";
+
 impl Backend {
     fn new(client: Client) -> Self {
         Self {
@@ -53,22 +118,37 @@ impl Backend {
             documents: Arc::new(RwLock::new(HashMap::new())),
             workspace: Arc::new(RwLock::new(Workspace::new())),
             workspace_folders: Arc::new(RwLock::new(Vec::new())),
+            config: Arc::new(RwLock::new(ServerConfig::default())),
+            progress_supported: Arc::new(RwLock::new(false)),
+            last_index: Arc::new(RwLock::new(IndexStats::default())),
+            semantic_tokens: Arc::new(RwLock::new(SemanticTokensCache::default())),
         }
     }
 
-    /// Recursively find all .nut files in a directory
-    fn find_nut_files(dir: &Path) -> Vec<PathBuf> {
+    /// Recursively find all Squirrel files in a directory, skipping paths
+    /// (relative to `root`) that match `ignore`
+    fn find_script_files(
+        root: &Path,
+        dir: &Path,
+        ignore: &IgnoreRules,
+        config: &ServerConfig,
+    ) -> Vec<PathBuf> {
         let mut files = Vec::new();
         if let Ok(entries) = std::fs::read_dir(dir) {
             for entry in entries.flatten() {
                 let path = entry.path();
-                if path.is_dir() {
+                let is_dir = path.is_dir();
+                let relative = path.strip_prefix(root).unwrap_or(&path);
+                if ignore.is_ignored(relative, is_dir) {
+                    continue;
+                }
+                if is_dir {
                     // Skip common non-source directories
                     let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
                     if !name.starts_with('.') && name != "node_modules" && name != "target" {
-                        files.extend(Self::find_nut_files(&path));
+                        files.extend(Self::find_script_files(root, &path, ignore, config));
                     }
-                } else if path.extension().is_some_and(|ext| ext == "nut") {
+                } else if config.is_script_file(&path) {
                     files.push(path);
                 }
             }
@@ -76,6 +156,68 @@ impl Backend {
         files
     }
 
+    /// Ignore rules for a workspace folder: its `.squirrelignore`, if any,
+    /// followed by the `exclude` option so client settings win on conflict.
+    fn ignore_rules(folder: &Path, exclude: &[String]) -> IgnoreRules {
+        let mut rules = std::fs::read_to_string(folder.join(IGNORE_FILE))
+            .map(|source| IgnoreRules::parse(&source))
+            .unwrap_or_default();
+        for pattern in exclude {
+            rules.add(pattern);
+        }
+        rules
+    }
+
+    /// Resolve the `scriptRoot` option: absolute roots are kept as they are,
+    /// relative ones are taken to live in every workspace folder.
+    fn script_roots(folders: &[PathBuf], configured: &[String]) -> Vec<PathBuf> {
+        let mut roots = Vec::new();
+        for root in configured.iter().map(PathBuf::from) {
+            if root.is_absolute() {
+                roots.push(root);
+            } else {
+                roots.extend(folders.iter().map(|folder| folder.join(&root)));
+            }
+        }
+        roots
+    }
+
+    /// Ask the client to show a progress bar. Returns `None` when the client
+    /// doesn't support progress or refuses the token.
+    async fn begin_progress(&self, title: &str, message: String) -> Option<NumberOrString> {
+        if !*self.progress_supported.read().await {
+            return None;
+        }
+
+        let token = NumberOrString::String("squirrel-lsp/indexing".to_string());
+        self.client
+            .send_request::<WorkDoneProgressCreate>(WorkDoneProgressCreateParams {
+                token: token.clone(),
+            })
+            .await
+            .ok()?;
+        self.send_progress(
+            &token,
+            WorkDoneProgress::Begin(WorkDoneProgressBegin {
+                title: title.to_string(),
+                cancellable: Some(false),
+                message: Some(message),
+                percentage: Some(0),
+            }),
+        )
+        .await;
+        Some(token)
+    }
+
+    async fn send_progress(&self, token: &NumberOrString, value: WorkDoneProgress) {
+        self.client
+            .send_notification::<Progress>(ProgressParams {
+                token: token.clone(),
+                value: ProgressParamsValue::WorkDone(value),
+            })
+            .await;
+    }
+
     /// Index all .nut files in the workspace
     async fn index_workspace(&self) {
         let folders = self.workspace_folders.read().await;
@@ -86,26 +228,70 @@ impl Backend {
             return;
         }
 
+        let config = self.config.read().await.clone();
+        let script_roots = Self::script_roots(&folders, &config.script_roots);
+        let require_function = config.require_function.clone();
+        let mut workspace = self.workspace.write().await;
+        workspace.set_script_roots(script_roots);
+        workspace.set_require_function(require_function);
+        workspace.set_track_references(config.unused_symbol_hints);
+        for name in config.known_globals() {
+            workspace.register_global(name);
+        }
+        workspace.load_stubs(config.game.api_stubs());
+        drop(workspace);
+
         let mut all_files = Vec::new();
         for folder in folders.iter() {
-            all_files.extend(Self::find_nut_files(folder));
+            let ignore = Self::ignore_rules(folder, &config.exclude);
+            all_files.extend(Self::find_script_files(folder, folder, &ignore, &config));
         }
 
         self.client
             .log_message(
                 MessageType::INFO,
                 format!(
-                    "Indexing {} .nut files for class hierarchy...",
+                    "Indexing {} script files for class hierarchy...",
                     all_files.len()
                 ),
             )
             .await;
 
+        let progress = self
+            .begin_progress(
+                "Indexing Squirrel workspace",
+                format!("0/{} files", all_files.len()),
+            )
+            .await;
+
         let mut workspace = self.workspace.write().await;
         let mut indexed_count = 0;
         let mut error_count = 0;
+        let mut oversized_count = 0;
 
-        for file_path in &all_files {
+        for (i, file_path) in all_files.iter().enumerate() {
+            if let Some(token) = &progress
+                && i > 0
+                && i % PROGRESS_INTERVAL == 0
+            {
+                let percentage = (i * 100 / all_files.len()) as u32;
+                self.send_progress(
+                    token,
+                    WorkDoneProgress::Report(WorkDoneProgressReport {
+                        cancellable: Some(false),
+                        message: Some(format!("{i}/{} files", all_files.len())),
+                        percentage: Some(percentage),
+                    }),
+                )
+                .await;
+            }
+            let Ok(metadata) = std::fs::metadata(file_path) else {
+                continue;
+            };
+            if config.exceeds_max_file_size(metadata.len() as usize) {
+                oversized_count += 1;
+                continue;
+            }
             if let Ok(content) = std::fs::read_to_string(file_path) {
                 if let Err(e) = workspace.index_file(file_path, &content) {
                     error_count += 1;
@@ -126,6 +312,22 @@ impl Backend {
 
         // Build inheritance relationships after all files are indexed
         workspace.build_inheritance_graph();
+        *self.last_index.write().await = IndexStats {
+            found: all_files.len(),
+            indexed: indexed_count,
+            errors: error_count,
+            oversized: oversized_count,
+        };
+
+        if let Some(token) = &progress {
+            self.send_progress(
+                token,
+                WorkDoneProgress::End(WorkDoneProgressEnd {
+                    message: Some(format!("Indexed {indexed_count} files")),
+                }),
+            )
+            .await;
+        }
 
         self.client
             .log_message(
@@ -139,13 +341,166 @@ impl Backend {
                 ),
             )
             .await;
+        if oversized_count > 0 {
+            self.client
+                .log_message(
+                    MessageType::INFO,
+                    format!("Skipped {oversized_count} files larger than maxFileSizeKb"),
+                )
+                .await;
+        }
+    }
+
+    /// The comment above the declaration of the symbol at `position`.
+    async fn declaration_comment(
+        &self,
+        uri: &Url,
+        text: String,
+        tree: Tree,
+        position: Position,
+    ) -> Option<String> {
+        let file_path = resolve_file_path(uri)?;
+        let workspace = self.workspace.read().await;
+        let declaration = navigation::find_documented_declaration(
+            &tree, &text, position, &file_path, &workspace,
+        )?;
+        drop(workspace);
+
+        // Prefer the open document's text over what is on disk
+        let (decl_text, decl_tree) = if declaration.file_path == file_path {
+            (text, tree)
+        } else if let Ok(decl_uri) = Url::from_file_path(&declaration.file_path)
+            && let Some(document) = self.get_document(&decl_uri).await
+        {
+            document
+        } else {
+            let decl_text = std::fs::read_to_string(&declaration.file_path).ok()?;
+            let decl_tree = helpers::parse_squirrel(&decl_text).ok()?;
+            (decl_text, decl_tree)
+        };
+
+        navigation::leading_comment(&decl_tree, &decl_text, declaration.line)
+    }
+
+    /// Throw the index away and build it again, keeping the unsaved text of
+    /// open documents, then refresh their diagnostics.
+    async fn reindex(&self) -> String {
+        *self.workspace.write().await = Workspace::new();
+        self.index_workspace().await;
+
+        let documents = self.documents.read().await.clone();
+        let config = self.config.read().await.clone();
+        let mut workspace = self.workspace.write().await;
+        for (uri, (text, tree)) in &documents {
+            if let Some(path) = resolve_file_path(uri)
+                && config.is_script_file(&path)
+            {
+                workspace.index_file_with_tree(&path, tree, text);
+            }
+        }
+        workspace.build_inheritance_graph();
+        let script_paths = workspace.files().len();
+        drop(workspace);
+
+        self.refresh_diagnostics().await;
+
+        format!("Reindexed workspace: {script_paths} script paths")
+    }
+
+    /// Publish diagnostics again for every open document.
+    async fn refresh_diagnostics(&self) {
+        let documents = self.documents.read().await.clone();
+        for (uri, (text, tree)) in documents {
+            self.publish_syntax_diagnostics(uri, &text, &tree).await;
+        }
     }
 
-    async fn get_document(&self, uri: &Url) -> Option<String> {
+    async fn get_document(&self, uri: &Url) -> Option<(String, Tree)> {
         let store = self.documents.read().await;
         store.get(uri).cloned()
     }
 
+    /// Cache `text` for `uri` and return its tree, only reparsing when the
+    /// text differs from what is already stored. `None` when the parser
+    /// fails, leaving the previous entry in place.
+    async fn update_document(&self, uri: &Url, text: String) -> Option<Tree> {
+        let mut store = self.documents.write().await;
+        if let Some((cached, tree)) = store.get(uri)
+            && *cached == text
+        {
+            return Some(tree.clone());
+        }
+
+        match helpers::parse_squirrel(&text) {
+            Ok(tree) => {
+                store.insert(uri.clone(), (text, tree.clone()));
+                Some(tree)
+            },
+            // Syntax errors still give a tree, so this only happens when the
+            // parser itself fails; keep serving the last text that parsed
+            Err(e) => {
+                drop(store);
+                self.client
+                    .log_message(MessageType::ERROR, format!("Failed to parse: {e}"))
+                    .await;
+                None
+            },
+        }
+    }
+
+    /// Refresh the workspace index and diagnostics after a document changed.
+    async fn document_changed(&self, uri: Url, text: &str, tree: &Tree) {
+        if self.config.read().await.exceeds_max_file_size(text.len()) {
+            // What the file defined while it was small enough is stale now,
+            // which only needs undoing on the change that crossed the limit
+            if let Some(path) = resolve_file_path(&uri)
+                && self.workspace.read().await.has_file(&path)
+            {
+                let mut workspace = self.workspace.write().await;
+                workspace.remove_file(&path);
+                workspace.build_inheritance_graph();
+            }
+            self.client
+                .log_message(
+                    MessageType::LOG,
+                    format!("Skipping analysis of {uri}: larger than maxFileSizeKb"),
+                )
+                .await;
+            self.client.publish_diagnostics(uri, Vec::new(), None).await;
+            return;
+        }
+
+        let mut dependents = Vec::new();
+        if let Some(path) = resolve_file_path(&uri)
+            && self.config.read().await.is_script_file(&path)
+        {
+            let mut workspace = self.workspace.write().await;
+            workspace.index_file_with_tree(&path, tree, text);
+            workspace.build_inheritance_graph();
+            dependents = workspace.dependent_files(&path);
+        }
+
+        self.publish_syntax_diagnostics(uri, text, tree).await;
+
+        // Children and hooks of the changed file may have become (in)valid
+        if dependents.is_empty() {
+            return;
+        }
+        let affected: Vec<(Url, (String, Tree))> = self
+            .documents
+            .read()
+            .await
+            .iter()
+            .filter(|(uri, _)| {
+                resolve_file_path(uri).is_some_and(|path| dependents.binary_search(&path).is_ok())
+            })
+            .map(|(uri, document)| (uri.clone(), document.clone()))
+            .collect();
+        for (uri, (text, tree)) in affected {
+            self.publish_syntax_diagnostics(uri, &text, &tree).await;
+        }
+    }
+
     fn map_formatting_options(options: &tower_lsp::lsp_types::FormattingOptions) -> FormatOptions {
         let tab_width = std::cmp::max(1, options.tab_size as usize);
         let indent_style = if options.insert_spaces {
@@ -165,12 +520,16 @@ impl Backend {
         params: DocumentFormattingParams,
     ) -> Result<Option<Vec<TextEdit>>> {
         let uri = params.text_document.uri;
-        let original = match self.get_document(&uri).await {
-            Some(text) => text,
+        let (original, tree) = match self.get_document(&uri).await {
+            Some(document) => document,
             None => return Ok(None),
         };
 
-        let options = Self::map_formatting_options(&params.options);
+        let mut options = Self::map_formatting_options(&params.options);
+        let config = self.config.read().await;
+        options.dialect = config.dialect;
+        options.brace_style = config.brace_style;
+        drop(config);
         match format_document(&original, &options) {
             Ok(formatted) => {
                 if formatted == original {
@@ -181,7 +540,7 @@ impl Backend {
             },
             Err(err) => {
                 self.report_format_error(&err).await;
-                self.publish_syntax_diagnostics(uri, &original).await;
+                self.publish_syntax_diagnostics(uri, &original, &tree).await;
                 Ok(None)
             },
         }
@@ -201,17 +560,27 @@ impl LanguageServer for Backend {
         let mut folders = self.workspace_folders.write().await;
         if let Some(workspace_folders) = params.workspace_folders {
             for folder in workspace_folders {
-                if let Ok(path) = folder.uri.to_file_path() {
+                if let Some(path) = resolve_file_path(&folder.uri) {
                     folders.push(path);
                 }
             }
         } else if let Some(root_uri) = params.root_uri
-            && let Ok(path) = root_uri.to_file_path()
+            && let Some(path) = resolve_file_path(&root_uri)
         {
             folders.push(path);
         }
         drop(folders);
 
+        *self.progress_supported.write().await = params
+            .capabilities
+            .window
+            .as_ref()
+            .and_then(|window| window.work_done_progress)
+            .unwrap_or(false);
+        let config = ServerConfig::from_options(params.initialization_options.as_ref());
+        let document_colors = config.document_colors;
+        *self.config.write().await = config;
+
         let token_types = vec![
             SemanticTokenType::NAMESPACE,
             SemanticTokenType::TYPE,
@@ -255,6 +624,7 @@ impl LanguageServer for Backend {
                 TextDocumentSyncOptions {
                     open_close: Some(true),
                     change: Some(TextDocumentSyncKind::FULL),
+                    save: Some(TextDocumentSyncSaveOptions::Supported(true)),
                     ..Default::default()
                 },
             )),
@@ -265,19 +635,46 @@ impl LanguageServer for Backend {
                         token_types,
                         token_modifiers,
                     },
-                    full: Some(SemanticTokensFullOptions::Bool(true)),
+                    full: Some(SemanticTokensFullOptions::Delta { delta: Some(true) }),
                     range: Some(false),
                     ..Default::default()
                 }),
             ),
             code_action_provider: Some(CodeActionProviderCapability::Options(CodeActionOptions {
-                code_action_kinds: Some(vec![CodeActionKind::QUICKFIX]),
+                code_action_kinds: Some(vec![
+                    CodeActionKind::QUICKFIX,
+                    CodeActionKind::REFACTOR_REWRITE,
+                ]),
                 resolve_provider: Some(false),
                 work_done_progress_options: Default::default(),
             })),
+            completion_provider: Some(CompletionOptions {
+                trigger_characters: Some(vec!["\"".to_string(), "/".to_string()]),
+                ..CompletionOptions::default()
+            }),
+            declaration_provider: Some(DeclarationCapability::Simple(true)),
             definition_provider: Some(OneOf::Left(true)),
+            rename_provider: Some(OneOf::Left(true)),
+            hover_provider: Some(HoverProviderCapability::Simple(true)),
+            implementation_provider: Some(ImplementationProviderCapability::Simple(true)),
             document_symbol_provider: Some(OneOf::Left(true)),
+            document_link_provider: Some(DocumentLinkOptions {
+                resolve_provider: Some(false),
+                work_done_progress_options: WorkDoneProgressOptions::default(),
+            }),
             workspace_symbol_provider: Some(OneOf::Left(true)),
+            color_provider: document_colors.then_some(ColorProviderCapability::Simple(true)),
+            folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
+            execute_command_provider: Some(ExecuteCommandOptions {
+                commands: vec![
+                    REINDEX_COMMAND.to_string(),
+                    CLASS_INFO_COMMAND.to_string(),
+                    STATUS_COMMAND.to_string(),
+                    DEBUG_TOKENS_COMMAND.to_string(),
+                    GO_TO_SUPER_COMMAND.to_string(),
+                ],
+                work_done_progress_options: WorkDoneProgressOptions::default(),
+            }),
             ..ServerCapabilities::default()
         };
 
@@ -310,48 +707,169 @@ impl LanguageServer for Backend {
         Ok(())
     }
 
+    async fn execute_command(&self, params: ExecuteCommandParams) -> Result<Option<Value>> {
+        match params.command.as_str() {
+            REINDEX_COMMAND => {
+                let summary = self.reindex().await;
+                self.client
+                    .show_message(MessageType::INFO, summary.clone())
+                    .await;
+                Ok(Some(Value::String(summary)))
+            },
+            CLASS_INFO_COMMAND => {
+                let Some(script_path) = params.arguments.first().and_then(Value::as_str) else {
+                    return Err(tower_lsp::jsonrpc::Error::invalid_params(
+                        "Expected a script path argument",
+                    ));
+                };
+                let workspace = self.workspace.read().await;
+                match navigation::class_info(&workspace, script_path) {
+                    Some(info) => Ok(Some(info)),
+                    None => Err(tower_lsp::jsonrpc::Error::invalid_params(format!(
+                        "Unknown script path: {script_path}"
+                    ))),
+                }
+            },
+            STATUS_COMMAND => {
+                let stats = *self.last_index.read().await;
+                let workspace = self.workspace.read().await;
+                Ok(Some(json!({
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "indexedFiles": workspace.files().len(),
+                    "globals": workspace.globals().len(),
+                    "lastIndex": {
+                        "found": stats.found,
+                        "indexed": stats.indexed,
+                        "parseErrors": stats.errors,
+                        "oversized": stats.oversized,
+                    },
+                })))
+            },
+            DEBUG_TOKENS_COMMAND => {
+                let Some(uri) = params
+                    .arguments
+                    .first()
+                    .and_then(Value::as_str)
+                    .and_then(|uri| Url::parse(uri).ok())
+                else {
+                    return Err(tower_lsp::jsonrpc::Error::invalid_params(
+                        "Expected a document URI argument",
+                    ));
+                };
+                let Some((text, _)) = self.get_document(&uri).await else {
+                    return Err(tower_lsp::jsonrpc::Error::invalid_params(format!(
+                        "Document not open: {uri}"
+                    )));
+                };
+                debug_tokens(&text)
+                    .map(|tokens| Some(Value::String(tokens)))
+                    .map_err(|e| tower_lsp::jsonrpc::Error::invalid_params(e.to_string()))
+            },
+            GO_TO_SUPER_COMMAND => {
+                let uri = params
+                    .arguments
+                    .first()
+                    .and_then(Value::as_str)
+                    .and_then(|uri| Url::parse(uri).ok());
+                let position = params
+                    .arguments
+                    .get(1)
+                    .and_then(|position| serde_json::from_value::<Position>(position.clone()).ok());
+                let (Some(uri), Some(position)) = (uri, position) else {
+                    return Err(tower_lsp::jsonrpc::Error::invalid_params(
+                        "Expected a document URI and a position",
+                    ));
+                };
+                let Some(file_path) = resolve_file_path(&uri) else {
+                    return Ok(None);
+                };
+                let Some((text, tree)) = self.get_document(&uri).await else {
+                    return Ok(None);
+                };
+                let workspace = self.workspace.read().await;
+                let location =
+                    navigation::find_super_method(&tree, &text, position, &file_path, &workspace)
+                        .and_then(navigation::definition_to_location);
+                Ok(location.map(|location| json!(location)))
+            },
+            command => Err(tower_lsp::jsonrpc::Error::invalid_params(format!(
+                "Unknown command: {command}"
+            ))),
+        }
+    }
+
+    async fn did_change_configuration(&self, params: DidChangeConfigurationParams) {
+        let mut current = self.config.write().await;
+        let Some(mut config) = current.with_settings(&params.settings) else {
+            return;
+        };
+
+        // The color provider is registered once, at initialization
+        config.document_colors = current.document_colors;
+        let reindex = current.needs_reindex(&config);
+        *current = config;
+        drop(current);
+
+        if reindex {
+            let summary = self.reindex().await;
+            self.client.log_message(MessageType::INFO, summary).await;
+        } else {
+            self.refresh_diagnostics().await;
+        }
+    }
+
     async fn did_open(&self, params: DidOpenTextDocumentParams) {
-        let mut store = self.documents.write().await;
         let uri = params.text_document.uri;
         let text = params.text_document.text;
-        store.insert(uri.clone(), text.clone());
-        drop(store);
-
-        // Update workspace index for this file
-        if let Ok(path) = uri.to_file_path() {
-            let mut workspace = self.workspace.write().await;
-            let _ = workspace.index_file(&path, &text);
-            workspace.build_inheritance_graph();
+        if let Some(tree) = self.update_document(&uri, text.clone()).await {
+            self.document_changed(uri, &text, &tree).await;
         }
-
-        self.publish_syntax_diagnostics(uri, &text).await;
     }
 
     async fn did_change(&self, params: DidChangeTextDocumentParams) {
-        let mut store = self.documents.write().await;
         let uri = params.text_document.uri;
-        if let Some(entry) = store.get_mut(&uri)
-            && let Some(TextDocumentContentChangeEvent { text, .. }) =
-                params.content_changes.into_iter().next_back()
-        {
-            *entry = text.clone();
-            drop(store);
+        if !self.documents.read().await.contains_key(&uri) {
+            return;
+        }
 
-            // Update workspace index for this file
-            if let Ok(path) = uri.to_file_path() {
-                let mut workspace = self.workspace.write().await;
-                let _ = workspace.index_file(&path, &text);
-                workspace.build_inheritance_graph();
+        if let Some(TextDocumentContentChangeEvent { text, .. }) =
+            params.content_changes.into_iter().next_back()
+            && let Some(tree) = self.update_document(&uri, text.clone()).await
+        {
+            let config = self.config.read().await.clone();
+            if config.diagnostics_mode == DiagnosticsMode::SyntaxOnSave {
+                // Indexing and the full analysis wait for the save, and files
+                // over the size limit get no diagnostics at all
+                let diags = if config.exceeds_max_file_size(text.len()) {
+                    Vec::new()
+                } else {
+                    compute_syntax_diagnostics_with_tree(&tree, &text)
+                };
+                self.client.publish_diagnostics(uri, diags, None).await;
+            } else {
+                self.document_changed(uri, &text, &tree).await;
             }
+        }
+    }
 
-            self.publish_syntax_diagnostics(uri, &text).await;
-            return;
+    async fn did_save(&self, params: DidSaveTextDocumentParams) {
+        // Reindex and check the saved file and its dependents in full, which
+        // is also when the syntax-only diagnostics mode catches up
+        let uri = params.text_document.uri;
+        if let Some((text, tree)) = self.get_document(&uri).await {
+            self.document_changed(uri, &text, &tree).await;
         }
     }
 
     async fn did_close(&self, params: DidCloseTextDocumentParams) {
         let mut store = self.documents.write().await;
         store.remove(&params.text_document.uri);
+        drop(store);
+        self.semantic_tokens
+            .write()
+            .await
+            .results
+            .remove(&params.text_document.uri);
     }
 
     async fn formatting(&self, params: DocumentFormattingParams) -> Result<Option<Vec<TextEdit>>> {
@@ -363,33 +881,67 @@ impl LanguageServer for Backend {
         params: SemanticTokensParams,
     ) -> Result<Option<SemanticTokensResult>> {
         let uri = params.text_document.uri;
-        let text = match self.get_document(&uri).await {
-            Some(text) => text,
+        let (text, tree) = match self.get_document(&uri).await {
+            Some(document) => document,
+            None => return Ok(None),
+        };
+
+        let data = compute_semantic_tokens_with_tree(&tree, &text);
+        let result_id = self.semantic_tokens.write().await.store(uri, data.clone());
+        Ok(Some(SemanticTokensResult::Tokens(SemanticTokens {
+            result_id: Some(result_id),
+            data,
+        })))
+    }
+
+    async fn semantic_tokens_full_delta(
+        &self,
+        params: SemanticTokensDeltaParams,
+    ) -> Result<Option<SemanticTokensFullDeltaResult>> {
+        let uri = params.text_document.uri;
+        let (text, tree) = match self.get_document(&uri).await {
+            Some(document) => document,
             None => return Ok(None),
         };
 
-        match compute_semantic_tokens(&text) {
-            Ok(data) => Ok(Some(SemanticTokensResult::Tokens(SemanticTokens {
-                result_id: None,
+        let data = compute_semantic_tokens_with_tree(&tree, &text);
+        let mut cache = self.semantic_tokens.write().await;
+        // Without the result the client builds on, send everything again
+        let edits = cache
+            .results
+            .get(&uri)
+            .filter(|(result_id, _)| *result_id == params.previous_result_id)
+            .map(|(_, previous)| diff_semantic_tokens(previous, &data));
+        let result_id = cache.store(uri, data.clone());
+
+        Ok(Some(match edits {
+            Some(edits) => SemanticTokensFullDeltaResult::TokensDelta(SemanticTokensDelta {
+                result_id: Some(result_id),
+                edits,
+            }),
+            None => SemanticTokensFullDeltaResult::Tokens(SemanticTokens {
+                result_id: Some(result_id),
                 data,
-            }))),
-            Err(err) => {
-                self.client
-                    .log_message(MessageType::ERROR, format!("Semantic tokens failed: {err}"))
-                    .await;
-                Ok(None)
-            },
-        }
+            }),
+        }))
     }
 
     async fn code_action(&self, params: CodeActionParams) -> Result<Option<CodeActionResponse>> {
         let uri = params.text_document.uri;
-        let text = match self.get_document(&uri).await {
-            Some(text) => text,
+        let (text, tree) = match self.get_document(&uri).await {
+            Some(document) => document,
             None => return Ok(None),
         };
 
-        let actions = generate_code_actions(&text, &params.context.diagnostics, &uri);
+        let functions_separately = self.config.read().await.sort_functions_separately;
+        let mut actions = generate_code_actions(&text, &params.context.diagnostics, &uri);
+        actions.extend(generate_refactor_actions(
+            &tree,
+            &text,
+            params.range,
+            &uri,
+            functions_separately,
+        ));
 
         if actions.is_empty() {
             Ok(None)
@@ -403,6 +955,52 @@ impl LanguageServer for Backend {
         }
     }
 
+    async fn completion(&self, params: CompletionParams) -> Result<Option<CompletionResponse>> {
+        let uri = params.text_document_position.text_document.uri;
+        let position = params.text_document_position.position;
+
+        let (text, tree) = match self.get_document(&uri).await {
+            Some(document) => document,
+            None => return Ok(None),
+        };
+
+        let workspace = self.workspace.read().await;
+        let items = script_path_completions(&tree, &text, position, &workspace);
+
+        if items.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(CompletionResponse::Array(items)))
+        }
+    }
+
+    async fn goto_declaration(
+        &self,
+        params: GotoDeclarationParams,
+    ) -> Result<Option<GotoDeclarationResponse>> {
+        let uri = params.text_document_position_params.text_document.uri;
+        let position = params.text_document_position_params.position;
+
+        let (text, tree) = match self.get_document(&uri).await {
+            Some(document) => document,
+            None => return Ok(None),
+        };
+
+        let Some(file_path) = resolve_file_path(&uri) else {
+            return Ok(None);
+        };
+        let workspace = self.workspace.read().await;
+
+        if let Some(result) =
+            navigation::find_declaration(&tree, &text, position, &file_path, &workspace)
+            && let Some(location) = navigation::definition_to_location(result)
+        {
+            return Ok(Some(GotoDeclarationResponse::Scalar(location)));
+        }
+
+        Ok(None)
+    }
+
     async fn goto_definition(
         &self,
         params: GotoDefinitionParams,
@@ -410,15 +1008,18 @@ impl LanguageServer for Backend {
         let uri = params.text_document_position_params.text_document.uri;
         let position = params.text_document_position_params.position;
 
-        let text = match self.get_document(&uri).await {
-            Some(text) => text,
+        let (text, tree) = match self.get_document(&uri).await {
+            Some(document) => document,
             None => return Ok(None),
         };
 
-        let file_path = uri.to_file_path().unwrap_or_default();
+        let Some(file_path) = resolve_file_path(&uri) else {
+            return Ok(None);
+        };
         let workspace = self.workspace.read().await;
 
-        if let Some(result) = navigation::find_definition(&text, position, &file_path, &workspace)
+        if let Some(result) =
+            navigation::find_definition(&tree, &text, position, &file_path, &workspace)
             && let Some(location) = navigation::definition_to_location(result)
         {
             return Ok(Some(GotoDefinitionResponse::Scalar(location)));
@@ -427,18 +1028,163 @@ impl LanguageServer for Backend {
         Ok(None)
     }
 
+    async fn hover(&self, params: HoverParams) -> Result<Option<Hover>> {
+        let uri = params.text_document_position_params.text_document.uri;
+        let position = params.text_document_position_params.position;
+
+        let (text, tree) = match self.get_document(&uri).await {
+            Some(document) => document,
+            None => return Ok(None),
+        };
+
+        let label = navigation::metamethod_at(&tree, &text, position)
+            .map(|name| format!("`{name}`: Squirrel metamethod"));
+        let doc = self.declaration_comment(&uri, text, tree, position).await;
+        let value = match (label, doc) {
+            (Some(label), Some(doc)) => format!("{label}\n\n{doc}"),
+            (label, doc) => match label.or(doc) {
+                Some(value) => value,
+                None => return Ok(None),
+            },
+        };
+
+        Ok(Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value,
+            }),
+            range: None,
+        }))
+    }
+
+    async fn rename(&self, params: RenameParams) -> Result<Option<WorkspaceEdit>> {
+        let uri = params.text_document_position.text_document.uri;
+        let position = params.text_document_position.position;
+        if !rename::is_valid_identifier(&params.new_name) {
+            return Err(tower_lsp::jsonrpc::Error::invalid_params(format!(
+                "'{}' is not a valid method name",
+                params.new_name
+            )));
+        }
+
+        let (text, tree) = match self.get_document(&uri).await {
+            Some(document) => document,
+            None => return Ok(None),
+        };
+
+        // Unsaved edits in open documents win over the files on disk
+        let open: HashMap<PathBuf, String> = self
+            .documents
+            .read()
+            .await
+            .iter()
+            .filter_map(|(uri, (text, _))| Some((resolve_file_path(uri)?, text.clone())))
+            .collect();
+
+        let Some(file_path) = resolve_file_path(&uri) else {
+            return Ok(None);
+        };
+        let workspace = self.workspace.read().await;
+        let result = rename::rename_target(&tree, &text, position, &file_path, &workspace);
+        drop(workspace);
+        let target = match result {
+            Ok(target) => target,
+            Err(rename::RenameError::NotAMethod) => return Ok(None),
+            Err(rename::RenameError::EngineMethod(class)) => {
+                self.client
+                    .show_message(
+                        MessageType::WARNING,
+                        format!(
+                            "Not renamed: the engine class '{class}' declares this method and \
+                             calls it by name."
+                        ),
+                    )
+                    .await;
+                return Ok(None);
+            },
+        };
+
+        // Reading and parsing the candidate files happens without the
+        // workspace lock and off the async runtime
+        let files = target.files.clone();
+        let others = tokio::task::spawn_blocking(move || {
+            rename::parse_files(&files, |path| {
+                open.get(path)
+                    .cloned()
+                    .or_else(|| std::fs::read_to_string(path).ok())
+            })
+        })
+        .await
+        .unwrap_or_default();
+
+        let workspace = self.workspace.read().await;
+        let changes = rename::rename_edits(
+            &target,
+            &tree,
+            &text,
+            &file_path,
+            &others,
+            &params.new_name,
+            &workspace,
+        );
+        drop(workspace);
+
+        let sites: usize = changes.values().map(Vec::len).sum();
+        self.client
+            .log_message(
+                MessageType::INFO,
+                format!(
+                    "Renaming {sites} occurrences in {} files. Calls through variables other than \
+                     `this` or a hook parameter may be missed.",
+                    changes.len()
+                ),
+            )
+            .await;
+
+        Ok(Some(WorkspaceEdit {
+            changes: Some(changes),
+            ..WorkspaceEdit::default()
+        }))
+    }
+
+    async fn goto_implementation(
+        &self,
+        params: GotoImplementationParams,
+    ) -> Result<Option<GotoImplementationResponse>> {
+        let uri = params.text_document_position_params.text_document.uri;
+        let position = params.text_document_position_params.position;
+
+        let (text, tree) = match self.get_document(&uri).await {
+            Some(document) => document,
+            None => return Ok(None),
+        };
+
+        let Some(file_path) = resolve_file_path(&uri) else {
+            return Ok(None);
+        };
+        let workspace = self.workspace.read().await;
+
+        let locations =
+            navigation::find_implementations(&tree, &text, position, &file_path, &workspace);
+        if locations.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(GotoImplementationResponse::Array(locations)))
+        }
+    }
+
     async fn document_symbol(
         &self,
         params: DocumentSymbolParams,
     ) -> Result<Option<DocumentSymbolResponse>> {
         let uri = params.text_document.uri;
 
-        let text = match self.get_document(&uri).await {
-            Some(text) => text,
+        let (text, tree) = match self.get_document(&uri).await {
+            Some(document) => document,
             None => return Ok(None),
         };
 
-        let symbols = navigation::get_document_symbols(&text);
+        let symbols = navigation::get_document_symbols(&tree, &text);
 
         if symbols.is_empty() {
             Ok(None)
@@ -447,6 +1193,49 @@ impl LanguageServer for Backend {
         }
     }
 
+    async fn folding_range(&self, params: FoldingRangeParams) -> Result<Option<Vec<FoldingRange>>> {
+        match self.get_document(&params.text_document.uri).await {
+            Some((text, tree)) => Ok(Some(compute_folding_ranges(&tree, &text))),
+            None => Ok(None),
+        }
+    }
+
+    async fn document_link(&self, params: DocumentLinkParams) -> Result<Option<Vec<DocumentLink>>> {
+        let uri = params.text_document.uri;
+
+        let (text, tree) = match self.get_document(&uri).await {
+            Some(document) => document,
+            None => return Ok(None),
+        };
+
+        let workspace = self.workspace.read().await;
+        let links = navigation::compute_document_links(&tree, &text, &workspace);
+
+        if links.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(links))
+        }
+    }
+
+    async fn document_color(&self, params: DocumentColorParams) -> Result<Vec<ColorInformation>> {
+        if !self.config.read().await.document_colors {
+            return Ok(Vec::new());
+        }
+
+        match self.get_document(&params.text_document.uri).await {
+            Some((text, tree)) => Ok(compute_document_colors(&tree, &text)),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    async fn color_presentation(
+        &self,
+        params: ColorPresentationParams,
+    ) -> Result<Vec<ColorPresentation>> {
+        Ok(color_presentations(params.color, params.range))
+    }
+
     async fn symbol(
         &self,
         params: WorkspaceSymbolParams,
@@ -480,75 +1269,148 @@ fn full_range(text: &str) -> Range {
 }
 
 impl Backend {
-    async fn publish_syntax_diagnostics(&self, uri: Url, text: &str) {
-        // Collect syntax diagnostics
-        let mut diags = match compute_syntax_diagnostics(text) {
-            Ok(syntax_diags) => syntax_diags,
-            Err(e) => {
-                self.client
-                    .log_message(MessageType::ERROR, format!("Failed to parse: {e}"))
-                    .await;
-                Vec::new()
-            },
-        };
-
-        // Get workspace for globals and other analyses
+    async fn publish_syntax_diagnostics(&self, uri: Url, text: &str, tree: &Tree) {
         let workspace = self.workspace.read().await;
-
-        // Get file path from URI for symbol resolution
-        let file_path = uri
-            .to_file_path()
-            .map(|p| p.to_string_lossy().to_string())
-            .unwrap_or_else(|_| uri.path().to_string());
-
-        // Collect semantic diagnostics using symbol resolver
-        match compute_symbol_diagnostics_with_globals(&file_path, text, workspace.globals()) {
-            Ok(semantic_diags) => {
-                diags.extend(semantic_diags);
-            },
-            Err(e) => {
-                self.client
-                    .log_message(MessageType::ERROR, format!("Semantic analysis failed: {e}"))
-                    .await;
-            },
+        let config = self.config.read().await.clone();
+        if config.exceeds_max_file_size(text.len()) {
+            self.client.publish_diagnostics(uri, Vec::new(), None).await;
+            return;
         }
 
-        // Analyze hooks for method validation
-        match analyze_hooks(text, &workspace) {
-            Ok(hook_diags) => {
-                diags.extend(hook_diags);
-            },
-            Err(e) => {
-                self.client
-                    .log_message(MessageType::ERROR, format!("Hook analysis failed: {e}"))
-                    .await;
-            },
+        let (diags, errors) = match resolve_file_path(&uri) {
+            Some(file_path) => analyze_document_with_tree(
+                &file_path.to_string_lossy(),
+                tree,
+                text,
+                &workspace,
+                &config,
+            ),
+            None => analyze_standalone_document(tree, text, &workspace, &config),
+        };
+        drop(workspace);
+        for err in errors {
+            self.client
+                .log_message(
+                    MessageType::ERROR,
+                    format!("Analysis of {uri} failed: {err}"),
+                )
+                .await;
         }
+        self.client.publish_diagnostics(uri, diags, None).await;
+    }
+}
 
-        // Analyze inheritance patterns (validates parent paths exist and no circular inheritance)
-        match analyze_inheritance(text, &workspace) {
-            Ok(inherit_diags) => {
-                diags.extend(inherit_diags);
-            },
-            Err(e) => {
-                self.client
-                    .log_message(
-                        MessageType::ERROR,
-                        format!("Inheritance analysis failed: {e}"),
-                    )
-                    .await;
+/// How the server talks to its client.
+#[derive(Debug, PartialEq, Eq)]
+enum Transport {
+    Stdio,
+    Socket(u16),
+}
+
+fn parse_transport(
+    args: impl IntoIterator<Item = String>,
+) -> std::result::Result<Transport, String> {
+    let mut transport = Transport::Stdio;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--stdio" => transport = Transport::Stdio,
+            "--listen" | "--socket" => {
+                let port = args
+                    .next()
+                    .ok_or_else(|| format!("{arg} requires a port"))?
+                    .parse()
+                    .map_err(|_| format!("{arg} expects a port number"))?;
+                transport = Transport::Socket(port);
             },
+            other => return Err(format!("unexpected argument '{other}'")),
         }
-
-        self.client.publish_diagnostics(uri, diags, None).await;
     }
+    Ok(transport)
 }
 
 #[tokio::main]
 async fn main() {
-    let stdin = tokio::io::stdin();
-    let stdout = tokio::io::stdout();
+    let mut args = std::env::args().skip(1).peekable();
+    if args.peek().is_some_and(|arg| arg == "format") {
+        args.next();
+        std::process::exit(cli::run_format(args));
+    }
+    if args.peek().is_some_and(|arg| arg == "check") {
+        args.next();
+        std::process::exit(cli::run_check(args));
+    }
+
+    let transport = match parse_transport(args) {
+        Ok(transport) => transport,
+        Err(err) => {
+            eprintln!("Error: {err}");
+            eprintln!("Usage: squirrel-lsp [--stdio | --listen <port>]");
+            eprintln!("       squirrel-lsp format [--check] <file.nut|directory>...");
+            eprintln!("       squirrel-lsp check [--format json] <file.nut|directory>...");
+            std::process::exit(2);
+        },
+    };
 
     let (service, socket) = LspService::new(Backend::new);
-    Server::new(stdin, stdout, socket).serve(service).await;
+    match transport {
+        Transport::Stdio => {
+            let stdin = tokio::io::stdin();
+            let stdout = tokio::io::stdout();
+            Server::new(stdin, stdout, socket).serve(service).await;
+        },
+        Transport::Socket(port) => {
+            let stream = match accept_connection(port).await {
+                Ok(stream) => stream,
+                Err(err) => {
+                    eprintln!("Error: failed to accept a connection on port {port}: {err}");
+                    std::process::exit(1);
+                },
+            };
+            let (read, write) = stream.into_split();
+            Server::new(read, write, socket).serve(service).await;
+        },
+    }
+}
+
+/// Wait for a single client on localhost, as editors that spawn the server
+/// over TCP connect exactly once.
+async fn accept_connection(port: u16) -> std::io::Result<TcpStream> {
+    let listener = TcpListener::bind(("127.0.0.1", port)).await?;
+    eprintln!("squirrel-lsp listening on 127.0.0.1:{port}");
+    let (stream, _) = listener.accept().await?;
+    Ok(stream)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_transport_defaults_to_stdio() {
+        assert_eq!(parse_transport(args(&[])), Ok(Transport::Stdio));
+        assert_eq!(parse_transport(args(&["--stdio"])), Ok(Transport::Stdio));
+    }
+
+    #[test]
+    fn test_transport_socket_port() {
+        assert_eq!(
+            parse_transport(args(&["--listen", "9257"])),
+            Ok(Transport::Socket(9257))
+        );
+        assert_eq!(
+            parse_transport(args(&["--socket", "9257"])),
+            Ok(Transport::Socket(9257))
+        );
+    }
+
+    #[test]
+    fn test_transport_rejects_bad_port() {
+        assert!(parse_transport(args(&["--listen"])).is_err());
+        assert!(parse_transport(args(&["--listen", "http"])).is_err());
+    }
 }
@@ -1,25 +1,32 @@
 //! Navigation support for Go to Definition, Find References, etc.
 
+use std::collections::HashSet;
 use std::path::Path;
 
+use serde_json::{Value, json};
 use tower_lsp::lsp_types::{
-    DocumentSymbol, Location, Position, Range, SymbolInformation, SymbolKind, Url,
+    DocumentLink, DocumentSymbol, Location, Position, Range, SymbolInformation, SymbolKind, Url,
 };
-use tree_sitter::Node;
+use tree_sitter::{Node, Tree};
 
+use crate::bb_support::{find_hook_calls, find_inherit_calls, is_const_path};
 use crate::helpers;
-use crate::workspace::{MemberType, Workspace};
+use crate::symbol_resolver::{SymbolResolver, is_metamethod};
+use crate::workspace::{GlobalKind, MemberInfo, MemberType, Workspace, global_path};
 
 #[derive(Debug)]
 enum SymbolAtPosition {
     InheritParentPath(String),
     MethodCall(String),
-    FunctionDeclaration(),
+    FunctionDeclaration(String),
     Identifier(String),
 }
 
-fn find_symbol_at_position(text: &str, position: Position) -> Option<SymbolAtPosition> {
-    let tree = helpers::parse_squirrel(text).ok()?;
+fn find_symbol_at_position(
+    tree: &Tree,
+    text: &str,
+    position: Position,
+) -> Option<SymbolAtPosition> {
     let root = tree.root_node();
 
     let byte_offset = byte_offset_at(text, position)?;
@@ -28,18 +35,27 @@ fn find_symbol_at_position(text: &str, position: Position) -> Option<SymbolAtPos
     classify_node(node, text)
 }
 
-fn find_deepest_node_at(node: Node, byte_offset: usize) -> Option<Node> {
+pub(crate) fn find_deepest_node_at(node: Node, byte_offset: usize) -> Option<Node> {
     if byte_offset < node.start_byte() || byte_offset > node.end_byte() {
         return None;
     }
 
+    // At the boundary between two tokens both contain the offset, so a
+    // cursor just past `foo` in `foo(` or just after the `.` in `a.foo`
+    // should land on the identifier rather than the punctuation
+    let mut best: Option<Node> = None;
     for child in node.children(&mut node.walk()) {
-        if let Some(deeper) = find_deepest_node_at(child, byte_offset) {
-            return Some(deeper);
+        if child.start_byte() > byte_offset {
+            break;
+        }
+        if let Some(deeper) = find_deepest_node_at(child, byte_offset)
+            && best.is_none_or(|_| deeper.is_named())
+        {
+            best = Some(deeper);
         }
     }
 
-    Some(node)
+    Some(best.unwrap_or(node))
 }
 
 fn classify_node(node: Node, text: &str) -> Option<SymbolAtPosition> {
@@ -68,7 +84,7 @@ fn classify_node(node: Node, text: &str) -> Option<SymbolAtPosition> {
                         return Some(SymbolAtPosition::MethodCall(node_text.to_string()));
                     },
                     "function_declaration" => {
-                        return Some(SymbolAtPosition::FunctionDeclaration());
+                        return Some(SymbolAtPosition::FunctionDeclaration(node_text.to_string()));
                     },
                     _ => {},
                 }
@@ -79,7 +95,30 @@ fn classify_node(node: Node, text: &str) -> Option<SymbolAtPosition> {
     }
 }
 
-fn is_inside_inherit_call(node: Node, source: &str) -> bool {
+/// The global path the identifier under the cursor names, such as
+/// "Legends.Arena" on the `Arena` of `::Legends.Arena`.
+fn global_at_position(tree: &Tree, text: &str, position: Position) -> Option<String> {
+    let node = find_deepest_node_at(tree.root_node(), byte_offset_at(text, position)?)?;
+    if node.kind() != "identifier" {
+        return None;
+    }
+
+    let mut target = node;
+    if let Some(parent) = target.parent()
+        && parent.kind() == "global_variable"
+    {
+        target = parent;
+    }
+    if let Some(parent) = target.parent()
+        && parent.kind() == "deref_expression"
+        && parent.named_child(1).is_some_and(|member| member == target)
+    {
+        target = parent;
+    }
+    global_path(target, text)
+}
+
+pub(crate) fn is_inside_inherit_call(node: Node, source: &str) -> bool {
     let source_bytes = source.as_bytes();
     let mut current = node;
     while let Some(parent) = current.parent() {
@@ -143,13 +182,40 @@ pub struct DefinitionResult {
     pub column: u32,
 }
 
+/// Where the symbol under the cursor is defined. A global assigned more than
+/// once resolves to the assignment that gives it a table or function body.
 pub fn find_definition(
+    tree: &Tree,
     text: &str,
     position: Position,
     current_file: &Path,
     workspace: &Workspace,
 ) -> Option<DefinitionResult> {
-    let symbol = find_symbol_at_position(text, position)?;
+    find_site(tree, text, position, current_file, workspace, false)
+}
+
+/// Where the symbol under the cursor is declared. For a global this is its
+/// first `<-` slot, even if only a later one fills it in; everything else is
+/// declared where it is defined.
+pub fn find_declaration(
+    tree: &Tree,
+    text: &str,
+    position: Position,
+    current_file: &Path,
+    workspace: &Workspace,
+) -> Option<DefinitionResult> {
+    find_site(tree, text, position, current_file, workspace, true)
+}
+
+fn find_site(
+    tree: &Tree,
+    text: &str,
+    position: Position,
+    current_file: &Path,
+    workspace: &Workspace,
+    declaration: bool,
+) -> Option<DefinitionResult> {
+    let symbol = find_symbol_at_position(tree, text, position)?;
 
     match symbol {
         SymbolAtPosition::InheritParentPath(path) => {
@@ -158,17 +224,31 @@ pub fn find_definition(
             if let Some(entry) = workspace.get(normalized) {
                 return Some(DefinitionResult {
                     file_path: entry.file_path.clone(),
-                    line: 0,
+                    line: entry.definition_line,
                     column: 0,
                 });
             }
         },
-        SymbolAtPosition::MethodCall(method_name) | SymbolAtPosition::Identifier(method_name) => {
-            let script_path = extract_script_path(current_file);
+        SymbolAtPosition::MethodCall(ref method_name)
+        | SymbolAtPosition::Identifier(ref method_name) => {
+            // Locals, parameters and loop variables resolve within the file
+            if let Some(offset) = byte_offset_at(text, position)
+                && let Some(range) =
+                    SymbolResolver::with_tree(&current_file.to_string_lossy(), tree, text)
+                        .find_local_declaration(offset)
+            {
+                return Some(DefinitionResult {
+                    file_path: current_file.to_path_buf(),
+                    line: range.start.line,
+                    column: range.start.character,
+                });
+            }
+
+            let script_path = workspace.script_path_for(current_file);
 
             if !script_path.is_empty()
                 && let Some((file_path, line, column)) =
-                    workspace.find_method_definition(&script_path, &method_name)
+                    workspace.find_method_definition(&script_path, method_name)
             {
                 return Some(DefinitionResult {
                     file_path: file_path.clone(),
@@ -177,7 +257,33 @@ pub fn find_definition(
                 });
             }
 
-            let results = workspace.find_method_anywhere(&method_name);
+            if let Some(name) = global_at_position(tree, text, position)
+                && let Some(sites) = workspace.global_sites(&name)
+            {
+                let (file_path, global) = if declaration {
+                    sites.declaration
+                } else {
+                    sites.definition
+                };
+                return Some(DefinitionResult {
+                    file_path: file_path.to_path_buf(),
+                    line: global.line,
+                    column: global.column,
+                });
+            }
+
+            // A bare name may refer to a class or table defined in any file
+            if matches!(symbol, SymbolAtPosition::Identifier(_))
+                && let Some(def) = workspace.definitions_named(method_name).first()
+            {
+                return Some(DefinitionResult {
+                    file_path: def.file_path.clone(),
+                    line: def.line,
+                    column: 0,
+                });
+            }
+
+            let results = workspace.find_method_anywhere(method_name);
             if let Some((file_path, line, column, _)) = results.first() {
                 return Some(DefinitionResult {
                     file_path: (*file_path).clone(),
@@ -186,7 +292,7 @@ pub fn find_definition(
                 });
             }
         },
-        SymbolAtPosition::FunctionDeclaration() => {
+        SymbolAtPosition::FunctionDeclaration(_) => {
             return None;
         },
     }
@@ -194,15 +300,172 @@ pub fn find_definition(
     None
 }
 
-fn extract_script_path(file_path: &Path) -> String {
-    let path_str = file_path.to_string_lossy();
+/// The metamethod named under the cursor, such as `_get` in a class body.
+pub fn metamethod_at(tree: &Tree, text: &str, position: Position) -> Option<String> {
+    let name = match find_symbol_at_position(tree, text, position)? {
+        SymbolAtPosition::MethodCall(name)
+        | SymbolAtPosition::FunctionDeclaration(name)
+        | SymbolAtPosition::Identifier(name) => name,
+        SymbolAtPosition::InheritParentPath(_) => return None,
+    };
+    is_metamethod(&name).then_some(name)
+}
+
+/// Where the documentation for the symbol under the cursor lives: the
+/// declaration itself when hovering a function's name, its definition
+/// otherwise.
+pub fn find_documented_declaration(
+    tree: &Tree,
+    text: &str,
+    position: Position,
+    current_file: &Path,
+    workspace: &Workspace,
+) -> Option<DefinitionResult> {
+    if let Some(SymbolAtPosition::FunctionDeclaration(_)) =
+        find_symbol_at_position(tree, text, position)
+    {
+        return Some(DefinitionResult {
+            file_path: current_file.to_path_buf(),
+            line: position.line,
+            column: position.character,
+        });
+    }
+    find_definition(tree, text, position, current_file, workspace)
+}
+
+/// The comment block right above the statement starting on `line`, with
+/// `//`, `/* */` and leading `*` markers removed. A comment trailing code
+/// on its own line belongs to that code and ends the block.
+pub fn leading_comment(tree: &Tree, text: &str, line: u32) -> Option<String> {
+    let line_text = text.lines().nth(line as usize)?;
+    let column = line_text.len() - line_text.trim_start().len();
+    let point = tree_sitter::Point::new(line as usize, column);
+    let root = tree.root_node();
+
+    let mut statement = root.descendant_for_point_range(point, point)?;
+    while let Some(parent) = statement.parent()
+        && parent.id() != root.id()
+        && parent.start_position().row == line as usize
+    {
+        statement = parent;
+    }
+
+    let mut comments = Vec::new();
+    let mut next_row = statement.start_position().row;
+    let mut previous = statement.prev_sibling();
+    while let Some(comment) = previous
+        && comment.kind() == "comment"
+        && comment.end_position().row + 1 >= next_row
+    {
+        let trails_code = comment
+            .prev_sibling()
+            .is_some_and(|code| code.end_position().row == comment.start_position().row);
+        if trails_code {
+            break;
+        }
+        comments.push(helpers::node_text(comment, text));
+        next_row = comment.start_position().row;
+        previous = comment.prev_sibling();
+    }
+
+    let doc = comments
+        .iter()
+        .rev()
+        .map(|comment| strip_comment_markers(comment))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let doc = doc.trim_matches('\n');
+    (!doc.trim().is_empty()).then(|| doc.to_string())
+}
 
-    if let Some(scripts_idx) = path_str.find("scripts/") {
-        let after_scripts = &path_str[scripts_idx + 8..];
-        return after_scripts.trim_end_matches(".nut").to_string();
+fn strip_comment_markers(comment: &str) -> String {
+    if let Some(line) = comment.strip_prefix("//") {
+        let line = line.trim_start_matches('/');
+        return line
+            .strip_prefix(' ')
+            .unwrap_or(line)
+            .trim_end()
+            .to_string();
     }
 
-    String::new()
+    let body = comment.trim_start_matches("/*").trim_start_matches('*');
+    let body = body.strip_suffix("*/").unwrap_or(body);
+    body.lines()
+        .map(|line| {
+            let line = line.trim();
+            let line = line.strip_prefix('*').unwrap_or(line);
+            line.strip_prefix(' ').unwrap_or(line).to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim_matches('\n')
+        .to_string()
+}
+
+/// Locations of every descendant class that overrides the method under the
+/// cursor, relative to the class defined in `current_file`.
+pub fn find_implementations(
+    tree: &Tree,
+    text: &str,
+    position: Position,
+    current_file: &Path,
+    workspace: &Workspace,
+) -> Vec<Location> {
+    let method_name = match find_symbol_at_position(tree, text, position) {
+        Some(
+            SymbolAtPosition::FunctionDeclaration(name)
+            | SymbolAtPosition::MethodCall(name)
+            | SymbolAtPosition::Identifier(name),
+        ) => name,
+        _ => return Vec::new(),
+    };
+
+    let script_path = workspace.script_path_for(current_file);
+    workspace
+        .find_overrides(&script_path, &method_name)
+        .into_iter()
+        .filter_map(|(entry, member)| {
+            let uri = Url::from_file_path(&entry.file_path).ok()?;
+            let position = Position::new(member.line, member.column);
+            Some(Location {
+                uri,
+                range: Range::new(position, position),
+            })
+        })
+        .collect()
+}
+
+/// The parent class's version of the method the cursor is in, found in the
+/// nearest ancestor that defines it. Unlike `find_definition`, the class's
+/// own definition of the method is skipped.
+pub fn find_super_method(
+    tree: &Tree,
+    text: &str,
+    position: Position,
+    current_file: &Path,
+    workspace: &Workspace,
+) -> Option<DefinitionResult> {
+    let offset = byte_offset_at(text, position)?;
+    let mut node = tree.root_node().descendant_for_byte_range(offset, offset)?;
+    let function = loop {
+        if node.kind() == "function_declaration" {
+            break node;
+        }
+        node = node.parent()?;
+    };
+    let name = function
+        .children(&mut function.walk())
+        .find(|child| child.kind() == "identifier")?;
+
+    let script_path = workspace.script_path_for(current_file);
+    let parent = workspace.get_ancestors(&script_path).into_iter().next()?;
+    let (file_path, line, column) =
+        workspace.find_method_definition(&parent.script_path, helpers::node_text(name, text))?;
+    Some(DefinitionResult {
+        file_path: file_path.clone(),
+        line,
+        column,
+    })
 }
 
 pub fn definition_to_location(result: DefinitionResult) -> Option<Location> {
@@ -214,12 +477,44 @@ pub fn definition_to_location(result: DefinitionResult) -> Option<Location> {
     })
 }
 
-pub fn get_document_symbols(text: &str) -> Vec<DocumentSymbol> {
-    let tree = match helpers::parse_squirrel(text) {
-        Ok(tree) => tree,
-        Err(_) => return Vec::new(),
-    };
+/// Links from the script path strings in `inherit(...)` and `::mods_hook*(...)`
+/// calls to the files they name. Paths that aren't indexed get no link.
+pub fn compute_document_links(tree: &Tree, text: &str, workspace: &Workspace) -> Vec<DocumentLink> {
+    let root = tree.root_node();
+    let inherits = find_inherit_calls(root, text)
+        .into_iter()
+        .map(|call| (call.parent_path, call.parent_path_node));
+    let hooks = find_hook_calls(root, text)
+        .into_iter()
+        .map(|hook| (hook.target_path, hook.target_path_node));
+
+    inherits
+        .chain(hooks)
+        .filter_map(|(path, node)| {
+            let entry = workspace.get(&path)?;
+            let target = Url::from_file_path(&entry.file_path).ok()?;
+
+            // Link the path itself rather than its quotes
+            let (mut start, mut end) = (node.start_byte(), node.end_byte());
+            if end - start >= 2 && text[start..end].ends_with('"') {
+                start += text[start..end].find('"').map_or(0, |i| i + 1);
+                end -= 1;
+            }
+
+            Some(DocumentLink {
+                range: Range::new(
+                    helpers::position_at(text, start),
+                    helpers::position_at(text, end),
+                ),
+                target: Some(target),
+                tooltip: Some(format!("Open {}", entry.script_path)),
+                data: None,
+            })
+        })
+        .collect()
+}
 
+pub fn get_document_symbols(tree: &Tree, text: &str) -> Vec<DocumentSymbol> {
     let root = tree.root_node();
     let mut symbols = Vec::new();
 
@@ -238,11 +533,13 @@ fn extract_symbol_from_node(node: Node, text: &str) -> Option<DocumentSymbol> {
             let mut name = None;
             let mut table_node = None;
             let mut is_class = false;
+            let mut is_const = false;
 
             for child in node.children(&mut node.walk()) {
                 match child.kind() {
                     "identifier" | "deref_expression" if name.is_none() => {
                         name = helpers::extract_identifier_name(child, text);
+                        is_const = is_const_path(helpers::node_text(child, text));
                     },
                     "call_expression" => {
                         for call_child in child.children(&mut child.walk()) {
@@ -278,7 +575,13 @@ fn extract_symbol_from_node(node: Node, text: &str) -> Option<DocumentSymbol> {
                 Position::new(end.row as u32, end.column as u32),
             );
 
-            let children = table_node.map(|t| extract_table_members(t, text));
+            let children = table_node.map(|t| {
+                if is_const {
+                    extract_const_members(t, text)
+                } else {
+                    extract_table_members(t, text)
+                }
+            });
 
             Some(DocumentSymbol {
                 name,
@@ -337,6 +640,13 @@ fn extract_symbol_from_node(node: Node, text: &str) -> Option<DocumentSymbol> {
                 Position::new(end.row as u32, end.column as u32),
             );
 
+            // Functions inside `local helpers = { ... }` are still worth listing
+            let children = node
+                .children(&mut node.walk())
+                .find(|c| c.kind() == "table")
+                .map(|t| extract_table_members(t, text))
+                .filter(|members| !members.is_empty());
+
             Some(DocumentSymbol {
                 name,
                 detail: None,
@@ -346,13 +656,165 @@ fn extract_symbol_from_node(node: Node, text: &str) -> Option<DocumentSymbol> {
                 deprecated: None,
                 range,
                 selection_range: range,
+                children,
+            })
+        },
+        "const_declaration" => {
+            let name = node
+                .children(&mut node.walk())
+                .find(|c| c.kind() == "identifier")
+                .map(|n| n.utf8_text(text.as_bytes()).unwrap_or("").to_string())?;
+
+            let start = node.start_position();
+            let end = node.end_position();
+            let range = Range::new(
+                Position::new(start.row as u32, start.column as u32),
+                Position::new(end.row as u32, end.column as u32),
+            );
+
+            Some(DocumentSymbol {
+                name,
+                detail: None,
+                kind: SymbolKind::CONSTANT,
+                tags: None,
+                #[allow(deprecated)]
+                deprecated: None,
+                range,
+                selection_range: range,
                 children: None,
             })
         },
+        "enum_declaration" => {
+            let name_node = node.child_by_field_name("name").or_else(|| {
+                node.children(&mut node.walk())
+                    .find(|c| c.kind() == "identifier")
+            })?;
+            let name = name_node.utf8_text(text.as_bytes()).ok()?.to_string();
+
+            let start = node.start_position();
+            let end = node.end_position();
+            let range = Range::new(
+                Position::new(start.row as u32, start.column as u32),
+                Position::new(end.row as u32, end.column as u32),
+            );
+
+            Some(DocumentSymbol {
+                name,
+                detail: None,
+                kind: SymbolKind::ENUM,
+                tags: None,
+                #[allow(deprecated)]
+                deprecated: None,
+                range,
+                selection_range: range,
+                children: Some(extract_enum_members(node, name_node, text)),
+            })
+        },
         _ => None,
     }
 }
 
+fn extract_enum_members(node: Node, name_node: Node, text: &str) -> Vec<DocumentSymbol> {
+    let mut members = Vec::new();
+
+    for child in node.children(&mut node.walk()) {
+        // Members are either bare identifiers or wrapped with their initializer
+        let ident = match child.kind() {
+            "identifier" if child.id() != name_node.id() => Some(child),
+            "enum_member" => child
+                .children(&mut child.walk())
+                .find(|c| c.kind() == "identifier"),
+            _ => None,
+        };
+        let Some(ident) = ident else {
+            continue;
+        };
+
+        let start = child.start_position();
+        let end = child.end_position();
+        let range = Range::new(
+            Position::new(start.row as u32, start.column as u32),
+            Position::new(end.row as u32, end.column as u32),
+        );
+
+        members.push(DocumentSymbol {
+            name: ident.utf8_text(text.as_bytes()).unwrap_or("").to_string(),
+            detail: None,
+            kind: SymbolKind::ENUM_MEMBER,
+            tags: None,
+            #[allow(deprecated)]
+            deprecated: None,
+            range,
+            selection_range: range,
+            children: None,
+        });
+    }
+
+    members
+}
+
+/// Every slot of a `::Const` table, nested tables included, so the values
+/// can be browsed like enum members.
+fn extract_const_members(node: Node, text: &str) -> Vec<DocumentSymbol> {
+    let mut members = Vec::new();
+
+    for slot in node.children(&mut node.walk()) {
+        if slot.kind() == "table_slots" {
+            members.extend(extract_const_members(slot, text));
+            continue;
+        }
+        if slot.kind() != "table_slot" {
+            continue;
+        }
+        let Some(key) = slot
+            .child_by_field_name("key")
+            .or_else(|| {
+                slot.children(&mut slot.walk())
+                    .find(|c| c.kind() == "identifier")
+            })
+            .filter(|key| matches!(key.kind(), "identifier" | "string"))
+        else {
+            continue;
+        };
+        let value = slot.child_by_field_name("value");
+        let (kind, children) = match value.map(|v| v.kind()) {
+            Some("table") => (
+                SymbolKind::NAMESPACE,
+                value.map(|table| extract_const_members(table, text)),
+            ),
+            Some("lambda_expression" | "anonymous_function") => (SymbolKind::METHOD, None),
+            None if slot
+                .children(&mut slot.walk())
+                .any(|c| c.kind() == "function_declaration") =>
+            {
+                (SymbolKind::METHOD, None)
+            },
+            _ => (SymbolKind::CONSTANT, None),
+        };
+
+        let start = slot.start_position();
+        let end = slot.end_position();
+        let range = Range::new(
+            Position::new(start.row as u32, start.column as u32),
+            Position::new(end.row as u32, end.column as u32),
+        );
+
+        members.push(DocumentSymbol {
+            name: helpers::extract_string_content(key, text),
+            detail: None,
+            kind,
+            tags: None,
+            #[allow(deprecated)]
+            deprecated: None,
+            range,
+            selection_range: range,
+            children,
+        });
+    }
+
+    members
+}
+
 fn extract_table_members(node: Node, text: &str) -> Vec<DocumentSymbol> {
     let mut members = Vec::new();
 
@@ -390,13 +852,13 @@ fn extract_table_members(node: Node, text: &str) -> Vec<DocumentSymbol> {
                 }
             },
             "table_slot" => {
-                if let Some(key) = child.child_by_field_name("key") {
+                if let Some(key) = helpers::table_slot_key(child) {
                     let is_function = child.child_by_field_name("value").is_some_and(|v| {
                         v.kind() == "lambda_expression" || v.kind() == "anonymous_function"
                     });
 
                     if is_function {
-                        let name = key.utf8_text(text.as_bytes()).unwrap_or("").to_string();
+                        let name = helpers::extract_string_content(key, text);
                         let start = child.start_position();
                         let end = child.end_position();
                         let range = Range::new(
@@ -494,56 +956,215 @@ fn extract_table_members(node: Node, text: &str) -> Vec<DocumentSymbol> {
     members
 }
 
+/// Upper bound on workspace symbol results, so broad queries stay responsive.
+const MAX_WORKSPACE_SYMBOLS: usize = 200;
+
 pub fn get_workspace_symbols(query: &str, workspace: &Workspace) -> Vec<SymbolInformation> {
     let query_lower = query.to_lowercase();
-    let mut results = Vec::new();
+    let mut scored: Vec<(i64, SymbolInformation)> = Vec::new();
 
     for (script_path, entry) in workspace.files() {
-        if entry.name.to_lowercase().contains(&query_lower)
-            && let Ok(uri) = Url::from_file_path(&entry.file_path)
-        {
-            results.push(SymbolInformation {
-                name: entry.name.clone(),
-                kind: SymbolKind::CLASS,
+        push_definition_symbols(
+            &mut scored,
+            &query_lower,
+            &entry.name,
+            &entry.file_path,
+            entry.definition_line,
+            script_path,
+            &entry.members,
+        );
+    }
+    for def in workspace.secondary_definitions() {
+        push_definition_symbols(
+            &mut scored,
+            &query_lower,
+            &def.name,
+            &def.file_path,
+            def.line,
+            &def.script_path,
+            &def.members,
+        );
+    }
+    for (file_path, global) in workspace.global_definitions() {
+        let kind = match global.kind {
+            GlobalKind::Enum => SymbolKind::ENUM,
+            GlobalKind::Const => SymbolKind::CONSTANT,
+            // Classes and tables are listed with their members above
+            GlobalKind::Class | GlobalKind::Slot => continue,
+        };
+        let Some(score) = fuzzy_score(&query_lower, &global.name) else {
+            continue;
+        };
+        let Ok(uri) = Url::from_file_path(file_path) else {
+            continue;
+        };
+        let script_path = workspace.script_path_for(file_path);
+        scored.push((
+            score,
+            SymbolInformation {
+                name: global.name.clone(),
+                kind,
                 tags: None,
                 #[allow(deprecated)]
                 deprecated: None,
                 location: Location {
                     uri,
-                    range: Range::new(Position::new(0, 0), Position::new(0, 0)),
+                    range: Range::new(
+                        Position::new(global.line, global.column),
+                        Position::new(global.line, global.end_column),
+                    ),
                 },
-                container_name: Some(script_path.clone()),
-            });
-        }
+                container_name: (!script_path.is_empty()).then_some(script_path),
+            },
+        ));
+    }
 
-        for member in &entry.members {
-            if member.name.to_lowercase().contains(&query_lower)
-                && let Ok(uri) = Url::from_file_path(&entry.file_path)
-            {
-                let kind = match member.member_type {
-                    MemberType::Method => SymbolKind::METHOD,
-                    MemberType::Field => SymbolKind::FIELD,
-                };
-                results.push(SymbolInformation {
+    scored.sort_by(|(a_score, a), (b_score, b)| {
+        b_score
+            .cmp(a_score)
+            .then_with(|| a.name.cmp(&b.name))
+            .then_with(|| a.container_name.cmp(&b.container_name))
+    });
+    scored.truncate(MAX_WORKSPACE_SYMBOLS);
+    scored.into_iter().map(|(_, symbol)| symbol).collect()
+}
+
+/// Add a class or table and its members to `scored` where they match the query.
+fn push_definition_symbols(
+    scored: &mut Vec<(i64, SymbolInformation)>,
+    query_lower: &str,
+    name: &str,
+    file_path: &Path,
+    line: u32,
+    script_path: &str,
+    members: &[MemberInfo],
+) {
+    let Ok(uri) = Url::from_file_path(file_path) else {
+        return;
+    };
+
+    if let Some(score) = fuzzy_score(query_lower, name) {
+        scored.push((
+            score,
+            SymbolInformation {
+                name: name.to_string(),
+                kind: SymbolKind::CLASS,
+                tags: None,
+                #[allow(deprecated)]
+                deprecated: None,
+                location: Location {
+                    uri: uri.clone(),
+                    range: Range::new(Position::new(line, 0), Position::new(line, 0)),
+                },
+                container_name: Some(script_path.to_string()),
+            },
+        ));
+    }
+
+    for member in members {
+        if let Some(score) = fuzzy_score(query_lower, &member.name) {
+            let kind = match member.member_type {
+                MemberType::Method => SymbolKind::METHOD,
+                MemberType::Field => SymbolKind::FIELD,
+            };
+            scored.push((
+                score,
+                SymbolInformation {
                     name: member.name.clone(),
                     kind,
                     tags: None,
                     #[allow(deprecated)]
                     deprecated: None,
                     location: Location {
-                        uri,
+                        uri: uri.clone(),
                         range: Range::new(
                             Position::new(member.line, member.column),
                             Position::new(member.line, member.column),
                         ),
                     },
-                    container_name: Some(entry.name.clone()),
-                });
+                    container_name: Some(name.to_string()),
+                },
+            ));
+        }
+    }
+}
+
+/// Score how well `name` matches an already lowercased query, or `None` if
+/// the query characters don't appear in order.
+///
+/// Exact matches beat prefixes, prefixes beat substrings, and substrings beat
+/// scattered subsequences; within a tier, shorter names rank higher.
+fn fuzzy_score(query_lower: &str, name: &str) -> Option<i64> {
+    let name_lower = name.to_lowercase();
+    let length_penalty = name_lower.chars().count() as i64;
+
+    if query_lower.is_empty() {
+        return Some(-length_penalty);
+    }
+    if name_lower == query_lower {
+        return Some(4000);
+    }
+    if name_lower.starts_with(query_lower) {
+        return Some(3000 - length_penalty);
+    }
+    if let Some(offset) = name_lower.find(query_lower) {
+        return Some(2000 - offset as i64 - length_penalty);
+    }
+
+    // Subsequence: penalize the characters skipped between matches
+    let mut gaps = 0i64;
+    let mut name_chars = name_lower.chars();
+    for query_char in query_lower.chars() {
+        loop {
+            let name_char = name_chars.next()?;
+            if name_char == query_char {
+                break;
+            }
+            gaps += 1;
+        }
+    }
+    Some(1000 - gaps - length_penalty)
+}
+
+/// Ancestor chain, direct children and members of the class at
+/// `script_path`, as JSON for the `squirrel-lsp.classInfo` command.
+/// Inherited members are the ones not overridden closer to the class.
+pub fn class_info(workspace: &Workspace, script_path: &str) -> Option<Value> {
+    let entry = workspace.get(script_path)?;
+    let ancestors = workspace.get_ancestors(&entry.script_path);
+
+    let member_json = |member: &MemberInfo| {
+        json!({
+            "name": member.name,
+            "kind": match member.member_type {
+                MemberType::Method => "method",
+                MemberType::Field => "field",
+            },
+            "line": member.line,
+        })
+    };
+
+    let mut seen: HashSet<&str> = entry.members.iter().map(|m| m.name.as_str()).collect();
+    let mut inherited = Vec::new();
+    for ancestor in &ancestors {
+        for member in &ancestor.members {
+            if seen.insert(member.name.as_str()) {
+                let mut info = member_json(member);
+                info["from"] = json!(ancestor.script_path);
+                inherited.push(info);
             }
         }
     }
 
-    results
+    Some(json!({
+        "scriptPath": entry.script_path,
+        "name": entry.name,
+        "file": (!entry.is_stub()).then_some(&entry.file_path),
+        "ancestors": ancestors.iter().map(|a| &a.script_path).collect::<Vec<_>>(),
+        "children": entry.children,
+        "members": entry.members.iter().map(member_json).collect::<Vec<_>>(),
+        "inheritedMembers": inherited,
+    }))
 }
 
 #[cfg(test)]
@@ -555,21 +1176,433 @@ mod tests {
         let code = r#"this.foo <- this.inherit("scripts/skills/skill", {});"#;
         let pos = Position::new(0, 30); // Inside the string
 
-        let symbol = find_symbol_at_position(code, pos);
+        let tree = helpers::parse_squirrel(code).unwrap();
+        let symbol = find_symbol_at_position(&tree, code, pos);
         assert!(matches!(
             symbol,
             Some(SymbolAtPosition::InheritParentPath(_))
         ));
     }
 
+    #[test]
+    fn test_find_node_at_identifier_edges() {
+        let code = r#"this.getContainer().getActor();"#;
+        let tree = helpers::parse_squirrel(code).unwrap();
+
+        // Start (just after the `.`), middle and just past the end (on `(`)
+        for column in [5, 10, 17] {
+            let symbol = find_symbol_at_position(&tree, code, Position::new(0, column));
+            assert!(
+                matches!(&symbol, Some(SymbolAtPosition::MethodCall(name)) if name == "getContainer"),
+                "column {column}: {symbol:?}"
+            );
+        }
+
+        let start = find_deepest_node_at(tree.root_node(), 20).unwrap();
+        assert_eq!(helpers::node_text(start, code), "getActor");
+    }
+
     #[test]
     fn test_find_method_call() {
         let code = r#"this.getContainer().getActor();"#;
         let pos = Position::new(0, 7); // On "getContainer"
 
-        let symbol = find_symbol_at_position(code, pos);
+        let tree = helpers::parse_squirrel(code).unwrap();
+        let symbol = find_symbol_at_position(&tree, code, pos);
         assert!(matches!(symbol, Some(SymbolAtPosition::MethodCall(_))));
     }
+
+    #[test]
+    fn test_find_definition_of_locals() {
+        let code = "function f(items, count) {\n    local total = count;\n    foreach (item in items) {\n        total += item;\n    }\n    return total;\n}\n";
+        let tree = helpers::parse_squirrel(code).unwrap();
+        let file = Path::new("/test/scripts/a.nut");
+        let workspace = Workspace::new();
+        let goto = |line, character| {
+            find_definition(
+                &tree,
+                code,
+                Position::new(line, character),
+                file,
+                &workspace,
+            )
+            .map(|result| (result.file_path, result.line, result.column))
+        };
+
+        let here = file.to_path_buf();
+        // `item` inside the loop body -> foreach variable
+        assert_eq!(goto(3, 18), Some((here.clone(), 2, 13)));
+        // `total` in the return -> local declaration
+        assert_eq!(goto(5, 12), Some((here.clone(), 1, 10)));
+        // `count` in the initializer -> parameter
+        assert_eq!(goto(1, 20), Some((here, 0, 18)));
+    }
+
+    #[test]
+    fn test_find_super_method() {
+        let mut workspace = Workspace::new();
+        let unit = Path::new("/test/scripts/entity/unit.nut");
+        workspace
+            .index_file(unit, "unit <- {\n    function onDeath() {}\n};\n")
+            .unwrap();
+        workspace
+            .index_file(
+                Path::new("/test/scripts/entity/actor.nut"),
+                "actor <- inherit(\"scripts/entity/unit\", {\n    function onTurnStart() {}\n});\n",
+            )
+            .unwrap();
+        let code = "knight <- inherit(\"scripts/entity/actor\", {\n    function onDeath() {\n        print(1);\n    }\n    function onTurnStart() {}\n    function onMove() {}\n});\n";
+        let file = Path::new("/test/scripts/entity/knight.nut");
+        workspace.index_file(file, code).unwrap();
+        workspace.build_inheritance_graph();
+        let tree = helpers::parse_squirrel(code).unwrap();
+        let find = |line, character| {
+            find_super_method(
+                &tree,
+                code,
+                Position::new(line, character),
+                file,
+                &workspace,
+            )
+            .map(|result| (result.file_path, result.line))
+        };
+
+        // Skips the parent, which doesn't override it, for the grandparent
+        assert_eq!(find(2, 8), Some((unit.to_path_buf(), 1)));
+        assert_eq!(
+            find(4, 14),
+            Some((Path::new("/test/scripts/entity/actor.nut").to_path_buf(), 1))
+        );
+        assert_eq!(find(5, 14), None);
+        assert_eq!(find(0, 0), None);
+    }
+
+    #[test]
+    fn test_declaration_and_definition_of_forward_declared_globals() {
+        let code = "Config <- null;\nif (!(\"Arena\" in ::Legends)) ::Legends.Arena <- null;\n::Legends.Arena <- { Size = 3 };\nConfig <- { Debug = false };\nlocal size = ::Legends.Arena.Size + Config.len();\n";
+        let tree = helpers::parse_squirrel(code).unwrap();
+        let file = Path::new("/mod/scripts/a.nut");
+        let mut workspace = Workspace::new();
+        workspace.index_file(file, code).unwrap();
+        let site = |result: Option<DefinitionResult>| result.map(|r| (r.line, r.column));
+        let arena = Position::new(4, 23);
+        let config = Position::new(4, 36);
+
+        assert_eq!(
+            site(find_declaration(&tree, code, arena, file, &workspace)),
+            Some((1, 29))
+        );
+        assert_eq!(
+            site(find_definition(&tree, code, arena, file, &workspace)),
+            Some((2, 0))
+        );
+        assert_eq!(
+            site(find_declaration(&tree, code, config, file, &workspace)),
+            Some((0, 0))
+        );
+        assert_eq!(
+            site(find_definition(&tree, code, config, file, &workspace)),
+            Some((3, 0))
+        );
+    }
+
+    #[test]
+    fn test_metamethod_at() {
+        let code = "class Vec {\n    function _add(other) { return this; }\n    function add(other) { return this; }\n}\n";
+        let tree = helpers::parse_squirrel(code).unwrap();
+        assert_eq!(
+            metamethod_at(&tree, code, Position::new(1, 14)).as_deref(),
+            Some("_add")
+        );
+        assert!(metamethod_at(&tree, code, Position::new(2, 14)).is_none());
+    }
+
+    #[test]
+    fn test_leading_comment() {
+        let code = r#"local x = 1; // about x
+
+/**
+ * Heals the actor.
+ *   Capped at max hitpoints.
+ */
+function heal(_amount) {}
+
+// Line one
+/// Line two
+function other() {}
+
+function undocumented() {}
+"#;
+        let tree = helpers::parse_squirrel(code).unwrap();
+        assert_eq!(
+            leading_comment(&tree, code, 6).as_deref(),
+            Some("Heals the actor.\n  Capped at max hitpoints.")
+        );
+        assert_eq!(
+            leading_comment(&tree, code, 10).as_deref(),
+            Some("Line one\nLine two")
+        );
+        assert_eq!(leading_comment(&tree, code, 12), None);
+    }
+
+    #[test]
+    fn test_fuzzy_score_tiers() {
+        let exact = fuzzy_score("ondeath", "onDeath").unwrap();
+        let prefix = fuzzy_score("ondeath", "onDeathStart").unwrap();
+        let substring = fuzzy_score("death", "onDeath").unwrap();
+        let subsequence = fuzzy_score("ondth", "onDeath").unwrap();
+        assert!(exact > prefix);
+        assert!(prefix > substring);
+        assert!(substring > subsequence);
+        assert_eq!(fuzzy_score("xyz", "onDeath"), None);
+        assert_eq!(fuzzy_score("htaed", "onDeath"), None);
+    }
+
+    #[test]
+    fn test_fuzzy_score_prefers_shorter_names() {
+        let short = fuzzy_score("get", "getName").unwrap();
+        let long = fuzzy_score("get", "getCurrentProperties").unwrap();
+        assert!(short > long);
+    }
+
+    #[test]
+    fn test_workspace_symbols_ranked() {
+        let mut workspace = Workspace::new();
+        workspace
+            .index_file(
+                Path::new("/test/scripts/entity/tactical/actor.nut"),
+                r#"
+                actor <- {
+                    function onDeath() {}
+                    function onDeathStart() {}
+                    function isAfterDeath() {}
+                    function onDamageTaken() {}
+                };
+                "#,
+            )
+            .unwrap();
+
+        let names: Vec<String> = get_workspace_symbols("onDth", &workspace)
+            .into_iter()
+            .map(|s| s.name)
+            .collect();
+        assert_eq!(names, vec!["onDeath", "onDeathStart"]);
+
+        let names: Vec<String> = get_workspace_symbols("death", &workspace)
+            .into_iter()
+            .map(|s| s.name)
+            .collect();
+        assert_eq!(names, vec!["onDeath", "onDeathStart", "isAfterDeath"]);
+    }
+
+    #[test]
+    fn test_document_links_for_script_paths() {
+        let mut workspace = Workspace::new();
+        workspace
+            .index_file(
+                Path::new("/test/scripts/entity/tactical/actor.nut"),
+                "this.actor <- this.inherit(\"scripts/entity/base\", {\n    function onDeath() {}\n});",
+            )
+            .unwrap();
+
+        let code = r#"this.knight <- this.inherit("scripts/entity/tactical/actor", {});
+::mods_hookExactClass("entity/tactical/actor", function(o) {});
+::mods_hookExactClass("entity/tactical/missing", function(o) {});
+"#;
+        let tree = helpers::parse_squirrel(code).unwrap();
+        let links = compute_document_links(&tree, code, &workspace);
+
+        assert_eq!(links.len(), 2);
+        let target = Url::from_file_path("/test/scripts/entity/tactical/actor.nut").unwrap();
+        assert!(links.iter().all(|l| l.target.as_ref() == Some(&target)));
+        assert_eq!(
+            links[0].range,
+            Range::new(Position::new(0, 29), Position::new(0, 58))
+        );
+        assert_eq!(
+            links[1].range,
+            Range::new(Position::new(1, 23), Position::new(1, 44))
+        );
+    }
+
+    #[test]
+    fn test_workspace_symbols_include_secondary_tables() {
+        let mut workspace = Workspace::new();
+        workspace
+            .index_file(
+                Path::new("/test/scripts/config/strings.nut"),
+                r#"
+strings <- {};
+TooltipStrings <- {
+    function describeTooltip() {}
+};
+"#,
+            )
+            .unwrap();
+
+        let symbols = get_workspace_symbols("tooltip", &workspace);
+        let found: Vec<(&str, Option<&str>)> = symbols
+            .iter()
+            .map(|s| (s.name.as_str(), s.container_name.as_deref()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("TooltipStrings", Some("config/strings")),
+                ("describeTooltip", Some("TooltipStrings"))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_workspace_symbols_include_enums_and_constants() {
+        let mut workspace = Workspace::new();
+        workspace
+            .index_file(
+                Path::new("/test/scripts/config/limits.nut"),
+                "enum LimitKind { Soft, Hard }\nconst MaxLimit = 10;\nlimits <- {};\n",
+            )
+            .unwrap();
+
+        let symbols = get_workspace_symbols("limit", &workspace);
+        let found: Vec<(&str, SymbolKind)> =
+            symbols.iter().map(|s| (s.name.as_str(), s.kind)).collect();
+        assert_eq!(
+            found,
+            vec![
+                ("limits", SymbolKind::CLASS),
+                ("LimitKind", SymbolKind::ENUM),
+                ("MaxLimit", SymbolKind::CONSTANT),
+            ]
+        );
+        assert_eq!(symbols[2].container_name.as_deref(), Some("config/limits"));
+    }
+
+    #[test]
+    fn test_class_info_lists_inherited_members() {
+        let mut workspace = Workspace::new();
+        workspace
+            .index_file(
+                Path::new("/test/scripts/skills/skill.nut"),
+                r#"skill <- { function create() {} function onUse() {} };"#,
+            )
+            .unwrap();
+        workspace
+            .index_file(
+                Path::new("/test/scripts/skills/actives/slash.nut"),
+                r#"this.slash <- this.inherit("scripts/skills/skill", { function onUse() {} });"#,
+            )
+            .unwrap();
+        workspace.build_inheritance_graph();
+
+        let info = class_info(&workspace, "scripts/skills/actives/slash").unwrap();
+        assert_eq!(info["scriptPath"], "skills/actives/slash");
+        assert_eq!(info["ancestors"], json!(["skills/skill"]));
+        assert_eq!(info["members"][0]["name"], "onUse");
+        assert_eq!(
+            info["inheritedMembers"],
+            json!([{ "name": "create", "kind": "method", "line": 0, "from": "skills/skill" }])
+        );
+
+        let parent = class_info(&workspace, "skills/skill").unwrap();
+        assert_eq!(parent["children"], json!(["skills/actives/slash"]));
+        assert!(class_info(&workspace, "skills/missing").is_none());
+    }
+
+    #[test]
+    fn test_document_symbols_enum_and_const() {
+        let code = r#"
+enum Direction {
+    North,
+    South = 2
+}
+const MaxLevel = 11;
+"#;
+        let symbols = get_document_symbols(&helpers::parse_squirrel(code).unwrap(), code);
+
+        let direction = symbols
+            .iter()
+            .find(|s| s.name == "Direction")
+            .expect("enum symbol");
+        assert_eq!(direction.kind, SymbolKind::ENUM);
+        let members: Vec<_> = direction
+            .children
+            .as_ref()
+            .expect("enum members")
+            .iter()
+            .map(|m| (m.name.as_str(), m.kind))
+            .collect();
+        assert_eq!(
+            members,
+            vec![
+                ("North", SymbolKind::ENUM_MEMBER),
+                ("South", SymbolKind::ENUM_MEMBER)
+            ]
+        );
+
+        let max_level = symbols
+            .iter()
+            .find(|s| s.name == "MaxLevel")
+            .expect("const symbol");
+        assert_eq!(max_level.kind, SymbolKind::CONSTANT);
+    }
+
+    #[test]
+    fn test_document_symbols_const_tables() {
+        let code = r#"
+::Const.ItemSlot <- {
+    Mainhand = 0,
+    Offhand = 1,
+    Body = 2,
+    Head = 3
+};
+::Const.Tactical <- {
+    Actor = {
+        Ally = 0,
+        Enemy = 1
+    }
+};
+"#;
+        let symbols = get_document_symbols(&helpers::parse_squirrel(code).unwrap(), code);
+
+        let item_slot = symbols
+            .iter()
+            .find(|s| s.name == "ItemSlot")
+            .expect("const table symbol");
+        let slots: Vec<_> = item_slot
+            .children
+            .as_ref()
+            .expect("const members")
+            .iter()
+            .map(|m| (m.name.as_str(), m.kind))
+            .collect();
+        assert_eq!(slots.len(), 4);
+        assert_eq!(slots[0], ("Mainhand", SymbolKind::CONSTANT));
+
+        let tactical = symbols.iter().find(|s| s.name == "Tactical").unwrap();
+        let actor = &tactical.children.as_ref().unwrap()[0];
+        assert_eq!(
+            (actor.name.as_str(), actor.kind),
+            ("Actor", SymbolKind::NAMESPACE)
+        );
+        assert_eq!(actor.children.as_ref().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_document_symbols_local_table_functions() {
+        let code = r#"
+local helpers = {
+    function clamp(_v) { return _v; }
+};
+"#;
+        let symbols = get_document_symbols(&helpers::parse_squirrel(code).unwrap(), code);
+        let helpers = symbols
+            .iter()
+            .find(|s| s.name == "helpers")
+            .expect("local symbol");
+        let children = helpers.children.as_ref().expect("table members");
+        assert!(children.iter().any(|c| c.name == "clamp"));
+    }
 }
 
 #[cfg(test)]
@@ -582,7 +1615,7 @@ mod symbol_tests {
             std::fs::read_to_string("/home/antoine/bb-ws/base_bb/scripts/skills/skill.nut")
                 .expect("Should read file");
 
-        let symbols = get_document_symbols(&content);
+        let symbols = get_document_symbols(&helpers::parse_squirrel(&content).unwrap(), &content);
         eprintln!("Document symbols count: {}", symbols.len());
         for s in &symbols {
             eprintln!("  - {} ({:?})", s.name, s.kind);
@@ -0,0 +1,486 @@
+//! Workspace-wide rename of class methods.
+//!
+//! Squirrel dispatches dynamically, so only accesses whose receiver is known
+//! are renamed: declarations and `this.name` in the files defining the class
+//! or one of its descendants, and `this.name` or `o.name` inside hooks on
+//! those classes. Calls through any other variable are left alone.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use tower_lsp::lsp_types::{Position, Range, TextEdit, Url};
+use tree_sitter::{Node, Point, Tree};
+
+use crate::bb_support::find_hook_calls;
+use crate::helpers;
+use crate::navigation::find_deepest_node_at;
+use crate::workspace::{FileEntry, MemberType, Workspace};
+
+const RESERVED_WORDS: &[&str] = &[
+    "base",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "clone",
+    "const",
+    "constructor",
+    "continue",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "enum",
+    "extends",
+    "false",
+    "for",
+    "foreach",
+    "function",
+    "if",
+    "in",
+    "instanceof",
+    "local",
+    "null",
+    "resume",
+    "return",
+    "static",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "while",
+    "yield",
+];
+
+/// Whether `name` can be used as a method name.
+pub fn is_valid_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !RESERVED_WORDS.contains(&name)
+}
+
+/// Why [`rename_method`] has no edits to offer.
+#[derive(Debug, PartialEq, Eq)]
+pub enum RenameError {
+    /// The cursor isn't on a method of an indexed class.
+    NotAMethod,
+    /// The method is declared by an engine class stub, so the engine calls
+    /// it by name and renaming it would break every override.
+    EngineMethod(String),
+}
+
+/// The method under the cursor and the files that may refer to it.
+#[derive(Debug)]
+pub struct RenameTarget {
+    name: String,
+    /// The class defining the method and its descendants
+    classes: HashSet<String>,
+    /// Files other than the current one that may refer to the method
+    pub files: Vec<PathBuf>,
+}
+
+/// A file read and parsed for [`rename_edits`].
+pub struct ParsedFile {
+    pub path: PathBuf,
+    pub text: String,
+    pub tree: Tree,
+}
+
+/// Edits renaming the method under the cursor to `new_name`, keyed by file.
+/// `read` returns the current text of files other than `current_file`.
+pub fn rename_method(
+    tree: &Tree,
+    text: &str,
+    position: Position,
+    current_file: &Path,
+    new_name: &str,
+    workspace: &Workspace,
+    read: impl Fn(&Path) -> Option<String>,
+) -> Result<HashMap<Url, Vec<TextEdit>>, RenameError> {
+    let target = rename_target(tree, text, position, current_file, workspace)?;
+    let others = parse_files(&target.files, read);
+    Ok(rename_edits(
+        &target,
+        tree,
+        text,
+        current_file,
+        &others,
+        new_name,
+        workspace,
+    ))
+}
+
+/// Find the method under the cursor and the files to look for it in. This
+/// only consults the workspace index; no file is read.
+pub fn rename_target(
+    tree: &Tree,
+    text: &str,
+    position: Position,
+    current_file: &Path,
+    workspace: &Workspace,
+) -> Result<RenameTarget, RenameError> {
+    let node = helpers::byte_offset_at(text, position)
+        .and_then(|offset| find_deepest_node_at(tree.root_node(), offset))
+        .filter(|node| node.kind() == "identifier")
+        .ok_or(RenameError::NotAMethod)?;
+    let name = helpers::node_text(node, text);
+    let class =
+        receiver_class(node, tree, text, current_file, workspace).ok_or(RenameError::NotAMethod)?;
+    let classes = affected_classes(workspace, &class, name)?;
+
+    let mut files: Vec<PathBuf> = workspace
+        .files_mentioning(name)
+        .into_iter()
+        .cloned()
+        .collect();
+    files.extend(
+        classes
+            .iter()
+            .filter_map(|script_path| workspace.get(script_path))
+            .map(|entry| entry.file_path.clone()),
+    );
+    files.retain(|file| file != current_file);
+    files.sort();
+    files.dedup();
+
+    Ok(RenameTarget {
+        name: name.to_string(),
+        classes,
+        files,
+    })
+}
+
+/// Read and parse `files`, skipping those `read` can't provide or that fail
+/// to parse.
+pub fn parse_files(files: &[PathBuf], read: impl Fn(&Path) -> Option<String>) -> Vec<ParsedFile> {
+    files
+        .iter()
+        .filter_map(|path| {
+            let text = read(path)?;
+            let tree = helpers::parse_squirrel(&text).ok()?;
+            Some(ParsedFile {
+                path: path.clone(),
+                text,
+                tree,
+            })
+        })
+        .collect()
+}
+
+/// Edits renaming `target` to `new_name` in the current file and `others`.
+pub fn rename_edits(
+    target: &RenameTarget,
+    tree: &Tree,
+    text: &str,
+    current_file: &Path,
+    others: &[ParsedFile],
+    new_name: &str,
+    workspace: &Workspace,
+) -> HashMap<Url, Vec<TextEdit>> {
+    let current = std::iter::once((current_file, tree, text));
+    let others = others
+        .iter()
+        .map(|file| (file.path.as_path(), &file.tree, file.text.as_str()));
+
+    let mut changes = HashMap::new();
+    for (file, tree, text) in current.chain(others) {
+        let ranges = method_sites(tree, text, file, &target.name, &target.classes, workspace);
+        if ranges.is_empty() {
+            continue;
+        }
+        let Ok(uri) = Url::from_file_path(file) else {
+            continue;
+        };
+        let edits = ranges
+            .into_iter()
+            .map(|range| TextEdit::new(range, new_name.to_string()))
+            .collect();
+        changes.insert(uri, edits);
+    }
+    changes
+}
+
+/// The class whose method `node` names: the hooked class for `this.name`
+/// or `o.name` inside a hook, otherwise the class the file defines.
+fn receiver_class(
+    node: Node,
+    tree: &Tree,
+    text: &str,
+    current_file: &Path,
+    workspace: &Workspace,
+) -> Option<String> {
+    let receiver = receiver_of(node, text);
+
+    for hook in find_hook_calls(tree.root_node(), text) {
+        let function = hook.hook_function;
+        if node.start_byte() < function.start_byte() || node.end_byte() > function.end_byte() {
+            continue;
+        }
+        let hooked = receiver == Some("this")
+            || receiver.is_some_and(|r| hook.hook_param_name.as_deref() == Some(r));
+        return hooked
+            .then(|| workspace.get(&hook.target_path))
+            .flatten()
+            .map(|entry| entry.script_path.clone());
+    }
+
+    let is_declaration = node.parent().is_some_and(|parent| {
+        matches!(
+            parent.kind(),
+            "function_declaration" | "table_slot" | "class_member"
+        )
+    });
+    if receiver != Some("this") && !is_declaration {
+        return None;
+    }
+    let entry = workspace.get(&workspace.script_path_for(current_file))?;
+    (entry.file_path == current_file).then(|| entry.script_path.clone())
+}
+
+/// The receiver text when `node` is the member of a `receiver.member` access.
+fn receiver_of<'a>(node: Node, text: &'a str) -> Option<&'a str> {
+    let parent = node.parent()?;
+    if parent.kind() != "deref_expression"
+        || parent.named_child_count() != 2
+        || parent.named_child(1)?.id() != node.id()
+    {
+        return None;
+    }
+    Some(helpers::node_text(parent.named_child(0)?, text))
+}
+
+/// The class defining the method highest up the chain from `class`, and every
+/// class descending from it, so overrides are renamed together.
+///
+/// Stubs list engine callbacks such as `onUse`, so a method declared by one
+/// is refused rather than taken as the root.
+fn affected_classes(
+    workspace: &Workspace,
+    class: &str,
+    name: &str,
+) -> Result<HashSet<String>, RenameError> {
+    let defining = |entry: &FileEntry| {
+        entry
+            .members
+            .iter()
+            .any(|m| m.name == name && m.member_type == MemberType::Method)
+    };
+
+    let mut root = None;
+    let own = workspace.get(class).into_iter();
+    for entry in own.chain(workspace.get_ancestors(class)) {
+        if !defining(entry) {
+            continue;
+        }
+        if entry.is_stub() {
+            return Err(RenameError::EngineMethod(entry.script_path.clone()));
+        }
+        root = Some(entry.script_path.clone());
+    }
+
+    let mut classes = HashSet::new();
+    let mut pending = vec![root.ok_or(RenameError::NotAMethod)?];
+    while let Some(script_path) = pending.pop() {
+        if let Some(entry) = workspace.get(&script_path)
+            && classes.insert(entry.script_path.clone())
+        {
+            pending.extend(entry.children.iter().cloned());
+        }
+    }
+    Ok(classes)
+}
+
+/// Ranges of `name` in one file that refer to the method of `classes`.
+fn method_sites(
+    tree: &Tree,
+    text: &str,
+    file: &Path,
+    name: &str,
+    classes: &HashSet<String>,
+    workspace: &Workspace,
+) -> Vec<Range> {
+    let root = tree.root_node();
+    let mut ranges = Vec::new();
+
+    if let Some(entry) = workspace.get(&workspace.script_path_for(file))
+        && entry.file_path == file
+        && classes.contains(&entry.script_path)
+    {
+        for member in entry.members.iter().filter(|m| m.name == name) {
+            let point = Point::new(member.line as usize, member.column as usize);
+            if let Some(ident) = root.descendant_for_point_range(point, point)
+                && ident.kind() == "identifier"
+                && helpers::node_text(ident, text) == name
+            {
+                ranges.push(node_range(ident, text));
+            }
+        }
+        collect_accesses(root, text, name, &["this"], &mut ranges);
+    }
+
+    for hook in find_hook_calls(root, text) {
+        let hooked = workspace
+            .get(&hook.target_path)
+            .is_some_and(|entry| classes.contains(&entry.script_path));
+        if !hooked {
+            continue;
+        }
+        let mut receivers = vec!["this"];
+        receivers.extend(hook.hook_param_name.as_deref());
+        collect_accesses(hook.hook_function, text, name, &receivers, &mut ranges);
+    }
+
+    ranges.sort_by_key(|r| (r.start.line, r.start.character));
+    ranges.dedup();
+    ranges
+}
+
+fn collect_accesses(
+    node: Node,
+    text: &str,
+    name: &str,
+    receivers: &[&str],
+    ranges: &mut Vec<Range>,
+) {
+    if node.kind() == "identifier"
+        && helpers::node_text(node, text) == name
+        && receiver_of(node, text).is_some_and(|r| receivers.contains(&r))
+    {
+        ranges.push(node_range(node, text));
+    }
+    for child in node.children(&mut node.walk()) {
+        collect_accesses(child, text, name, receivers, ranges);
+    }
+}
+
+fn node_range(node: Node, text: &str) -> Range {
+    Range::new(
+        helpers::position_at(text, node.start_byte()),
+        helpers::position_at(text, node.end_byte()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SKILL: &str = "/test/scripts/skills/skill.nut";
+    const SLASH: &str = "/test/scripts/skills/slash.nut";
+    const HOOK: &str = "/test/scripts/mods/hook_slash.nut";
+
+    fn sources() -> HashMap<PathBuf, String> {
+        HashMap::from([
+            (
+                PathBuf::from(SKILL),
+                "skill <- {\n    function onUse() {}\n    function use() { this.onUse(); }\n};\n"
+                    .to_string(),
+            ),
+            (
+                PathBuf::from(SLASH),
+                "this.slash <- this.inherit(\"scripts/skills/skill\", {\n    function onUse(_user) { _user.onUse(); }\n});\n"
+                    .to_string(),
+            ),
+            (
+                PathBuf::from(HOOK),
+                "::mods_hookExactClass(\"skills/slash\", function(o) {\n    local onUse = o.onUse;\n    o.onUse = function(_user) { onUse(_user); };\n});\n"
+                    .to_string(),
+            ),
+        ])
+    }
+
+    #[test]
+    fn test_rename_method_across_hierarchy_and_hooks() {
+        let sources = sources();
+        let mut workspace = Workspace::new();
+        for (path, text) in &sources {
+            workspace.index_file(path, text).unwrap();
+        }
+        workspace.build_inheritance_graph();
+
+        let text = &sources[&PathBuf::from(SKILL)];
+        let tree = helpers::parse_squirrel(text).unwrap();
+        let changes = rename_method(
+            &tree,
+            text,
+            Position::new(2, 28),
+            Path::new(SKILL),
+            "onExecute",
+            &workspace,
+            |path| sources.get(path).cloned(),
+        )
+        .unwrap();
+
+        let lines = |file: &str| -> Vec<u32> {
+            let uri = Url::from_file_path(file).unwrap();
+            changes[&uri].iter().map(|e| e.range.start.line).collect()
+        };
+        assert_eq!(lines(SKILL), vec![1, 2]);
+        // `_user.onUse()` has an unknown receiver and is left alone
+        assert_eq!(lines(SLASH), vec![1]);
+        assert_eq!(lines(HOOK), vec![1, 2]);
+        assert_eq!(
+            changes[&Url::from_file_path(SLASH).unwrap()][0]
+                .range
+                .start
+                .character,
+            13
+        );
+    }
+
+    #[test]
+    fn test_rename_rejects_unknown_receivers_and_bad_names() {
+        let sources = sources();
+        let mut workspace = Workspace::new();
+        for (path, text) in &sources {
+            workspace.index_file(path, text).unwrap();
+        }
+        workspace.build_inheritance_graph();
+
+        let text = &sources[&PathBuf::from(SLASH)];
+        let tree = helpers::parse_squirrel(text).unwrap();
+        let on_user = rename_method(
+            &tree,
+            text,
+            Position::new(1, 34),
+            Path::new(SLASH),
+            "onExecute",
+            &workspace,
+            |path| sources.get(path).cloned(),
+        );
+        assert_eq!(on_user, Err(RenameError::NotAMethod));
+
+        assert!(is_valid_identifier("onExecute"));
+        assert!(!is_valid_identifier("2fast"));
+        assert!(!is_valid_identifier("local"));
+    }
+
+    #[test]
+    fn test_rename_refuses_engine_callbacks() {
+        let sources = sources();
+        let mut workspace = Workspace::new();
+        workspace.load_stubs("skills/skill | | onUse");
+        let slash = &sources[&PathBuf::from(SLASH)];
+        workspace.index_file(Path::new(SLASH), slash).unwrap();
+        workspace.build_inheritance_graph();
+
+        let tree = helpers::parse_squirrel(slash).unwrap();
+        let changes = rename_method(
+            &tree,
+            slash,
+            Position::new(1, 14),
+            Path::new(SLASH),
+            "onExecute",
+            &workspace,
+            |path| sources.get(path).cloned(),
+        );
+        assert_eq!(
+            changes,
+            Err(RenameError::EngineMethod("skills/skill".to_string()))
+        );
+    }
+}
@@ -2,8 +2,12 @@
 //!
 //! This module provides semantic tokens for syntax highlighting in editors.
 
-use tower_lsp::lsp_types::SemanticToken;
+use std::collections::HashSet;
 
+use tower_lsp::lsp_types::{SemanticToken, SemanticTokensEdit};
+use tree_sitter::{Node, Tree};
+
+use crate::bb_support::get_node_text;
 use crate::errors::AnalysisError;
 use crate::helpers;
 
@@ -20,16 +24,23 @@ const TOKEN_TYPE_STRING: u32 = 18;
 const TOKEN_TYPE_NUMBER: u32 = 19;
 const TOKEN_TYPE_OPERATOR: u32 = 21;
 
+const MODIFIER_DECLARATION: u32 = 1 << 0;
+const MODIFIER_READONLY: u32 = 1 << 2;
+const MODIFIER_STATIC: u32 = 1 << 3;
+
 pub fn compute_semantic_tokens(text: &str) -> Result<Vec<SemanticToken>, AnalysisError> {
     let tree = helpers::parse_squirrel(text)?;
+    Ok(compute_semantic_tokens_with_tree(&tree, text))
+}
+
+/// Like `compute_semantic_tokens`, but reuses an already parsed tree.
+pub fn compute_semantic_tokens_with_tree(tree: &Tree, text: &str) -> Vec<SemanticToken> {
     let root = tree.root_node();
 
     let mut tokens: Vec<(usize, usize, u32, u32)> = Vec::new();
     let mut cursor = root.walk();
     let mut visited_children = false;
-
-    const MODIFIER_DECLARATION: u32 = 1 << 0;
-    const MODIFIER_READONLY: u32 = 1 << 2;
+    let names = ModifierNames::collect(root, text);
 
     loop {
         let node = cursor.node();
@@ -103,6 +114,12 @@ pub fn compute_semantic_tokens(text: &str) -> Result<Vec<SemanticToken>, Analysi
                 _ => (None, 0),
             };
 
+            let modifiers = if kind == "identifier" {
+                modifiers | names.modifiers(node, text)
+            } else {
+                modifiers
+            };
+
             if let Some(token_type) = token_type {
                 let start_byte = node.start_byte();
                 let end_byte = node.end_byte();
@@ -184,5 +201,274 @@ pub fn compute_semantic_tokens(text: &str) -> Result<Vec<SemanticToken>, Analysi
         }
     }
 
-    Ok(semantic_tokens)
+    semantic_tokens
+}
+
+/// Edits that turn `old` into `new`, for a `semanticTokens/full/delta`
+/// response: whatever lies between their common prefix and suffix is
+/// replaced in one edit. Offsets count integers, five per token.
+pub fn diff_semantic_tokens(
+    old: &[SemanticToken],
+    new: &[SemanticToken],
+) -> Vec<SemanticTokensEdit> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let deleted = old.len() - prefix - suffix;
+    let inserted = &new[prefix..new.len() - suffix];
+    if deleted == 0 && inserted.is_empty() {
+        return Vec::new();
+    }
+
+    vec![SemanticTokensEdit {
+        start: (prefix * 5) as u32,
+        delete_count: (deleted * 5) as u32,
+        data: Some(inserted.to_vec()),
+    }]
+}
+
+/// Names declared in the file whose references carry a modifier: constants
+/// and enum members are read-only, static class members are static.
+#[derive(Default)]
+struct ModifierNames<'a> {
+    constants: HashSet<&'a str>,
+    enums: HashSet<&'a str>,
+    /// Class name and member name of each static member
+    statics: HashSet<(&'a str, &'a str)>,
+}
+
+impl<'a> ModifierNames<'a> {
+    fn collect(root: Node, text: &'a str) -> Self {
+        let mut names = Self::default();
+        let mut pending = vec![root];
+        while let Some(node) = pending.pop() {
+            let name = || {
+                node.children(&mut node.walk())
+                    .find(|c| c.kind() == "identifier")
+                    .map(|ident| get_node_text(ident, text))
+            };
+            match node.kind() {
+                "const_declaration" => names.constants.extend(name()),
+                "enum_declaration" => names.enums.extend(name()),
+                "class_member"
+                    if node
+                        .children(&mut node.walk())
+                        .any(|c| c.kind() == "static") =>
+                {
+                    if let Some(class) = enclosing_class_name(node, text) {
+                        names.statics.extend(name().map(|member| (class, member)));
+                    }
+                },
+                _ => {},
+            }
+            pending.extend(node.children(&mut node.walk()));
+        }
+        names
+    }
+
+    /// Modifiers for an identifier: `Color.Red` reads an enum member and
+    /// `MAX` a constant unless a local hides it, while a static member is
+    /// static where it's declared and when reached as `Class.member` or
+    /// `this.member`.
+    fn modifiers(&self, node: Node, text: &str) -> u32 {
+        let name = get_node_text(node, text);
+        let mut modifiers = 0;
+
+        let object = node
+            .parent()
+            .filter(|parent| parent.kind() == "deref_expression")
+            .filter(|parent| parent.named_child(1) == Some(node))
+            .and_then(|parent| parent.named_child(0));
+        let class = match object {
+            Some(object) if get_node_text(object, text) == "this" => {
+                enclosing_class_name(node, text)
+            },
+            Some(object) if object.kind() == "identifier" => Some(get_node_text(object, text)),
+            Some(_) => None,
+            None => node
+                .parent()
+                .filter(|parent| parent.kind() == "class_member")
+                .and_then(|_| enclosing_class_name(node, text)),
+        };
+        if class.is_some_and(|class| self.statics.contains(&(class, name))) {
+            modifiers |= MODIFIER_STATIC;
+        }
+
+        let readonly = match object {
+            Some(object) => {
+                let enum_name = get_node_text(object, text);
+                object.kind() == "identifier"
+                    && self.enums.contains(enum_name)
+                    && !is_shadowed(object, enum_name, text)
+            },
+            None => self.constants.contains(name) && !is_shadowed(node, name, text),
+        };
+        if readonly {
+            modifiers |= MODIFIER_READONLY;
+        }
+        modifiers
+    }
+}
+
+/// Name of the class declaration `node` is inside, if any.
+fn enclosing_class_name<'a>(node: Node, text: &'a str) -> Option<&'a str> {
+    let mut current = node.parent();
+    while let Some(ancestor) = current {
+        if ancestor.kind() == "class_declaration" {
+            return ancestor
+                .children(&mut ancestor.walk())
+                .find(|child| child.kind() == "identifier")
+                .map(|ident| get_node_text(ident, text));
+        }
+        current = ancestor.parent();
+    }
+    None
+}
+
+/// Whether `node` is, or is in scope of, a local, parameter, loop or catch
+/// variable called `name`, which hides a constant or enum of that name.
+fn is_shadowed(node: Node, name: &str, text: &str) -> bool {
+    let named = |ident: Node| get_node_text(ident, text) == name;
+    let mut child = node;
+    while let Some(scope) = child.parent() {
+        let declares = match scope.kind() {
+            "function_declaration" | "lambda_expression" | "anonymous_function" => scope
+                .children(&mut scope.walk())
+                .filter(|c| c.kind() == "parameters")
+                .any(|params| {
+                    params
+                        .children(&mut params.walk())
+                        .filter_map(helpers::parameter_name)
+                        .any(named)
+                }),
+            "foreach_statement" => ["index", "value"]
+                .into_iter()
+                .filter_map(|field| scope.child_by_field_name(field))
+                .any(named),
+            "catch_statement" => scope
+                .children(&mut scope.walk())
+                .any(|c| c.kind() == "identifier" && named(c)),
+            // The name of a local declaration, but not its initializer
+            "local_declaration" | "var_statement" => declared_names(scope).contains(&child),
+            _ => false,
+        };
+        let declared_before = scope
+            .children(&mut scope.walk())
+            .take_while(|sibling| *sibling != child)
+            .filter(|sibling| matches!(sibling.kind(), "local_declaration" | "var_statement"))
+            .any(|decl| declared_names(decl).into_iter().any(named));
+        if declares || declared_before {
+            return true;
+        }
+        child = scope;
+    }
+    false
+}
+
+/// Identifiers a `local` or `var` statement declares, skipping the ones in
+/// initializers.
+fn declared_names(node: Node) -> Vec<Node> {
+    let mut names = Vec::new();
+    let mut expect_name = true;
+    for child in node.children(&mut node.walk()) {
+        match child.kind() {
+            "local" | "var" | "," => expect_name = true,
+            "=" => expect_name = false,
+            "identifier" if expect_name => {
+                names.push(child);
+                expect_name = false;
+            },
+            _ => {},
+        }
+    }
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Modifier bitmask of the token starting at `line`:`character`
+    fn modifiers_at(code: &str, line: u32, character: u32) -> Option<u32> {
+        let (mut current_line, mut current_col) = (0, 0);
+        compute_semantic_tokens(code)
+            .unwrap()
+            .into_iter()
+            .find_map(|token| {
+                current_line += token.delta_line;
+                current_col = if token.delta_line == 0 {
+                    current_col + token.delta_start
+                } else {
+                    token.delta_start
+                };
+                (current_line == line && current_col == character)
+                    .then_some(token.token_modifiers_bitset)
+            })
+    }
+
+    #[test]
+    fn test_diff_semantic_tokens() {
+        let old = compute_semantic_tokens("local a = 1;\nlocal b = a;\nlocal c = b;\n").unwrap();
+        let new =
+            compute_semantic_tokens("local a = 1;\nlocal b = a + a;\nlocal c = b;\n").unwrap();
+
+        let edits = diff_semantic_tokens(&old, &new);
+        assert_eq!(edits.len(), 1);
+        let mut patched = old.clone();
+        let edit = &edits[0];
+        let start = edit.start as usize / 5;
+        let end = start + edit.delete_count as usize / 5;
+        patched.splice(start..end, edit.data.clone().unwrap_or_default());
+        assert_eq!(patched, new);
+
+        assert!(diff_semantic_tokens(&new, &new).is_empty());
+    }
+
+    #[test]
+    fn test_const_reference_is_readonly() {
+        let code = "const MAX = 10;\nlocal limit = MAX;\nlocal other = limit;\n";
+        assert_eq!(
+            modifiers_at(code, 0, 6),
+            Some(MODIFIER_DECLARATION | MODIFIER_READONLY)
+        );
+        assert_eq!(modifiers_at(code, 1, 14), Some(MODIFIER_READONLY));
+        assert_eq!(modifiers_at(code, 2, 14), Some(0));
+    }
+
+    #[test]
+    fn test_enum_member_reference_is_readonly() {
+        let code = "enum Color { Red, Green }\nlocal c = Color.Red;\n";
+        assert_eq!(modifiers_at(code, 1, 16), Some(MODIFIER_READONLY));
+    }
+
+    #[test]
+    fn test_static_member_is_static() {
+        let code = "class Counter {\n    static count = 0;\n    function bump() { return Counter.count; }\n}\n";
+        assert_eq!(
+            modifiers_at(code, 1, 11).map(|m| m & MODIFIER_STATIC),
+            Some(MODIFIER_STATIC)
+        );
+        assert_eq!(modifiers_at(code, 2, 37), Some(MODIFIER_STATIC));
+    }
+
+    #[test]
+    fn test_static_only_through_class_or_this() {
+        let code = "class Counter {\n    static count = 0;\n    function bump() { return this.count; }\n}\nlocal count = 1;\nlocal other = count;\nlocal y = other.count;\n";
+        assert_eq!(modifiers_at(code, 2, 34), Some(MODIFIER_STATIC));
+        assert_eq!(modifiers_at(code, 5, 14), Some(0));
+        assert_eq!(modifiers_at(code, 6, 16), Some(0));
+    }
+
+    #[test]
+    fn test_shadowed_const_is_not_readonly() {
+        let code = "const MAX = 10;\nfunction f(MAX) { return MAX; }\nfunction g() { local MAX = 1; return MAX; }\nlocal h = MAX;\n";
+        assert_eq!(modifiers_at(code, 1, 25), Some(0));
+        assert_eq!(modifiers_at(code, 2, 21), Some(MODIFIER_DECLARATION));
+        assert_eq!(modifiers_at(code, 2, 37), Some(0));
+        assert_eq!(modifiers_at(code, 3, 10), Some(MODIFIER_READONLY));
+    }
 }
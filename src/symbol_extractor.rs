@@ -4,7 +4,7 @@
 //! all the definitions in a file.
 
 use tower_lsp::lsp_types::Position;
-use tree_sitter::Node;
+use tree_sitter::{Node, Tree};
 
 use crate::errors::AnalysisError;
 use crate::helpers;
@@ -13,6 +13,11 @@ use crate::symbols::{FileSymbols, Symbol, SymbolKind, SymbolMap, Table, extract_
 /// Extract symbols from a Squirrel file
 pub fn extract_file_symbols(file_path: &str, text: &str) -> Result<FileSymbols, AnalysisError> {
     let tree = helpers::parse_squirrel(text)?;
+    Ok(extract_file_symbols_with_tree(file_path, &tree, text))
+}
+
+/// Like `extract_file_symbols`, but reuses an already parsed tree.
+pub fn extract_file_symbols_with_tree(file_path: &str, tree: &Tree, text: &str) -> FileSymbols {
     let root = tree.root_node();
 
     let script_path = extract_script_path(file_path);
@@ -28,7 +33,7 @@ pub fn extract_file_symbols(file_path: &str, text: &str) -> Result<FileSymbols,
         process_top_level_node(child, text, &mut file_symbols, &script_path);
     }
 
-    Ok(file_symbols)
+    file_symbols
 }
 
 fn process_top_level_node(
@@ -37,8 +42,41 @@ fn process_top_level_node(
     file_symbols: &mut FileSymbols,
     script_path: &str,
 ) {
-    if node.kind() == "update_expression" {
-        process_update_expression(node, text, file_symbols, script_path);
+    match node.kind() {
+        "update_expression" => process_update_expression(node, text, file_symbols, script_path),
+        "function_declaration" => process_function_declaration(node, text, file_symbols),
+        _ => {},
+    }
+}
+
+/// Record a top-level `function name(...) {}` as a function symbol.
+fn process_function_declaration(node: Node, text: &str, file_symbols: &mut FileSymbols) {
+    let Some(name) = declared_function_name(node) else {
+        return;
+    };
+    let symbol = Symbol {
+        kind: extract_symbol_from_value(node, text),
+        defined_at: Position {
+            line: name.start_position().row as u32,
+            character: name.start_position().column as u32,
+        },
+    };
+    file_symbols
+        .symbols
+        .insert(helpers::node_text(name, text).to_string(), symbol);
+}
+
+/// Name of a plain `function name(...)` declaration. `function Foo::bar()`
+/// defines a member of `Foo` and has none.
+pub(crate) fn declared_function_name(node: Node) -> Option<Node> {
+    let names: Vec<Node> = node
+        .children(&mut node.walk())
+        .take_while(|c| c.kind() != "parameters")
+        .filter(|c| c.kind() == "identifier")
+        .collect();
+    match names.as_slice() {
+        [name] => Some(*name),
+        _ => None,
     }
 }
 
@@ -121,7 +159,7 @@ fn extract_lhs_name(node: Node, text: &str) -> Option<String> {
     }
 }
 
-fn extract_symbol_from_value(node: Node, text: &str) -> SymbolKind {
+pub(crate) fn extract_symbol_from_value(node: Node, text: &str) -> SymbolKind {
     match node.kind() {
         "table" => {
             let slots = extract_table_slots(node, text);
@@ -147,7 +185,15 @@ fn extract_symbol_from_value(node: Node, text: &str) -> SymbolKind {
         },
         "function_declaration" | "lambda_expression" | "anonymous_function" => {
             let params = extract_function_params(node, text);
-            SymbolKind::Function { params }
+            let required = count_required_params(node);
+            let vararg = node
+                .children(&mut node.walk())
+                .any(|c| c.kind() == "parameters" && helpers::has_varargs(c));
+            SymbolKind::Function {
+                params,
+                required,
+                vararg,
+            }
         },
         _ => SymbolKind::Variable,
     }
@@ -212,16 +258,13 @@ fn extract_assignment_slot(node: Node, text: &str) -> Option<(String, Symbol)> {
 }
 
 fn extract_table_slot(slot_node: Node, text: &str) -> Option<(String, Symbol)> {
-    let mut name: Option<String> = None;
+    let mut name_node = helpers::table_slot_key(slot_node);
+    let mut name = name_node.map(|key| helpers::extract_string_content(key, text));
     let mut value_node: Option<Node> = None;
-    let mut name_node: Option<Node> = None;
 
     for child in slot_node.children(&mut slot_node.walk()) {
         match child.kind() {
-            "identifier" if name.is_none() => {
-                name = Some(helpers::node_text(child, text).to_string());
-                name_node = Some(child);
-            },
+            _ if Some(child) == name_node => {},
             "function_declaration" => {
                 if let Some(fn_name_node) = find_first_identifier(child) {
                     name = Some(helpers::node_text(fn_name_node, text).to_string());
@@ -229,7 +272,7 @@ fn extract_table_slot(slot_node: Node, text: &str) -> Option<(String, Symbol)> {
                 }
                 value_node = Some(child);
             },
-            "=" | "," => {},
+            "=" | ":" | "," => {},
             _ if value_node.is_none() && name.is_some() => {
                 value_node = Some(child);
             },
@@ -315,7 +358,7 @@ fn extract_function_params(node: Node, text: &str) -> Vec<String> {
         if child.kind() == "parameters" {
             for param in child.children(&mut child.walk()) {
                 if param.kind() == "parameter"
-                    && let Some(ident) = find_first_identifier(param)
+                    && let Some(ident) = helpers::parameter_name(param)
                 {
                     params.push(helpers::node_text(ident, text).to_string());
                 }
@@ -326,6 +369,25 @@ fn extract_function_params(node: Node, text: &str) -> Vec<String> {
     params
 }
 
+fn count_required_params(node: Node) -> usize {
+    let mut required = 0;
+
+    for child in node.children(&mut node.walk()) {
+        if child.kind() == "parameters" {
+            required += child
+                .children(&mut child.walk())
+                .filter(|&param| {
+                    param.kind() == "parameter"
+                        && helpers::parameter_name(param).is_some()
+                        && helpers::parameter_default(param).is_none()
+                })
+                .count();
+        }
+    }
+
+    required
+}
+
 fn find_first_identifier(node: Node) -> Option<Node> {
     node.children(&mut node.walk())
         .find(|&child| child.kind() == "identifier")
@@ -335,6 +397,46 @@ fn find_first_identifier(node: Node) -> Option<Node> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_function_params_with_defaults_and_varargs() {
+        let code = r#"
+            format <- function (_text, _width = 10, ...) {
+                return _text;
+            };
+        "#;
+        let file_symbols = extract_file_symbols("scripts/utils.nut", code).unwrap();
+        let symbol = file_symbols.symbols.get("format").expect("function symbol");
+        match &symbol.kind {
+            SymbolKind::Function {
+                params,
+                required,
+                vararg,
+            } => {
+                assert_eq!(params, &vec!["_text".to_string(), "_width".to_string()]);
+                assert_eq!(*required, 1);
+                assert!(vararg);
+            },
+            other => panic!("expected function, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_top_level_function_declaration() {
+        let code = "function clamp(value, low, high = 1) {\n    return value;\n}\nfunction Foo::bar() {}\n";
+        let file_symbols = extract_file_symbols("scripts/utils.nut", code).unwrap();
+        let clamp = &file_symbols.symbols["clamp"];
+        assert_eq!(clamp.defined_at, Position::new(0, 9));
+        assert!(matches!(
+            clamp.kind,
+            SymbolKind::Function {
+                required: 2,
+                vararg: false,
+                ..
+            }
+        ));
+        assert!(!file_symbols.symbols.contains_key("Foo"));
+    }
+
     #[test]
     fn test_simple_table() {
         let code = r#"
@@ -361,6 +463,25 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_string_keyed_slots() {
+        let code = r#"
+            utils <- {
+                "format" = function (_text) { return _text; },
+                [key] = 1
+            };
+        "#;
+        let file_symbols = extract_file_symbols("scripts/utils.nut", code).unwrap();
+        let SymbolKind::Table { slots, .. } = &file_symbols.symbols["utils"].kind else {
+            panic!("expected table");
+        };
+        assert!(matches!(
+            slots.get("format").map(|s| &s.kind),
+            Some(SymbolKind::Function { .. })
+        ));
+        assert_eq!(slots.len(), 1);
+    }
+
     #[test]
     fn test_inherit_call() {
         let code = r#"
@@ -3,16 +3,26 @@
 //! This module resolves identifiers against the symbol maps,
 //! checking scope, class members, and inherited members.
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::ops::RangeInclusive;
+use std::path::Path;
 use std::sync::LazyLock;
 
-use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, DiagnosticTag, Position, Range};
-use tree_sitter::Node;
+use tower_lsp::lsp_types::{
+    Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity, DiagnosticTag, Location,
+    Position, Range, Url,
+};
+use tree_sitter::{Node, Tree};
 
+use crate::bb_support::{find_hook_calls, find_inherit_calls};
+use crate::config::{Dialect, ServerConfig};
 use crate::errors::AnalysisError;
 use crate::helpers;
-use crate::symbol_extractor::extract_file_symbols;
-use crate::symbols::FileSymbols;
+use crate::symbol_extractor::extract_file_symbols_with_tree;
+use crate::symbols::{FileSymbols, SymbolKind};
+use crate::workspace::{
+    GlobalDefinition, Workspace, find_class_declarations, levenshtein_distance,
+};
 
 static BUILTINS: LazyLock<HashSet<&'static str>> = LazyLock::new(|| {
     HashSet::from([
@@ -43,12 +53,50 @@ static BUILTINS: LazyLock<HashSet<&'static str>> = LazyLock::new(|| {
         "typeof",
         // Special keywords
         "this",
+        "base",
         "Math",
         // Battle Brothers specific
         "inherit",
     ])
 });
 
+/// Members the VM calls implicitly when defined on a class, table delegate
+/// or instance
+static METAMETHODS: LazyLock<HashSet<&'static str>> = LazyLock::new(|| {
+    HashSet::from([
+        "_add",
+        "_call",
+        "_cloned",
+        "_cmp",
+        "_delslot",
+        "_div",
+        "_get",
+        "_inherited",
+        "_modulo",
+        "_mul",
+        "_newmember",
+        "_newslot",
+        "_nexti",
+        "_set",
+        "_sub",
+        "_tostring",
+        "_typeof",
+        "_unm",
+    ])
+});
+
+pub fn is_metamethod(name: &str) -> bool {
+    METAMETHODS.contains(name)
+}
+
+/// Members every function gets from the closure default delegate
+static CLOSURE_DELEGATES: LazyLock<HashSet<&'static str>> = LazyLock::new(|| {
+    HashSet::from([
+        "acall", "bindenv", "call", "getinfos", "getroot", "pacall", "pcall", "setroot",
+        "tostring", "weakref",
+    ])
+});
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum DeclarationKind {
     Parameter,
@@ -57,6 +105,15 @@ enum DeclarationKind {
     CatchVariable,
 }
 
+/// Enclosing construct a `break` or `continue` could apply to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum JumpFrame {
+    Loop,
+    Switch,
+    /// Jumps never cross a function boundary
+    Function,
+}
+
 #[derive(Debug, Clone)]
 struct Declaration {
     name: String,
@@ -67,6 +124,13 @@ struct Declaration {
 #[derive(Debug, Clone)]
 struct ResolverContext {
     locals: HashSet<String>,
+    /// Locals declared later in an enclosing block, keyed to the byte offset
+    /// of their declaration. Squirrel locals are not hoisted.
+    upcoming: HashMap<String, usize>,
+    /// Names declared in enclosing scopes, for shadowing hints
+    outer_declarations: HashSet<String>,
+    /// Where each visible local was declared, for related information
+    declared_at: HashMap<String, Range>,
     declarations: Vec<Declaration>,
     references: HashSet<String>,
     has_parent: bool,
@@ -76,6 +140,9 @@ impl ResolverContext {
     fn new() -> Self {
         Self {
             locals: HashSet::new(),
+            upcoming: HashMap::new(),
+            outer_declarations: HashSet::new(),
+            declared_at: HashMap::new(),
             declarations: Vec::new(),
             references: HashSet::new(),
             has_parent: false,
@@ -84,6 +151,8 @@ impl ResolverContext {
 
     fn add_declaration(&mut self, name: String, range: Range, kind: DeclarationKind) {
         self.locals.insert(name.clone());
+        self.upcoming.remove(&name);
+        self.declared_at.insert(name.clone(), range);
         self.declarations.push(Declaration { name, range, kind });
     }
 
@@ -102,6 +171,14 @@ impl ResolverContext {
     fn child(&self) -> Self {
         Self {
             locals: self.locals.clone(),
+            upcoming: self.upcoming.clone(),
+            outer_declarations: self
+                .outer_declarations
+                .iter()
+                .cloned()
+                .chain(self.declarations.iter().map(|d| d.name.clone()))
+                .collect(),
+            declared_at: self.declared_at.clone(),
             declarations: Vec::new(),
             references: HashSet::new(),
             has_parent: self.has_parent,
@@ -111,21 +188,37 @@ impl ResolverContext {
 
 pub struct SymbolResolver<'a> {
     text: &'a str,
+    tree: Tree,
+    uri: Option<Url>,
     file_symbols: FileSymbols,
     known_globals: Option<&'a HashSet<String>>,
+    /// Globals defined by files this one requires
+    required_globals: HashSet<String>,
+    workspace: Option<&'a Workspace>,
+    dialect: Dialect,
+    shadowing_hints: bool,
+    recursion_hints: bool,
+    /// Warn when a call to a same-file or workspace function passes too few
+    /// or too many arguments
+    argument_count_checks: bool,
+    /// Report unused variables even when their name starts with `_`
+    report_underscore_unused: bool,
+    /// Names never reported as unreferenced; `None` disables the check
+    unused_symbol_allowlist: Option<Vec<String>>,
+    /// Namespaces whose globals may be defined by several files
+    shared_globals: Vec<String>,
+    /// Byte offset of an identifier whose local declaration is wanted
+    probe: Option<usize>,
+    probed_declaration: Option<Range>,
+    /// Hook function parameters, whose unused hint comes from `analyze_hooks`
+    hook_parameters: Vec<Range>,
+    unused_parameter_ranges: Vec<Range>,
     diagnostics: Vec<Diagnostic>,
 }
 
 impl<'a> SymbolResolver<'a> {
-    #[allow(dead_code)]
     pub fn new(file_path: &str, text: &'a str) -> Result<Self, AnalysisError> {
-        let file_symbols = extract_file_symbols(file_path, text)?;
-        Ok(Self {
-            text,
-            file_symbols,
-            known_globals: None,
-            diagnostics: Vec::new(),
-        })
+        Self::parse_and_build(file_path, text, None, None)
     }
 
     pub fn with_globals(
@@ -133,42 +226,651 @@ impl<'a> SymbolResolver<'a> {
         text: &'a str,
         globals: &'a HashSet<String>,
     ) -> Result<Self, AnalysisError> {
-        let file_symbols = extract_file_symbols(file_path, text)?;
-        Ok(Self {
+        Self::parse_and_build(file_path, text, Some(globals), None)
+    }
+
+    /// Like `with_globals`, but also validates class member calls against
+    /// the workspace class hierarchy.
+    pub fn with_workspace(
+        file_path: &str,
+        text: &'a str,
+        workspace: &'a Workspace,
+    ) -> Result<Self, AnalysisError> {
+        Self::parse_and_build(file_path, text, Some(workspace.globals()), Some(workspace))
+    }
+
+    /// Like `new`, but reuses a tree the caller already parsed.
+    pub fn with_tree(file_path: &str, tree: &Tree, text: &'a str) -> Self {
+        Self::build(file_path, tree.clone(), text, None, None)
+    }
+
+    /// Like `with_workspace`, but reuses a tree the caller already parsed.
+    pub fn with_workspace_and_tree(
+        file_path: &str,
+        tree: &Tree,
+        text: &'a str,
+        workspace: &'a Workspace,
+    ) -> Self {
+        Self::build(
+            file_path,
+            tree.clone(),
+            text,
+            Some(workspace.globals()),
+            Some(workspace),
+        )
+    }
+
+    fn parse_and_build(
+        file_path: &str,
+        text: &'a str,
+        known_globals: Option<&'a HashSet<String>>,
+        workspace: Option<&'a Workspace>,
+    ) -> Result<Self, AnalysisError> {
+        let tree = helpers::parse_squirrel(text)?;
+        Ok(Self::build(file_path, tree, text, known_globals, workspace))
+    }
+
+    fn build(
+        file_path: &str,
+        tree: Tree,
+        text: &'a str,
+        known_globals: Option<&'a HashSet<String>>,
+        workspace: Option<&'a Workspace>,
+    ) -> Self {
+        let mut file_symbols = extract_file_symbols_with_tree(file_path, &tree, text);
+        // Agree with the workspace on where script paths are rooted
+        if let Some(workspace) = workspace {
+            let script_path = workspace.script_path_for(Path::new(file_path));
+            if !script_path.is_empty() {
+                file_symbols.path = script_path;
+            }
+        }
+        let hook_parameters = find_hook_calls(tree.root_node(), text)
+            .iter()
+            .filter_map(|hook| hook.hook_param_node)
+            .map(|param| {
+                Range::new(
+                    helpers::position_at(text, param.start_byte()),
+                    helpers::position_at(text, param.end_byte()),
+                )
+            })
+            .collect();
+        Self {
             text,
+            tree,
+            uri: Url::from_file_path(file_path).ok(),
             file_symbols,
-            known_globals: Some(globals),
+            known_globals,
+            required_globals: workspace
+                .map(|w| w.dependency_globals(Path::new(file_path)))
+                .unwrap_or_default(),
+            workspace,
+            dialect: Dialect::default(),
+            shadowing_hints: false,
+            recursion_hints: false,
+            argument_count_checks: false,
+            report_underscore_unused: false,
+            unused_symbol_allowlist: None,
+            shared_globals: Vec::new(),
+            probe: None,
+            probed_declaration: None,
+            hook_parameters,
+            unused_parameter_ranges: Vec::new(),
             diagnostics: Vec::new(),
-        })
+        }
+    }
+
+    pub fn with_dialect(mut self, dialect: Dialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    /// Report locals that reuse a name from an enclosing scope.
+    pub fn with_shadowing_hints(mut self, enabled: bool) -> Self {
+        self.shadowing_hints = enabled;
+        self
+    }
+
+    /// Report methods whose whole body is a call to themselves.
+    pub fn with_recursion_hints(mut self, enabled: bool) -> Self {
+        self.recursion_hints = enabled;
+        self
+    }
+
+    /// Report calls to same-file functions whose argument count falls outside
+    /// what the parameters and their defaults allow.
+    pub fn with_argument_count_checks(mut self, enabled: bool) -> Self {
+        self.argument_count_checks = enabled;
+        self
+    }
+
+    /// Report unused variables and parameters whose name starts with `_`,
+    /// which are otherwise taken to be unused on purpose.
+    pub fn with_underscore_unused(mut self, enabled: bool) -> Self {
+        self.report_underscore_unused = enabled;
+        self
+    }
+
+    /// Hint at functions and methods no indexed file refers to, except those
+    /// matching `allowlist` (a trailing `*` matches any suffix).
+    pub fn with_unused_symbol_hints(mut self, allowlist: Option<Vec<String>>) -> Self {
+        self.unused_symbol_allowlist = allowlist;
+        self
+    }
+
+    /// Don't warn when several files define `namespace` or a global under it.
+    pub fn with_shared_globals(mut self, namespaces: Vec<String>) -> Self {
+        self.shared_globals = namespaces;
+        self
     }
 
     pub fn analyze(mut self) -> Result<Vec<Diagnostic>, AnalysisError> {
-        let tree = helpers::parse_squirrel(self.text)?;
+        let tree = self.tree.clone();
         let root = tree.root_node();
 
+        let ctx = self.resolve_scopes(root);
+        self.report_unused_variables(&ctx);
+
+        self.validate_table_assignments(root);
+        self.validate_jump_statements(root, &mut Vec::new());
+        if self.recursion_hints {
+            self.report_self_recursive_methods(root);
+        }
+        if let Some(workspace) = self.workspace {
+            self.validate_this_members(root, workspace);
+            self.report_unreferenced_definitions(workspace);
+            self.report_global_collisions(workspace);
+        }
+
+        Ok(self.diagnostics)
+    }
+
+    /// Where the local, parameter or loop variable used at `offset` is
+    /// declared, using the same scope walk as the diagnostics.
+    pub fn find_local_declaration(mut self, offset: usize) -> Option<Range> {
+        self.probe = Some(offset);
+        let tree = self.tree.clone();
+        self.resolve_scopes(tree.root_node());
+        self.probed_declaration
+    }
+
+    /// Parameters of `function` that its body never reads, using the same
+    /// scoping rules as the unused-variable diagnostics. One resolver can
+    /// answer this for every function of its file.
+    pub fn unused_parameters(&mut self, function: Node) -> Vec<Range> {
+        self.hook_parameters.clear();
+        self.unused_parameter_ranges.clear();
+        self.diagnostics.clear();
+        let mut ctx = self.file_scope();
+        self.analyze_function(function, &mut ctx);
+        std::mem::take(&mut self.unused_parameter_ranges)
+    }
+
+    fn file_scope(&self) -> ResolverContext {
         let mut ctx = ResolverContext::new();
         for name in self.file_symbols.symbols.keys() {
             ctx.locals.insert(name.clone());
         }
+        ctx
+    }
 
+    fn resolve_scopes(&mut self, root: Node) -> ResolverContext {
+        let mut ctx = self.file_scope();
         self.analyze_script(root, &mut ctx);
-        self.report_unused_variables(&ctx);
+        ctx
+    }
 
-        Ok(self.diagnostics)
+    fn report_unreferenced_definitions(&mut self, workspace: &Workspace) {
+        let Some(allowlist) = &self.unused_symbol_allowlist else {
+            return;
+        };
+        let Some(file_path) = self.uri.as_ref().and_then(|uri| uri.to_file_path().ok()) else {
+            return;
+        };
+
+        for def in workspace.unreferenced_definitions(&file_path) {
+            // Metamethods and allowlisted engine callbacks are called implicitly
+            if is_metamethod(&def.name) || allowlist.iter().any(|p| name_matches(p, &def.name)) {
+                continue;
+            }
+            let start = Position::new(def.line, def.column);
+            let end = Position::new(def.line, def.column + def.name.chars().count() as u32);
+            self.diagnostics.push(Diagnostic {
+                range: Range::new(start, end),
+                severity: Some(DiagnosticSeverity::HINT),
+                source: Some("squirrel-semantic".to_string()),
+                message: format!("'{}' is never referenced in the workspace", def.name),
+                tags: Some(vec![DiagnosticTag::UNNECESSARY]),
+                ..helpers::coded_diagnostic("unreferenced-symbol")
+            });
+        }
+    }
+
+    /// Hint at class and table methods such as `function create() { create(); }`,
+    /// whose only statement calls the method itself. In a hook or subclass
+    /// that is usually meant to be `base.create()`, and otherwise recurses
+    /// forever.
+    fn report_self_recursive_methods(&mut self, node: Node) {
+        let is_method = node.kind() == "function_declaration"
+            && node.parent().is_some_and(|parent| {
+                matches!(
+                    parent.kind(),
+                    "table" | "table_slot" | "table_slots" | "class_member" | "class_body"
+                )
+            });
+        if is_method
+            && let Some(name) = self.find_first_identifier(node)
+            && let Some(call) = self.sole_self_call(node, self.node_text(name))
+        {
+            let name = self.node_text(name).to_string();
+            self.diagnostics.push(Diagnostic {
+                range: Range::new(
+                    self.position_at(call.start_byte()),
+                    self.position_at(call.end_byte()),
+                ),
+                severity: Some(DiagnosticSeverity::HINT),
+                source: Some("squirrel-semantic".to_string()),
+                message: format!(
+                    "'{name}' only calls itself and never returns; did you mean 'base.{name}()'?"
+                ),
+                ..helpers::coded_diagnostic("self-recursive-method")
+            });
+        }
+
+        for child in node.children(&mut node.walk()) {
+            self.report_self_recursive_methods(child);
+        }
+    }
+
+    /// The call in a function body made of a single `name()`, `this.name()`
+    /// or `return name()` statement that is the body's only call.
+    fn sole_self_call<'b>(&self, function: Node<'b>, name: &str) -> Option<Node<'b>> {
+        let body = function
+            .children(&mut function.walk())
+            .find(|child| child.kind() == "block")?;
+        let mut cursor = body.walk();
+        let mut statements = body
+            .named_children(&mut cursor)
+            .filter(|child| !child.is_extra() && child.kind() != "comment");
+        let statement = statements.next()?;
+        if statements.next().is_some() {
+            return None;
+        }
+
+        let call = match statement.kind() {
+            "call_expression" => statement,
+            "expression_statement" | "return_statement" => statement.named_child(0)?,
+            _ => return None,
+        };
+        if call.kind() != "call_expression" || count_calls(body) != 1 {
+            return None;
+        }
+
+        let callee = call.child_by_field_name("function")?;
+        let calls_itself = match self.simple_member_access(callee) {
+            Some((base, member)) => {
+                self.node_text(base) == "this" && self.node_text(member) == name
+            },
+            None => callee.kind() == "identifier" && self.node_text(callee) == name,
+        };
+        calls_itself.then_some(call)
+    }
+
+    fn report_global_collisions(&mut self, workspace: &Workspace) {
+        let Some(file_path) = self.uri.as_ref().and_then(|uri| uri.to_file_path().ok()) else {
+            return;
+        };
+
+        for collision in workspace.global_collisions(&file_path) {
+            let name = &collision.global.name;
+            let shared = self.shared_globals.iter().any(|namespace| {
+                name == namespace
+                    || name
+                        .strip_prefix(namespace.as_str())
+                        .is_some_and(|rest| rest.starts_with('.'))
+            });
+            if shared {
+                continue;
+            }
+
+            let related_information = collision
+                .others
+                .iter()
+                .filter_map(|(path, other)| {
+                    Some(DiagnosticRelatedInformation {
+                        location: Location::new(
+                            Url::from_file_path(path).ok()?,
+                            global_range(other),
+                        ),
+                        message: format!("'{name}' is also defined here"),
+                    })
+                })
+                .collect();
+            let mut files: Vec<&Path> = collision.others.iter().map(|(path, _)| *path).collect();
+            files.dedup();
+            let files = match files.len() {
+                1 => "another file".to_string(),
+                n => format!("{n} other files"),
+            };
+            self.diagnostics.push(Diagnostic {
+                range: global_range(collision.global),
+                severity: Some(DiagnosticSeverity::WARNING),
+                source: Some("squirrel-semantic".to_string()),
+                message: format!(
+                    "'{name}' is also defined in {files}; whichever loads last overwrites the others"
+                ),
+                related_information: Some(related_information),
+                ..helpers::coded_diagnostic("duplicate-global")
+            });
+        }
+    }
+
+    /// Validate `this.method()` calls and `this.field = value` assignments in
+    /// the file's class body against the workspace, and `base.method()`
+    /// calls against the parent class. Only runs when every
+    /// ancestor is indexed, since otherwise the member may simply live in a
+    /// file we haven't seen.
+    fn validate_this_members(&mut self, root: Node, workspace: &Workspace) {
+        let script_path = self.file_symbols.path.clone();
+        if !workspace.has_resolved_ancestry(&script_path) {
+            return;
+        }
+        let Some(entry) = workspace.get(&script_path) else {
+            return;
+        };
+        let class_body = match find_inherit_calls(root, self.text).into_iter().next() {
+            Some(inherit) => inherit.class_body,
+            None => match find_class_declarations(root, self.text)
+                .into_iter()
+                .find(|class| class.name == entry.name)
+            {
+                Some(class) => class.body,
+                None => return,
+            },
+        };
+
+        let members: HashSet<String> = workspace
+            .get_all_members(&script_path)
+            .into_iter()
+            .map(|m| m.name)
+            .collect();
+
+        let mut calls = Vec::new();
+        let mut base_calls = Vec::new();
+        let mut assignments = Vec::new();
+        let mut m_reads = Vec::new();
+        self.collect_this_members(
+            class_body,
+            class_body,
+            &mut calls,
+            &mut base_calls,
+            &mut assignments,
+            &mut m_reads,
+        );
+
+        for member_node in calls {
+            let name = self.node_text(member_node);
+            if members.contains(name) {
+                continue;
+            }
+
+            let mut message = format!(
+                "Method '{}' not found in '{}' or its ancestors",
+                name, entry.name
+            );
+            let suggestions = workspace.find_similar_methods(&script_path, name);
+            if !suggestions.is_empty() {
+                message.push_str(". Did you mean: ");
+                message.push_str(&suggestions.join(", "));
+                message.push('?');
+            }
+
+            self.diagnostics.push(Diagnostic {
+                range: Range::new(
+                    self.position_at(member_node.start_byte()),
+                    self.position_at(member_node.end_byte()),
+                ),
+                severity: Some(DiagnosticSeverity::ERROR),
+                source: Some("squirrel-semantic".to_string()),
+                message,
+                ..helpers::coded_diagnostic("method-not-found")
+            });
+        }
+
+        if let Some(parent) = workspace.get_ancestors(&script_path).first() {
+            let parent_members: HashSet<String> = workspace
+                .get_all_members(&parent.script_path)
+                .into_iter()
+                .map(|m| m.name)
+                .collect();
+            for member_node in base_calls {
+                let name = self.node_text(member_node);
+                if parent_members.contains(name) {
+                    continue;
+                }
+
+                let mut message = format!(
+                    "Method '{}' not found in parent '{}' or its ancestors",
+                    name, parent.name
+                );
+                let suggestions = workspace.find_similar_methods(&parent.script_path, name);
+                if !suggestions.is_empty() {
+                    message.push_str(". Did you mean: ");
+                    message.push_str(&suggestions.join(", "));
+                    message.push('?');
+                }
+
+                self.diagnostics.push(Diagnostic {
+                    range: Range::new(
+                        self.position_at(member_node.start_byte()),
+                        self.position_at(member_node.end_byte()),
+                    ),
+                    severity: Some(DiagnosticSeverity::ERROR),
+                    source: Some("squirrel-semantic".to_string()),
+                    message,
+                    ..helpers::coded_diagnostic("method-not-found")
+                });
+            }
+        }
+
+        for member_node in assignments {
+            if !members.contains(self.node_text(member_node)) {
+                self.report_missing_slot_assignment(member_node);
+            }
+        }
+
+        // Classes without an `m` table anywhere in their ancestry aren't checked
+        let m_fields: HashSet<String> = workspace
+            .get_all_m_fields(&script_path)
+            .into_iter()
+            .map(|m| m.name)
+            .collect();
+        if m_fields.is_empty() {
+            return;
+        }
+        for field_node in m_reads {
+            let name = self.node_text(field_node);
+            if m_fields.contains(name) {
+                continue;
+            }
+            self.diagnostics.push(Diagnostic {
+                range: Range::new(
+                    self.position_at(field_node.start_byte()),
+                    self.position_at(field_node.end_byte()),
+                ),
+                // `m` can gain slots at runtime, so this is only a warning
+                severity: Some(DiagnosticSeverity::WARNING),
+                source: Some("squirrel-semantic".to_string()),
+                message: format!(
+                    "Field '{}' not found in 'm' of '{}' or its ancestors",
+                    name, entry.name
+                ),
+                ..helpers::coded_diagnostic("unknown-m-field")
+            });
+        }
+    }
+
+    /// Collect the member name nodes of `this.name(...)` and `base.name(...)`
+    /// calls, `this.name = value` assignments and `this.m.name` reads whose
+    /// `this` is the given class body. Nested tables and classes rebind
+    /// `this`, so they are not descended into.
+    fn collect_this_members<'b>(
+        &self,
+        node: Node<'b>,
+        class_body: Node<'b>,
+        calls: &mut Vec<Node<'b>>,
+        base_calls: &mut Vec<Node<'b>>,
+        assignments: &mut Vec<Node<'b>>,
+        m_reads: &mut Vec<Node<'b>>,
+    ) {
+        if node.id() != class_body.id() && matches!(node.kind(), "table" | "class_declaration") {
+            return;
+        }
+
+        if node.kind() == "call_expression"
+            && let Some(function) = node.child_by_field_name("function")
+            && let Some((base, member)) = self.simple_member_access(function)
+        {
+            match self.node_text(base) {
+                "this" => calls.push(member),
+                "base" => base_calls.push(member),
+                _ => {},
+            }
+        }
+
+        if self.is_plain_assignment(node)
+            && let Some(left) = node.child_by_field_name("left")
+            && let Some((base, member)) = self.simple_member_access(left)
+            && self.node_text(base) == "this"
+        {
+            assignments.push(member);
+        }
+
+        if let Some((base, field)) = helpers::m_field_access(node, self.text)
+            && self.node_text(base) == "this"
+        {
+            m_reads.push(field);
+        }
+
+        for child in node.children(&mut node.walk()) {
+            self.collect_this_members(child, class_body, calls, base_calls, assignments, m_reads);
+        }
+    }
+
+    /// Flag `table.slot = value` where `table` is a plain table defined in
+    /// this file and `slot` is neither one of its slots nor created elsewhere
+    /// with `table.slot <- value`.
+    fn validate_table_assignments(&mut self, root: Node) {
+        let mut assignments = Vec::new();
+        let mut new_slots = HashSet::new();
+        self.collect_table_assignments(root, &mut assignments, &mut new_slots);
+
+        for (base, member) in assignments {
+            let base_name = self.node_text(base);
+            let member_name = self.node_text(member);
+            if new_slots.contains(&(base_name.to_string(), member_name.to_string())) {
+                continue;
+            }
+            if let Some(symbol) = self.file_symbols.symbols.get(base_name)
+                && let SymbolKind::Table {
+                    parent: None,
+                    slots,
+                } = &symbol.kind
+                && !slots.contains_key(member_name)
+            {
+                self.report_missing_slot_assignment(member);
+            }
+        }
+    }
+
+    fn collect_table_assignments<'b>(
+        &self,
+        node: Node<'b>,
+        assignments: &mut Vec<(Node<'b>, Node<'b>)>,
+        new_slots: &mut HashSet<(String, String)>,
+    ) {
+        if self.is_plain_assignment(node)
+            && let Some(left) = node.child_by_field_name("left")
+            && let Some((base, member)) = self.simple_member_access(left)
+            && base.kind() == "identifier"
+        {
+            assignments.push((base, member));
+        }
+
+        if node.kind() == "update_expression"
+            && node.children(&mut node.walk()).any(|c| c.kind() == "<-")
+            && let Some(left) = node.named_child(0)
+            && let Some((base, member)) = self.simple_member_access(left)
+        {
+            new_slots.insert((
+                self.node_text(base).to_string(),
+                self.node_text(member).to_string(),
+            ));
+        }
+
+        for child in node.children(&mut node.walk()) {
+            self.collect_table_assignments(child, assignments, new_slots);
+        }
+    }
+
+    fn report_missing_slot_assignment(&mut self, member_node: Node) {
+        self.diagnostics.push(Diagnostic {
+            range: Range::new(
+                self.position_at(member_node.start_byte()),
+                self.position_at(member_node.end_byte()),
+            ),
+            severity: Some(DiagnosticSeverity::WARNING),
+            source: Some("squirrel-semantic".to_string()),
+            message: format!(
+                "Assignment to unknown member '{}'. Use '<-' to create a new slot",
+                self.node_text(member_node)
+            ),
+            ..helpers::coded_diagnostic("assignment-to-missing-slot")
+        });
+    }
+
+    /// Split a `base.member` deref expression into its two identifiers.
+    fn simple_member_access<'b>(&self, node: Node<'b>) -> Option<(Node<'b>, Node<'b>)> {
+        if node.kind() != "deref_expression" || node.named_child_count() != 2 {
+            return None;
+        }
+        let base = node.named_child(0)?;
+        let member = node.named_child(1)?;
+        if member.kind() != "identifier" {
+            return None;
+        }
+        Some((base, member))
+    }
+
+    fn is_plain_assignment(&self, node: Node) -> bool {
+        node.kind() == "assignment_expression"
+            && node
+                .children(&mut node.walk())
+                .any(|child| child.kind() == "=")
     }
 
     fn analyze_script(&mut self, node: Node, ctx: &mut ResolverContext) {
+        self.collect_upcoming_declarations(node, ctx);
+
         let mut cursor = node.walk();
         for child in node.children(&mut cursor) {
             match child.kind() {
-                "local_declaration" | "var_statement" | "const_declaration" => {
+                "local_declaration" | "var_statement" => {
                     for ident in self.find_all_declaration_names(child) {
                         let name = self.node_text(ident).to_string();
                         let range = Range::new(
                             self.position_at(ident.start_byte()),
                             self.position_at(ident.end_byte()),
                         );
-                        ctx.add_declaration(name, range, DeclarationKind::Local);
+                        self.declare(ctx, name, range, DeclarationKind::Local);
+                    }
+                    self.analyze_declaration(child, ctx);
+                },
+                "const_declaration" => {
+                    // Top-level constants are visible to other scripts, so
+                    // they are never reported as unused
+                    for ident in self.find_all_declaration_names(child) {
+                        ctx.locals.insert(self.node_text(ident).to_string());
                     }
                     self.analyze_declaration(child, ctx);
                 },
@@ -233,6 +935,14 @@ impl<'a> SymbolResolver<'a> {
                     return;
                 }
             },
+            "enum_declaration" => {
+                // Members are only reachable as `Name.Member`, which the
+                // member-access skip already handles
+                if let Some(ident) = self.find_first_identifier(node) {
+                    ctx.locals.insert(self.node_text(ident).to_string());
+                }
+                return;
+            },
             "local_declaration" | "var_statement" => {
                 for ident in self.find_all_declaration_names(node) {
                     let name = self.node_text(ident).to_string();
@@ -240,7 +950,7 @@ impl<'a> SymbolResolver<'a> {
                         self.position_at(ident.start_byte()),
                         self.position_at(ident.end_byte()),
                     );
-                    ctx.add_declaration(name, range, DeclarationKind::Local);
+                    self.declare(ctx, name, range, DeclarationKind::Local);
                 }
                 self.analyze_declaration(node, ctx);
                 return;
@@ -257,22 +967,53 @@ impl<'a> SymbolResolver<'a> {
         }
     }
 
+    fn declare(
+        &mut self,
+        ctx: &mut ResolverContext,
+        name: String,
+        range: Range,
+        kind: DeclarationKind,
+    ) {
+        if self.shadowing_hints && ctx.outer_declarations.contains(&name) {
+            self.diagnostics.push(Diagnostic {
+                range,
+                severity: Some(DiagnosticSeverity::HINT),
+                source: Some("squirrel-semantic".to_string()),
+                message: format!("Variable '{name}' shadows an outer declaration"),
+                ..helpers::coded_diagnostic("shadowed-variable")
+            });
+        }
+        ctx.add_declaration(name, range, kind);
+    }
+
     fn report_unused_variables(&mut self, ctx: &ResolverContext) {
         for decl in &ctx.declarations {
-            if decl.name.starts_with('_') {
+            if (decl.name.starts_with('_') && !self.report_underscore_unused)
+                || self.hook_parameters.contains(&decl.range)
+            {
                 continue;
             }
             if !ctx.references.contains(&decl.name) {
+                if decl.kind == DeclarationKind::Parameter {
+                    self.unused_parameter_ranges.push(decl.range);
+                }
                 let severity = match decl.kind {
                     DeclarationKind::Parameter => DiagnosticSeverity::HINT,
                     _ => DiagnosticSeverity::WARNING,
                 };
+                let related_information = self.uri.as_ref().map(|uri| {
+                    vec![DiagnosticRelatedInformation {
+                        location: Location::new(uri.clone(), decl.range),
+                        message: format!("'{}' declared here", decl.name),
+                    }]
+                });
                 self.diagnostics.push(Diagnostic {
                     range: decl.range,
                     severity: Some(severity),
                     source: Some("squirrel-semantic".to_string()),
                     message: format!("Unused variable '{}'", decl.name),
                     tags: Some(vec![DiagnosticTag::UNNECESSARY]),
+                    related_information,
                     ..Diagnostic::default()
                 });
             }
@@ -285,9 +1026,14 @@ impl<'a> SymbolResolver<'a> {
         for child in node.children(&mut node.walk()) {
             if child.kind() == "parameters" {
                 for param in child.children(&mut child.walk()) {
-                    if param.kind() == "parameter"
-                        && let Some(ident) = self.find_first_identifier(param)
-                    {
+                    if param.kind() != "parameter" {
+                        continue;
+                    }
+                    // Defaults are evaluated in the enclosing scope
+                    if let Some(default) = helpers::parameter_default(param) {
+                        self.analyze_node(default, parent_ctx);
+                    }
+                    if let Some(ident) = helpers::parameter_name(param) {
                         let name = self.node_text(ident).to_string();
                         let range = Range::new(
                             self.position_at(ident.start_byte()),
@@ -296,6 +1042,9 @@ impl<'a> SymbolResolver<'a> {
                         ctx.add_declaration(name, range, DeclarationKind::Parameter);
                     }
                 }
+                if helpers::has_varargs(child) {
+                    ctx.locals.insert("vargv".to_string());
+                }
             }
         }
 
@@ -350,8 +1099,28 @@ impl<'a> SymbolResolver<'a> {
     }
 
     fn analyze_block_statements(&mut self, node: Node, ctx: &mut ResolverContext) {
+        self.collect_upcoming_declarations(node, ctx);
+
+        let mut terminated = false;
+        let mut reported_unreachable = false;
         let mut cursor = node.walk();
         for child in node.children(&mut cursor) {
+            if child.is_named() && !child.is_extra() && child.kind() != "comment" {
+                if terminated && !reported_unreachable {
+                    self.report_unreachable(child);
+                    reported_unreachable = true;
+                }
+                if matches!(
+                    child.kind(),
+                    "return_statement"
+                        | "break_statement"
+                        | "continue_statement"
+                        | "throw_statement"
+                ) {
+                    terminated = true;
+                }
+            }
+
             match child.kind() {
                 "local_declaration" | "var_statement" | "const_declaration" => {
                     for ident in self.find_all_declaration_names(child) {
@@ -360,7 +1129,7 @@ impl<'a> SymbolResolver<'a> {
                             self.position_at(ident.start_byte()),
                             self.position_at(ident.end_byte()),
                         );
-                        ctx.add_declaration(name, range, DeclarationKind::Local);
+                        self.declare(ctx, name, range, DeclarationKind::Local);
                     }
                     self.analyze_declaration(child, ctx);
                 },
@@ -377,6 +1146,40 @@ impl<'a> SymbolResolver<'a> {
         }
     }
 
+    fn report_unreachable(&mut self, statement: Node) {
+        self.diagnostics.push(Diagnostic {
+            range: Range::new(
+                self.position_at(statement.start_byte()),
+                self.position_at(statement.end_byte()),
+            ),
+            severity: Some(DiagnosticSeverity::WARNING),
+            source: Some("squirrel-semantic".to_string()),
+            message: "Unreachable code".to_string(),
+            tags: Some(vec![DiagnosticTag::UNNECESSARY]),
+            ..helpers::coded_diagnostic("unreachable-code")
+        });
+    }
+
+    /// Record the locals a block declares so that references appearing
+    /// before their declaration can be told apart from undeclared names.
+    fn collect_upcoming_declarations(&self, node: Node, ctx: &mut ResolverContext) {
+        for child in node.children(&mut node.walk()) {
+            if matches!(
+                child.kind(),
+                "local_declaration" | "var_statement" | "const_declaration"
+            ) {
+                for ident in self.find_all_declaration_names(child) {
+                    let name = self.node_text(ident);
+                    if !ctx.locals.contains(name) {
+                        ctx.upcoming
+                            .entry(name.to_string())
+                            .or_insert(ident.start_byte());
+                    }
+                }
+            }
+        }
+    }
+
     fn analyze_class(&mut self, node: Node, parent_ctx: &ResolverContext) {
         let members = self.extract_class_member_names(node);
 
@@ -394,6 +1197,12 @@ impl<'a> SymbolResolver<'a> {
 
         for child in node.children(&mut node.walk()) {
             if child.kind() == "class_body" {
+                let names: Vec<Node> = child
+                    .children(&mut child.walk())
+                    .filter(|member| member.kind() == "class_member")
+                    .filter_map(|member| self.class_member_name_node(member))
+                    .collect();
+                self.report_duplicate_members(&names);
                 self.analyze_class_body(child, &ctx);
             }
         }
@@ -439,14 +1248,19 @@ impl<'a> SymbolResolver<'a> {
     }
 
     fn extract_class_member_name(&self, node: Node) -> Option<String> {
+        self.class_member_name_node(node)
+            .map(|ident| self.node_text(ident).to_string())
+    }
+
+    fn class_member_name_node<'b>(&self, node: Node<'b>) -> Option<Node<'b>> {
         for child in node.children(&mut node.walk()) {
             match child.kind() {
                 "identifier" => {
-                    return Some(self.node_text(child).to_string());
+                    return Some(child);
                 },
                 "function_declaration" => {
                     if let Some(ident) = self.find_first_identifier(child) {
-                        return Some(self.node_text(ident).to_string());
+                        return Some(ident);
                     }
                 },
                 _ => {},
@@ -458,11 +1272,24 @@ impl<'a> SymbolResolver<'a> {
     fn analyze_table(&mut self, node: Node, parent_ctx: &mut ResolverContext) {
         let slots = self.extract_table_slot_names(node);
 
-        let mut ctx = parent_ctx.child();
-        for slot in &slots {
-            ctx.locals.insert(slot.clone());
-        }
-
+        let mut names = Vec::new();
+        for child in node.children(&mut node.walk()) {
+            if child.kind() == "table_slots" {
+                names.extend(
+                    child
+                        .children(&mut child.walk())
+                        .filter(|slot| slot.kind() == "table_slot")
+                        .filter_map(|slot| self.slot_name_node(slot)),
+                );
+            }
+        }
+        self.report_duplicate_members(&names);
+
+        let mut ctx = parent_ctx.child();
+        for slot in &slots {
+            ctx.locals.insert(slot.clone());
+        }
+
         for child in node.children(&mut node.walk()) {
             if child.kind() == "table_slots" {
                 for slot in child.children(&mut child.walk()) {
@@ -476,17 +1303,55 @@ impl<'a> SymbolResolver<'a> {
         parent_ctx.merge_references(&ctx);
     }
 
+    /// Warn about members defined more than once in the same table or class
+    /// body. Squirrel silently keeps the last definition.
+    fn report_duplicate_members(&mut self, names: &[Node]) {
+        let mut seen: HashMap<String, Node> = HashMap::new();
+        for &ident in names {
+            // `"name" = value` and `name = value` define the same slot
+            let name = helpers::extract_string_content(ident, self.text);
+            let Some(first) = seen.get(&name).copied() else {
+                seen.insert(name, ident);
+                continue;
+            };
+
+            let first_range = Range::new(
+                self.position_at(first.start_byte()),
+                self.position_at(first.end_byte()),
+            );
+            let related_information = self.uri.as_ref().map(|uri| {
+                vec![DiagnosticRelatedInformation {
+                    location: Location::new(uri.clone(), first_range),
+                    message: format!("'{}' first defined here", name),
+                }]
+            });
+
+            self.diagnostics.push(Diagnostic {
+                range: Range::new(
+                    self.position_at(ident.start_byte()),
+                    self.position_at(ident.end_byte()),
+                ),
+                severity: Some(DiagnosticSeverity::WARNING),
+                source: Some("squirrel-semantic".to_string()),
+                message: format!(
+                    "Duplicate member '{}' (first defined on line {})",
+                    name,
+                    first_range.start.line + 1
+                ),
+                related_information,
+                ..helpers::coded_diagnostic("duplicate-member")
+            });
+        }
+    }
+
     fn analyze_table_slot(&mut self, node: Node, ctx: &mut ResolverContext) {
-        let mut saw_equals = false;
+        // A computed `[expr]` key has no static key, so `expr` is analyzed
+        // like any other reference
+        let key = helpers::table_slot_key(node);
         for child in node.children(&mut node.walk()) {
             match child.kind() {
-                "=" => {
-                    saw_equals = true;
-                },
-                "identifier" if !saw_equals => {
-                    // This is the slot name (key), skip it
-                },
-                "," => {},
+                _ if Some(child) == key => {},
+                "=" | ":" | "," => {},
                 "function_declaration" => {
                     self.analyze_function(child, ctx);
                 },
@@ -552,7 +1417,7 @@ impl<'a> SymbolResolver<'a> {
                 self.position_at(index.start_byte()),
                 self.position_at(index.end_byte()),
             );
-            ctx.add_declaration(name, range, DeclarationKind::LoopVariable);
+            self.declare(&mut ctx, name, range, DeclarationKind::LoopVariable);
         }
         if let Some(value) = node.child_by_field_name("value") {
             let name = self.node_text(value).to_string();
@@ -560,7 +1425,7 @@ impl<'a> SymbolResolver<'a> {
                 self.position_at(value.start_byte()),
                 self.position_at(value.end_byte()),
             );
-            ctx.add_declaration(name, range, DeclarationKind::LoopVariable);
+            self.declare(&mut ctx, name, range, DeclarationKind::LoopVariable);
         }
 
         let collection = node.child_by_field_name("collection");
@@ -600,7 +1465,7 @@ impl<'a> SymbolResolver<'a> {
                     self.position_at(ident.start_byte()),
                     self.position_at(ident.end_byte()),
                 );
-                ctx.add_declaration(name, range, DeclarationKind::LoopVariable);
+                self.declare(&mut ctx, name, range, DeclarationKind::LoopVariable);
             }
         }
 
@@ -633,12 +1498,13 @@ impl<'a> SymbolResolver<'a> {
                     self.position_at(child.start_byte()),
                     self.position_at(child.end_byte()),
                 );
-                ctx.add_declaration(name, range, DeclarationKind::CatchVariable);
+                self.declare(&mut ctx, name, range, DeclarationKind::CatchVariable);
             }
         }
 
         for child in node.children(&mut node.walk()) {
             if child.kind() == "block" {
+                self.report_empty_catch(node, child);
                 self.analyze_block(child, &mut ctx);
             }
         }
@@ -647,6 +1513,90 @@ impl<'a> SymbolResolver<'a> {
         parent_ctx.merge_references(&ctx);
     }
 
+    /// Report `break` outside any loop or switch and `continue` outside any
+    /// loop, which Squirrel refuses to compile.
+    fn validate_jump_statements(&mut self, node: Node, frames: &mut Vec<JumpFrame>) {
+        let frame = match node.kind() {
+            "for_statement" | "foreach_statement" | "while_statement" | "do_while_statement" => {
+                Some(JumpFrame::Loop)
+            },
+            "switch_statement" => Some(JumpFrame::Switch),
+            "function_declaration" | "lambda_expression" | "anonymous_function" => {
+                Some(JumpFrame::Function)
+            },
+            "break_statement" | "continue_statement" => {
+                self.check_jump_target(node, frames);
+                None
+            },
+            _ => None,
+        };
+
+        if let Some(frame) = frame {
+            frames.push(frame);
+        }
+        for child in node.children(&mut node.walk()) {
+            self.validate_jump_statements(child, frames);
+        }
+        if frame.is_some() {
+            frames.pop();
+        }
+    }
+
+    fn check_jump_target(&mut self, statement: Node, frames: &[JumpFrame]) {
+        let is_break = statement.kind() == "break_statement";
+        let has_target = frames
+            .iter()
+            .rev()
+            .take_while(|frame| **frame != JumpFrame::Function)
+            .any(|frame| *frame == JumpFrame::Loop || (is_break && *frame == JumpFrame::Switch));
+        if has_target {
+            return;
+        }
+
+        let (code, message) = if is_break {
+            ("break-outside-loop", "'break' outside of a loop or switch")
+        } else {
+            ("continue-outside-loop", "'continue' outside of a loop")
+        };
+        self.diagnostics.push(Diagnostic {
+            range: Range::new(
+                self.position_at(statement.start_byte()),
+                self.position_at(statement.end_byte()),
+            ),
+            severity: Some(DiagnosticSeverity::ERROR),
+            source: Some("squirrel-semantic".to_string()),
+            message: message.to_string(),
+            ..helpers::coded_diagnostic(code)
+        });
+    }
+
+    /// Hint at a catch body with nothing but comments in it, which silently
+    /// swallows the error.
+    fn report_empty_catch(&mut self, catch: Node, body: Node) {
+        let is_empty = body
+            .named_children(&mut body.walk())
+            .all(|child| child.kind() == "comment");
+        if !is_empty {
+            return;
+        }
+
+        let keyword = catch
+            .children(&mut catch.walk())
+            .find(|child| child.kind() == "catch")
+            .unwrap_or(catch);
+        self.diagnostics.push(Diagnostic {
+            range: Range::new(
+                self.position_at(keyword.start_byte()),
+                self.position_at(keyword.end_byte()),
+            ),
+            severity: Some(DiagnosticSeverity::HINT),
+            source: Some("squirrel-semantic".to_string()),
+            message: "Empty catch block swallows the error; consider at least logging it"
+                .to_string(),
+            ..helpers::coded_diagnostic("empty-catch")
+        });
+    }
+
     fn check_identifier(&mut self, node: Node, ctx: &mut ResolverContext) {
         if self.should_skip_identifier(node) {
             return;
@@ -657,6 +1607,13 @@ impl<'a> SymbolResolver<'a> {
         // Check locals first as they shadow builtins and globals
         if ctx.locals.contains(name) {
             ctx.record_reference(name);
+            if self
+                .probe
+                .is_some_and(|offset| (node.start_byte()..=node.end_byte()).contains(&offset))
+            {
+                self.probed_declaration = ctx.declared_at.get(name).copied();
+            }
+            self.check_symbol_usage(node, ctx);
             return;
         }
 
@@ -664,7 +1621,26 @@ impl<'a> SymbolResolver<'a> {
             return;
         }
 
-        if self.known_globals.is_some_and(|g| g.contains(name)) {
+        // A later local of the same name doesn't hide a global before it
+        if self.known_globals.is_some_and(|g| g.contains(name))
+            || self.required_globals.contains(name)
+        {
+            self.check_workspace_call(node);
+            return;
+        }
+
+        if let Some(&declared_at) = ctx.upcoming.get(name)
+            && declared_at > node.start_byte()
+        {
+            let start = self.position_at(node.start_byte());
+            let end = self.position_at(node.end_byte());
+            self.diagnostics.push(Diagnostic {
+                range: Range::new(start, end),
+                severity: Some(DiagnosticSeverity::ERROR),
+                source: Some("squirrel-semantic".to_string()),
+                message: format!("Variable '{}' used before declaration", name),
+                ..helpers::coded_diagnostic("used-before-declaration")
+            });
             return;
         }
 
@@ -673,6 +1649,19 @@ impl<'a> SymbolResolver<'a> {
             return;
         }
 
+        let suggestions = self.similar_names(name, ctx);
+        let related_information = suggestions.first().and_then(|similar| {
+            let range = ctx.declared_at.get(similar)?;
+            let uri = self.uri.as_ref()?;
+            Some(vec![DiagnosticRelatedInformation {
+                location: Location::new(uri.clone(), *range),
+                message: format!("Did you mean '{similar}'?"),
+            }])
+        });
+        // Read back by the "Change to" quick-fix
+        let data =
+            (!suggestions.is_empty()).then(|| serde_json::json!({ "suggestions": suggestions }));
+
         let start = self.position_at(node.start_byte());
         let end = self.position_at(node.end_byte());
         self.diagnostics.push(Diagnostic {
@@ -680,10 +1669,176 @@ impl<'a> SymbolResolver<'a> {
             severity: Some(DiagnosticSeverity::ERROR),
             source: Some("squirrel-semantic".to_string()),
             message: format!("Undeclared variable '{}'", name),
-            ..Diagnostic::default()
+            related_information,
+            data,
+            ..helpers::coded_diagnostic("undeclared-variable")
+        });
+    }
+
+    /// Warn when a same-file table is called like a function, or a same-file
+    /// function is accessed like a table. Only top-level `name <- value` and
+    /// `function name()` symbols that nothing in scope shadows are checked.
+    fn check_symbol_usage(&mut self, node: Node, ctx: &ResolverContext) {
+        let name = helpers::node_text(node, self.text);
+        if ctx.declared_at.contains_key(name) || self.is_shadowed_by_slot(node, name) {
+            return;
+        }
+        let Some(symbol) = self.file_symbols.symbols.get(name) else {
+            return;
+        };
+
+        if let SymbolKind::Function {
+            params,
+            required,
+            vararg: false,
+        } = &symbol.kind
+            && self.argument_count_checks
+            && self.is_function_call(node)
+        {
+            let expected = *required..=params.len();
+            self.check_argument_count(node, name, expected);
+            return;
+        }
+
+        let message = match &symbol.kind {
+            SymbolKind::Table { parent: None, .. } if self.is_function_call(node) => {
+                format!("'{name}' is a table and cannot be called")
+            },
+            SymbolKind::Function { .. } => {
+                let Some(member) = node
+                    .next_sibling()
+                    .filter(|dot| {
+                        dot.kind() == "."
+                            && node
+                                .parent()
+                                .is_some_and(|p| p.kind() == "deref_expression")
+                    })
+                    .and_then(|dot| dot.next_sibling())
+                else {
+                    return;
+                };
+                // Functions still have the closure default delegate
+                if CLOSURE_DELEGATES.contains(helpers::node_text(member, self.text)) {
+                    return;
+                }
+                format!("'{name}' is a function and has no members")
+            },
+            _ => return,
+        };
+
+        self.diagnostics.push(Diagnostic {
+            range: Range::new(
+                self.position_at(node.start_byte()),
+                self.position_at(node.end_byte()),
+            ),
+            severity: Some(DiagnosticSeverity::WARNING),
+            source: Some("squirrel-semantic".to_string()),
+            message,
+            ..helpers::coded_diagnostic("symbol-kind-mismatch")
+        });
+    }
+
+    /// Check the arguments of a call to a global function that another indexed
+    /// file defines at the top level.
+    fn check_workspace_call(&mut self, node: Node) {
+        let name = helpers::node_text(node, self.text);
+        if !self.argument_count_checks
+            || !self.is_function_call(node)
+            || self.is_shadowed_by_slot(node, name)
+        {
+            return;
+        }
+        let Some(signature) = self
+            .workspace
+            .and_then(|workspace| workspace.function_signature(name))
+            .filter(|signature| !signature.vararg)
+        else {
+            return;
+        };
+        let expected = signature.required..=signature.params;
+        self.check_argument_count(node, name, expected);
+    }
+
+    /// Warn when the call whose callee is `node` passes a number of arguments
+    /// outside `expected`.
+    fn check_argument_count(&mut self, node: Node, name: &str, expected: RangeInclusive<usize>) {
+        let Some(args) = node.parent().and_then(|call| {
+            call.children(&mut call.walk())
+                .find(|c| c.kind() == "call_args")
+        }) else {
+            return;
+        };
+        let actual = args
+            .named_children(&mut args.walk())
+            .filter(|arg| arg.kind() != "comment")
+            .count();
+        if expected.contains(&actual) {
+            return;
+        }
+
+        let (min, max) = (*expected.start(), *expected.end());
+        let count = if min == max {
+            format!("{max} argument{}", if max == 1 { "" } else { "s" })
+        } else {
+            format!("{min} to {max} arguments")
+        };
+        self.diagnostics.push(Diagnostic {
+            range: Range::new(
+                self.position_at(node.start_byte()),
+                self.position_at(args.end_byte()),
+            ),
+            severity: Some(DiagnosticSeverity::WARNING),
+            source: Some("squirrel-semantic".to_string()),
+            message: format!("'{name}' expects {count} but got {actual}"),
+            ..helpers::coded_diagnostic("argument-count")
         });
     }
 
+    /// Whether an enclosing table or class defines a slot called `name`,
+    /// which is what a bare `name` refers to inside its methods.
+    fn is_shadowed_by_slot(&self, node: Node, name: &str) -> bool {
+        let mut current = node.parent();
+        while let Some(ancestor) = current {
+            let slots = match ancestor.kind() {
+                "table" => self.extract_table_slot_names(ancestor),
+                "class_declaration" => self.extract_class_member_names(ancestor),
+                _ => HashSet::new(),
+            };
+            if slots.contains(name) {
+                return true;
+            }
+            current = ancestor.parent();
+        }
+        false
+    }
+
+    /// Up to three in-scope locals, known globals or builtins closest to
+    /// `name`, using the same distance cutoff as the workspace "did you
+    /// mean?" suggestions.
+    fn similar_names(&self, name: &str, ctx: &ResolverContext) -> Vec<String> {
+        let globals = self.known_globals.into_iter().flatten();
+        let candidates: HashSet<&str> = ctx
+            .locals
+            .iter()
+            .chain(globals)
+            .map(String::as_str)
+            .chain(BUILTINS.iter().copied())
+            .collect();
+
+        let mut scored: Vec<(usize, &str)> = candidates
+            .into_iter()
+            .map(|candidate| (levenshtein_distance(name, candidate), candidate))
+            .filter(|(distance, _)| *distance < name.len() / 2)
+            .collect();
+        scored.sort();
+
+        scored
+            .into_iter()
+            .take(3)
+            .map(|(_, candidate)| candidate.to_string())
+            .collect()
+    }
+
     fn should_skip_identifier(&self, node: Node) -> bool {
         let Some(parent) = node.parent() else {
             return false;
@@ -714,11 +1869,32 @@ impl<'a> SymbolResolver<'a> {
             return true;
         }
 
+        // Skip Quirrel null-safe accesses (obj?.property), which the Squirrel
+        // grammar sees as a stray '?' followed by '.'
+        if self.dialect == Dialect::Quirrel
+            && let Some(prev) = node.prev_sibling()
+            && (prev.kind() == "?."
+                || (prev.kind() == "."
+                    && prev
+                        .prev_sibling()
+                        .is_some_and(|q| q.kind() == "?" && q.end_byte() == prev.start_byte())))
+        {
+            return true;
+        }
+
         // Skip global variable syntax (::var)
         if parent_kind == "global_variable" {
             return true;
         }
 
+        // `delete name` removes a slot from `this` rather than reading a variable
+        if node
+            .prev_sibling()
+            .is_some_and(|prev| prev.kind() == "delete")
+        {
+            return true;
+        }
+
         // Skip new slot declaration LHS (name <- value)
         if parent_kind == "update_expression" {
             let mut is_first = false;
@@ -727,7 +1903,7 @@ impl<'a> SymbolResolver<'a> {
                 if child.kind() == "identifier" && !is_first {
                     is_first = child.id() == node.id();
                 }
-                if child.kind() == "<-" {
+                if self.dialect.is_new_slot_operator(child.kind()) {
                     has_new_slot = true;
                 }
             }
@@ -805,20 +1981,18 @@ impl<'a> SymbolResolver<'a> {
     }
 
     fn extract_slot_name(&self, node: Node) -> Option<String> {
-        for child in node.children(&mut node.walk()) {
-            match child.kind() {
-                "identifier" => {
-                    return Some(self.node_text(child).to_string());
-                },
-                "function_declaration" => {
-                    if let Some(ident) = self.find_first_identifier(child) {
-                        return Some(self.node_text(ident).to_string());
-                    }
-                },
-                _ => {},
-            }
+        self.slot_name_node(node)
+            .map(|key| helpers::extract_string_content(key, self.text))
+    }
+
+    /// The identifier or string literal naming a table slot.
+    fn slot_name_node<'b>(&self, node: Node<'b>) -> Option<Node<'b>> {
+        if let Some(key) = helpers::table_slot_key(node) {
+            return Some(key);
         }
-        None
+        node.children(&mut node.walk())
+            .find(|child| child.kind() == "function_declaration")
+            .and_then(|function| self.find_first_identifier(function))
     }
 
     fn find_declaration_name<'b>(&self, node: Node<'b>) -> Option<Node<'b>> {
@@ -884,7 +2058,6 @@ impl<'a> SymbolResolver<'a> {
     }
 }
 
-#[allow(dead_code)]
 pub fn compute_symbol_diagnostics(
     file_path: &str,
     text: &str,
@@ -902,9 +2075,97 @@ pub fn compute_symbol_diagnostics_with_globals(
     resolver.analyze()
 }
 
+pub fn compute_symbol_diagnostics_with_workspace(
+    file_path: &str,
+    text: &str,
+    workspace: &Workspace,
+    config: &ServerConfig,
+) -> Result<Vec<Diagnostic>, AnalysisError> {
+    let resolver = SymbolResolver::with_workspace(file_path, text, workspace)?
+        .with_dialect(config.dialect)
+        .with_shadowing_hints(config.shadowing_hints)
+        .with_recursion_hints(config.recursion_hints)
+        .with_argument_count_checks(config.argument_count_checks)
+        .with_underscore_unused(config.report_underscore_unused)
+        .with_unused_symbol_hints(config.unused_symbol_allowlist())
+        .with_shared_globals(config.shared_globals.clone());
+    resolver.analyze()
+}
+
+/// Like `compute_symbol_diagnostics_with_workspace`, but reuses an already
+/// parsed tree.
+pub fn compute_symbol_diagnostics_with_tree(
+    file_path: &str,
+    tree: &Tree,
+    text: &str,
+    workspace: &Workspace,
+    config: &ServerConfig,
+) -> Result<Vec<Diagnostic>, AnalysisError> {
+    let resolver = SymbolResolver::with_workspace_and_tree(file_path, tree, text, workspace)
+        .with_dialect(config.dialect)
+        .with_shadowing_hints(config.shadowing_hints)
+        .with_recursion_hints(config.recursion_hints)
+        .with_argument_count_checks(config.argument_count_checks)
+        .with_underscore_unused(config.report_underscore_unused)
+        .with_unused_symbol_hints(config.unused_symbol_allowlist())
+        .with_shared_globals(config.shared_globals.clone());
+    resolver.analyze()
+}
+
+/// Number of call expressions in `node`, including nested ones.
+fn count_calls(node: Node) -> usize {
+    let own = usize::from(node.kind() == "call_expression");
+    own + node
+        .children(&mut node.walk())
+        .map(count_calls)
+        .sum::<usize>()
+}
+
+fn global_range(global: &GlobalDefinition) -> Range {
+    Range::new(
+        Position::new(global.line, global.column),
+        Position::new(global.line, global.end_column),
+    )
+}
+
+/// Match a name against an allowlist entry, where a trailing `*` matches any
+/// suffix.
+pub(crate) fn name_matches(pattern: &str, name: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => name.starts_with(prefix),
+        None => pattern == name,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::path::Path;
+
+    const KNIGHT_PATH: &str = "/test/scripts/entity/tactical/knight.nut";
+
+    fn create_knight_workspace(knight_code: &str, parent_indexed: bool) -> Workspace {
+        let mut workspace = Workspace::new();
+        if parent_indexed {
+            let actor_code = r#"
+                actor <- {
+                    function onDeath() {}
+                    function setFatigue(_f) {}
+                };
+            "#;
+            workspace
+                .index_file(
+                    Path::new("/test/scripts/entity/tactical/actor.nut"),
+                    actor_code,
+                )
+                .unwrap();
+        }
+        workspace
+            .index_file(Path::new(KNIGHT_PATH), knight_code)
+            .unwrap();
+        workspace.build_inheritance_graph();
+        workspace
+    }
 
     #[test]
     fn test_local_variable() {
@@ -983,6 +2244,25 @@ mod tests {
         assert!(undeclared.is_empty(), "Got: {:?}", undeclared);
     }
 
+    #[test]
+    fn test_static_class_member_visible_to_methods() {
+        let code = r#"
+            class Counter {
+                static Limit = 10;
+                count = 0;
+                function isFull() {
+                    return count >= Limit;
+                }
+            }
+        "#;
+        let diagnostics = compute_symbol_diagnostics("test.nut", code).unwrap();
+        let undeclared: Vec<_> = diagnostics
+            .iter()
+            .filter(|d| d.message.contains("Undeclared"))
+            .collect();
+        assert!(undeclared.is_empty(), "Got: {:?}", undeclared);
+    }
+
     #[test]
     fn test_inherited_function_call() {
         let code = r#"
@@ -1047,6 +2327,40 @@ mod tests {
         assert_eq!(unused[0].severity, Some(DiagnosticSeverity::WARNING));
     }
 
+    #[test]
+    fn test_underscore_prefix_reported_when_not_exempt() {
+        let code = r#"
+            function test(_unused_param) {
+                local _unused_local = 1;
+                return 0;
+            }
+        "#;
+        let diagnostics = SymbolResolver::new("test.nut", code)
+            .unwrap()
+            .with_underscore_unused(true)
+            .analyze()
+            .unwrap();
+        let mut unused: Vec<_> = diagnostics
+            .iter()
+            .filter(|d| d.message.contains("Unused"))
+            .map(|d| (d.message.as_str(), d.severity))
+            .collect();
+        unused.sort_by_key(|(message, _)| *message);
+        assert_eq!(
+            unused,
+            vec![
+                (
+                    "Unused variable '_unused_local'",
+                    Some(DiagnosticSeverity::WARNING)
+                ),
+                (
+                    "Unused variable '_unused_param'",
+                    Some(DiagnosticSeverity::HINT)
+                ),
+            ]
+        );
+    }
+
     #[test]
     fn test_unused_parameter_is_hint() {
         let code = r#"
@@ -1390,6 +2704,42 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_lambda_comma_expression_body() {
+        let code = r#"
+            function pair() {
+                local both = @(a, b) (a + b, a * b);
+                return both(2, 3);
+            }
+        "#;
+        let diagnostics = compute_symbol_diagnostics("test.nut", code).unwrap();
+        let params: Vec<_> = diagnostics
+            .iter()
+            .filter(|d| d.message.contains("'a'") || d.message.contains("'b'"))
+            .collect();
+        assert!(params.is_empty(), "Got: {:?}", params);
+    }
+
+    #[test]
+    fn test_generator_yield_reads_locals() {
+        let code = r#"
+            function counter(_limit) {
+                local i = 0;
+                while (i < _limit) {
+                    local value = i * 2;
+                    yield value;
+                    i++;
+                }
+                return null;
+            }
+            local gen = counter(3);
+            local first = resume gen;
+            print(first);
+        "#;
+        let diagnostics = compute_symbol_diagnostics("test.nut", code).unwrap();
+        assert!(diagnostics.is_empty(), "Got: {:?}", diagnostics);
+    }
+
     #[test]
     fn test_lambda_in_filter_chain() {
         let code = r#"
@@ -1551,4 +2901,988 @@ mod tests {
         let diagnostics = compute_symbol_diagnostics("test.nut", code).unwrap();
         assert!(!diagnostics.iter().any(|d| d.message.contains("Undeclared")));
     }
+
+    #[test]
+    fn test_this_call_to_missing_method() {
+        let code = r#"
+            this.knight <- this.inherit("scripts/entity/tactical/actor", {
+                function onTurnStart() {
+                    this.setFatige(10);
+                }
+            });
+        "#;
+        let workspace = create_knight_workspace(code, true);
+        let diagnostics = compute_symbol_diagnostics_with_workspace(
+            KNIGHT_PATH,
+            code,
+            &workspace,
+            &ServerConfig::default(),
+        )
+        .unwrap();
+        let missing: Vec<_> = diagnostics
+            .iter()
+            .filter(|d| d.message.contains("not found"))
+            .collect();
+        assert_eq!(missing.len(), 1, "Got: {:?}", diagnostics);
+        assert!(missing[0].message.contains("'setFatige'"));
+        assert!(missing[0].message.contains("Did you mean: setFatigue?"));
+    }
+
+    #[test]
+    fn test_base_call_checked_against_parent() {
+        let code = r#"
+            this.knight <- this.inherit("scripts/entity/tactical/actor", {
+                function onDeath() {
+                    base.onDeath();
+                    base.setFatige(10);
+                }
+                function onTurnStart() {
+                    base.onTurnStart();
+                }
+            });
+        "#;
+        let workspace = create_knight_workspace(code, true);
+        let diagnostics = compute_symbol_diagnostics_with_workspace(
+            KNIGHT_PATH,
+            code,
+            &workspace,
+            &ServerConfig::default(),
+        )
+        .unwrap();
+        assert!(!diagnostics.iter().any(|d| d.message.contains("Undeclared")));
+        let missing: Vec<_> = diagnostics
+            .iter()
+            .filter(|d| d.message.contains("not found"))
+            .map(|d| d.message.as_str())
+            .collect();
+        assert_eq!(
+            missing,
+            vec![
+                "Method 'setFatige' not found in parent 'actor' or its ancestors. Did you mean: setFatigue?",
+                "Method 'onTurnStart' not found in parent 'actor' or its ancestors",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_this_call_to_inherited_method() {
+        let code = r#"
+            this.knight <- this.inherit("scripts/entity/tactical/actor", {
+                function onTurnStart() {
+                    this.setFatigue(10);
+                    this.onTurnStart();
+                    local helper = {
+                        function run() {
+                            this.somethingElse();
+                        }
+                    };
+                    return helper;
+                }
+            });
+        "#;
+        let workspace = create_knight_workspace(code, true);
+        let diagnostics = compute_symbol_diagnostics_with_workspace(
+            KNIGHT_PATH,
+            code,
+            &workspace,
+            &ServerConfig::default(),
+        )
+        .unwrap();
+        assert!(
+            !diagnostics.iter().any(|d| d.message.contains("not found")),
+            "Got: {:?}",
+            diagnostics
+        );
+    }
+
+    #[test]
+    fn test_this_m_field_checked_against_ancestors() {
+        let mut workspace = Workspace::new();
+        let actor_code = r#"
+            actor <- {
+                m = { Hitpoints = 0 },
+                function onDeath() {}
+            };
+        "#;
+        workspace
+            .index_file(
+                Path::new("/test/scripts/entity/tactical/actor.nut"),
+                actor_code,
+            )
+            .unwrap();
+        let code = r#"
+            this.knight <- this.inherit("scripts/entity/tactical/actor", {
+                m = { Armor = 0 },
+                function onTurnStart() {
+                    this.m.Hitpoints = this.m.Armor + this.m.Hitpionts;
+                    this.m.Extra <- 1;
+                }
+            });
+        "#;
+        workspace.index_file(Path::new(KNIGHT_PATH), code).unwrap();
+        workspace.build_inheritance_graph();
+
+        let diagnostics = compute_symbol_diagnostics_with_workspace(
+            KNIGHT_PATH,
+            code,
+            &workspace,
+            &ServerConfig::default(),
+        )
+        .unwrap();
+        let unknown: Vec<_> = diagnostics
+            .iter()
+            .filter(|d| d.message.contains("not found in 'm'"))
+            .collect();
+        assert_eq!(unknown.len(), 1, "Got: {:?}", diagnostics);
+        assert!(unknown[0].message.contains("'Hitpionts'"));
+        assert_eq!(unknown[0].severity, Some(DiagnosticSeverity::WARNING));
+    }
+
+    #[test]
+    fn test_this_call_with_unindexed_parent() {
+        let code = r#"
+            this.knight <- this.inherit("scripts/entity/tactical/actor", {
+                function onTurnStart() {
+                    this.setFatige(10);
+                }
+            });
+        "#;
+        let workspace = create_knight_workspace(code, false);
+        let diagnostics = compute_symbol_diagnostics_with_workspace(
+            KNIGHT_PATH,
+            code,
+            &workspace,
+            &ServerConfig::default(),
+        )
+        .unwrap();
+        assert!(
+            !diagnostics.iter().any(|d| d.message.contains("not found")),
+            "Got: {:?}",
+            diagnostics
+        );
+    }
+
+    #[test]
+    fn test_unreferenced_symbol_hints() {
+        let helper_path = "/test/scripts/util/helper.nut";
+        let helper_code = r#"
+            helper <- {
+                function used() {}
+                function unused() {}
+                function onCombatStarted() {}
+                function _get(_key) {}
+                function _private() {}
+            };
+            function lonely() {}
+        "#;
+        let mut workspace = Workspace::new();
+        workspace.set_track_references(true);
+        workspace
+            .index_file(Path::new(helper_path), helper_code)
+            .unwrap();
+        workspace
+            .index_file(
+                Path::new("/test/scripts/util/caller.nut"),
+                "caller <- { function run() { ::helper.used(); } };",
+            )
+            .unwrap();
+
+        let config = ServerConfig {
+            unused_symbol_hints: true,
+            unused_symbol_allowlist: vec!["run".to_string()],
+            ..ServerConfig::default()
+        };
+        let diagnostics = compute_symbol_diagnostics_with_workspace(
+            helper_path,
+            helper_code,
+            &workspace,
+            &config,
+        )
+        .unwrap();
+        let mut unreferenced: Vec<_> = diagnostics
+            .iter()
+            .filter(|d| d.message.ends_with("never referenced in the workspace"))
+            .map(|d| d.message.as_str())
+            .collect();
+        unreferenced.sort_unstable();
+        assert_eq!(
+            unreferenced,
+            [
+                "'_private' is never referenced in the workspace",
+                "'lonely' is never referenced in the workspace",
+                "'unused' is never referenced in the workspace"
+            ]
+        );
+
+        let disabled = compute_symbol_diagnostics_with_workspace(
+            helper_path,
+            helper_code,
+            &workspace,
+            &ServerConfig::default(),
+        )
+        .unwrap();
+        assert!(
+            !disabled
+                .iter()
+                .any(|d| d.message.ends_with("never referenced in the workspace"))
+        );
+    }
+
+    #[test]
+    fn test_string_and_computed_slot_keys() {
+        let code = r#"
+            function f() {
+                return {
+                    "Label" = "sword",
+                    function getLabel() { return Label; },
+                    [missing] = 1
+                };
+            }
+        "#;
+        let diagnostics = compute_symbol_diagnostics("/test/a.nut", code).unwrap();
+        let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(messages, ["Undeclared variable 'missing'"]);
+    }
+
+    #[test]
+    fn test_symbol_kind_mismatch() {
+        let code = r#"
+            Config <- { Size = 1 };
+            makeConfig <- function () { return {}; };
+            function f() {
+                Config();
+                makeConfig.Size;
+                makeConfig.call(this);
+                return Config.Size;
+            }
+        "#;
+        let diagnostics = compute_symbol_diagnostics("/test/a.nut", code).unwrap();
+        let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "'Config' is a table and cannot be called",
+                "'makeConfig' is a function and has no members"
+            ]
+        );
+        assert!(
+            diagnostics
+                .iter()
+                .all(|d| d.severity == Some(DiagnosticSeverity::WARNING))
+        );
+    }
+
+    #[test]
+    fn test_argument_count() {
+        let code = r#"
+            clamp <- function (value, low, high = 1) { return value; };
+            identity <- function (x) { return x; };
+            report <- function (...) {};
+            function pad(text, width = 2) { return text; }
+            function f(identity) {
+                clamp(0.5);
+                clamp(0.5, 0);
+                clamp(0.5, 0, 1, 2);
+                report(1, 2, 3);
+                pad();
+                return identity();
+            }
+            function g() {
+                return identity();
+            }
+        "#;
+        let argument_counts = |enabled| {
+            SymbolResolver::new("test.nut", code)
+                .unwrap()
+                .with_argument_count_checks(enabled)
+                .analyze()
+                .unwrap()
+                .into_iter()
+                .filter(|d| d.message.contains(" expects "))
+                .map(|d| d.message)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            argument_counts(true),
+            [
+                "'clamp' expects 2 to 3 arguments but got 1",
+                "'clamp' expects 2 to 3 arguments but got 4",
+                "'pad' expects 1 to 2 arguments but got 0",
+                "'identity' expects 1 argument but got 0"
+            ]
+        );
+        assert!(argument_counts(false).is_empty());
+    }
+
+    #[test]
+    fn test_argument_count_of_workspace_functions() {
+        let mut workspace = Workspace::new();
+        workspace
+            .index_file(
+                Path::new("/test/scripts/utils.nut"),
+                "clamp <- function (value, low, high = 1) { return value; };\n",
+            )
+            .unwrap();
+        let code = "function f() {\n    clamp(1);\n    clamp(1, 0);\n}\n";
+        let config = ServerConfig {
+            argument_count_checks: true,
+            ..ServerConfig::default()
+        };
+        let diagnostics = compute_symbol_diagnostics_with_workspace(
+            "/test/scripts/caller.nut",
+            code,
+            &workspace,
+            &config,
+        )
+        .unwrap();
+        let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(messages, ["'clamp' expects 2 to 3 arguments but got 1"]);
+    }
+
+    #[test]
+    fn test_symbol_kind_mismatch_respects_shadowing() {
+        let code = r#"
+            Config <- {};
+            Other <- {
+                Config = function () {},
+                function f(makeConfig) {
+                    Config();
+                    return makeConfig.Size;
+                }
+            };
+            makeConfig <- function () {};
+        "#;
+        let diagnostics = compute_symbol_diagnostics("/test/a.nut", code).unwrap();
+        assert!(diagnostics.is_empty(), "Got: {:?}", diagnostics);
+    }
+
+    #[test]
+    fn test_in_operator_checks_container_only() {
+        let code = r#"
+            function f() {
+                local key = "Arena";
+                local table = {};
+                if ("Arena" in ::Legends) {}
+                if (key in table) {}
+                return "Arena" in missing;
+            }
+        "#;
+        let diagnostics = compute_symbol_diagnostics("/test/a.nut", code).unwrap();
+        let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(messages, ["Undeclared variable 'missing'"]);
+    }
+
+    #[test]
+    fn test_delete_slot_not_reported() {
+        let code = r#"
+            function f(obj) {
+                delete obj.slot;
+                delete obj["other"];
+                delete cachedSlot;
+                ::Legends.rawdelete("Arena");
+            }
+        "#;
+        let diagnostics = compute_symbol_diagnostics("/test/a.nut", code).unwrap();
+        assert!(diagnostics.is_empty(), "Got: {:?}", diagnostics);
+    }
+
+    #[test]
+    fn test_undeclared_variable_links_similar_local() {
+        let code = r#"
+            function f() {
+                local counter = 0;
+                return countr + 1;
+            }
+        "#;
+        let diagnostics = compute_symbol_diagnostics("/test/a.nut", code).unwrap();
+        let undeclared = diagnostics
+            .iter()
+            .find(|d| d.message == "Undeclared variable 'countr'")
+            .expect("missing undeclared diagnostic");
+        let related = undeclared.related_information.as_ref().unwrap();
+        assert_eq!(related[0].message, "Did you mean 'counter'?");
+        assert_eq!(related[0].location.range.start.line, 2);
+        assert_eq!(
+            undeclared.data,
+            Some(serde_json::json!({ "suggestions": ["counter"] }))
+        );
+    }
+
+    #[test]
+    fn test_unused_variable_links_declaration() {
+        let code = "function f() {\n    local unused = 1;\n}\n";
+        let diagnostics = compute_symbol_diagnostics("/test/a.nut", code).unwrap();
+        let unused = diagnostics
+            .iter()
+            .find(|d| d.message == "Unused variable 'unused'")
+            .expect("missing unused diagnostic");
+        let related = unused.related_information.as_ref().unwrap();
+        assert_eq!(related[0].location.range, unused.range);
+        assert_eq!(related[0].message, "'unused' declared here");
+    }
+
+    #[test]
+    fn test_duplicate_table_member() {
+        let code = r#"
+            my_class <- {
+                function create() {
+                    return 1;
+                },
+                function create() {
+                    return 2;
+                }
+            };
+        "#;
+        let diagnostics = compute_symbol_diagnostics("/test/my_class.nut", code).unwrap();
+        let duplicates: Vec<_> = diagnostics
+            .iter()
+            .filter(|d| d.message.contains("Duplicate member"))
+            .collect();
+        assert_eq!(duplicates.len(), 1, "Got: {:?}", diagnostics);
+        assert_eq!(duplicates[0].severity, Some(DiagnosticSeverity::WARNING));
+        assert_eq!(duplicates[0].range.start.line, 5);
+
+        let related = duplicates[0].related_information.as_ref().unwrap();
+        assert_eq!(related.len(), 1);
+        assert_eq!(related[0].location.range.start.line, 2);
+    }
+
+    #[test]
+    fn test_same_member_name_in_nested_table() {
+        let code = r#"
+            my_class <- {
+                m = {
+                    create = null
+                },
+                function create() {
+                    return m.create;
+                }
+            };
+        "#;
+        let diagnostics = compute_symbol_diagnostics("test.nut", code).unwrap();
+        assert!(
+            !diagnostics
+                .iter()
+                .any(|d| d.message.contains("Duplicate member")),
+            "Got: {:?}",
+            diagnostics
+        );
+    }
+
+    #[test]
+    fn test_local_used_before_declaration() {
+        let code = r#"
+            function test() {
+                print(x);
+                local x = 1;
+                return x;
+            }
+        "#;
+        let diagnostics = compute_symbol_diagnostics("test.nut", code).unwrap();
+        let early: Vec<_> = diagnostics
+            .iter()
+            .filter(|d| d.message.contains("used before declaration"))
+            .collect();
+        assert_eq!(early.len(), 1, "Got: {:?}", diagnostics);
+        assert!(early[0].message.contains("'x'"));
+        assert_eq!(early[0].range.start.line, 2);
+        assert!(!diagnostics.iter().any(|d| d.message.contains("Undeclared")));
+    }
+
+    #[test]
+    fn test_outer_local_used_in_nested_block_before_declaration() {
+        let code = r#"
+            function test(_flag) {
+                if (_flag) {
+                    return total;
+                }
+                local total = 0;
+                return total;
+            }
+        "#;
+        let diagnostics = compute_symbol_diagnostics("test.nut", code).unwrap();
+        assert!(
+            diagnostics
+                .iter()
+                .any(|d| d.message == "Variable 'total' used before declaration"),
+            "Got: {:?}",
+            diagnostics
+        );
+    }
+
+    #[test]
+    fn test_later_local_does_not_hide_builtins_and_globals() {
+        let code = r#"
+            function test() {
+                print(Legends);
+                local print = 1;
+                local Legends = 2;
+                return print + Legends;
+            }
+        "#;
+        let globals = HashSet::from(["Legends".to_string()]);
+        let diagnostics =
+            compute_symbol_diagnostics_with_globals("test.nut", code, &globals).unwrap();
+        assert!(
+            !diagnostics
+                .iter()
+                .any(|d| d.message.ends_with("used before declaration")),
+            "Got: {:?}",
+            diagnostics
+        );
+    }
+
+    #[test]
+    fn test_forward_reference_to_table_sibling_function() {
+        let code = r#"
+            my_class <- {
+                function first() {
+                    return second();
+                },
+                function second() {
+                    return 42;
+                }
+            };
+        "#;
+        let diagnostics = compute_symbol_diagnostics("test.nut", code).unwrap();
+        assert!(
+            !diagnostics
+                .iter()
+                .any(|d| d.message.contains("before declaration")),
+            "Got: {:?}",
+            diagnostics
+        );
+    }
+
+    #[test]
+    fn test_assignment_to_unknown_table_member() {
+        let code = r#"
+            config <- {
+                Enabled = true
+            };
+            config.Enabled = false;
+            config.Verbose = true;
+            config.Extra <- 1;
+            config.Extra = 2;
+        "#;
+        let diagnostics = compute_symbol_diagnostics("test.nut", code).unwrap();
+        let missing: Vec<_> = diagnostics
+            .iter()
+            .filter(|d| d.message.contains("Assignment to unknown member"))
+            .collect();
+        assert_eq!(missing.len(), 1, "Got: {:?}", diagnostics);
+        assert!(missing[0].message.contains("'Verbose'"));
+        assert_eq!(missing[0].severity, Some(DiagnosticSeverity::WARNING));
+    }
+
+    #[test]
+    fn test_assignment_to_unknown_this_member() {
+        let code = r#"
+            this.knight <- this.inherit("scripts/entity/tactical/actor", {
+                function onTurnStart() {
+                    this.Fatigue = 10;
+                }
+            });
+        "#;
+        let workspace = create_knight_workspace(code, true);
+        let diagnostics = compute_symbol_diagnostics_with_workspace(
+            KNIGHT_PATH,
+            code,
+            &workspace,
+            &ServerConfig::default(),
+        )
+        .unwrap();
+        assert!(
+            diagnostics
+                .iter()
+                .any(|d| d.message.contains("Assignment to unknown member 'Fatigue'")),
+            "Got: {:?}",
+            diagnostics
+        );
+    }
+
+    #[test]
+    fn test_new_slot_declares_in_each_dialect() {
+        let code = r#"
+            counter <- 0;
+            function bump() {
+                return counter + 1;
+            }
+        "#;
+        for dialect in [Dialect::Squirrel, Dialect::Quirrel] {
+            let diagnostics = SymbolResolver::new("test.nut", code)
+                .unwrap()
+                .with_dialect(dialect)
+                .analyze()
+                .unwrap();
+            assert!(
+                !diagnostics.iter().any(|d| d.message.contains("Undeclared")),
+                "{dialect:?}: {diagnostics:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_quirrel_null_safe_member_access() {
+        let code = r#"
+            function test(_unit) {
+                return _unit?.getName();
+            }
+        "#;
+        let diagnostics = SymbolResolver::new("test.nut", code)
+            .unwrap()
+            .with_dialect(Dialect::Quirrel)
+            .analyze()
+            .unwrap();
+        assert!(
+            !diagnostics
+                .iter()
+                .any(|d| d.message.contains("Undeclared variable 'getName'")),
+            "Got: {:?}",
+            diagnostics
+        );
+    }
+
+    #[test]
+    fn test_empty_catch_hint() {
+        let code = r#"
+            function test() {
+                try { print(1); } catch (_e) {}
+                try { print(2); } catch (_e) {
+                    // ignored on purpose
+                }
+                try { print(3); } catch (e) {
+                    print(e);
+                }
+            }
+        "#;
+        let diagnostics = compute_symbol_diagnostics("test.nut", code).unwrap();
+        let empty: Vec<_> = diagnostics
+            .iter()
+            .filter(|d| d.message.contains("Empty catch block"))
+            .collect();
+        assert_eq!(empty.len(), 2, "Got: {:?}", diagnostics);
+        assert_eq!(empty[0].severity, Some(DiagnosticSeverity::HINT));
+        assert_eq!(empty[0].range.start.line, 2);
+    }
+
+    #[test]
+    fn test_break_and_continue_outside_loop() {
+        let code = r#"
+            function f(items, kind) {
+                foreach (item in items) {
+                    if (item == null) continue;
+                    switch (kind) {
+                        case 1:
+                            continue;
+                        default:
+                            break;
+                    }
+                }
+                switch (kind) {
+                    case 1:
+                        continue;
+                }
+                local g = function() {
+                    break;
+                };
+                g();
+            }
+        "#;
+        let diagnostics = compute_symbol_diagnostics("test.nut", code).unwrap();
+        let jumps: Vec<_> = diagnostics
+            .iter()
+            .filter(|d| d.severity == Some(DiagnosticSeverity::ERROR))
+            .map(|d| (d.range.start.line, d.message.as_str()))
+            .collect();
+        assert_eq!(
+            jumps,
+            vec![
+                (13, "'continue' outside of a loop"),
+                (16, "'break' outside of a loop or switch")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_break_and_continue_in_while_loops() {
+        let code = r#"
+            function f(items) {
+                local i = 0;
+                while (i < items.len()) {
+                    i++;
+                    if (items[i] == null) continue;
+                    break;
+                }
+                do {
+                    i--;
+                    if (i == 2) continue;
+                    if (i == 1) break;
+                } while (i > 0);
+                while (i < 10) {
+                    local g = function() { break; };
+                    i++;
+                }
+            }
+        "#;
+        let diagnostics = compute_symbol_diagnostics("test.nut", code).unwrap();
+        let jumps: Vec<_> = diagnostics
+            .iter()
+            .filter(|d| d.severity == Some(DiagnosticSeverity::ERROR))
+            .map(|d| (d.range.start.line, d.message.as_str()))
+            .collect();
+        assert_eq!(jumps, vec![(14, "'break' outside of a loop or switch")]);
+    }
+
+    #[test]
+    fn test_global_defined_in_several_files() {
+        let first_path = "/test/scripts/mods/first.nut";
+        let first_code = "::MyMod <- {};\n::Legends.Foo <- {};\nenum Color { Red }\nthis.local_slot <- 1;\n::MyMod.Late <- null;\nif (!(\"Arena\" in ::MyMod)) ::MyMod.Arena <- {};\n";
+        let mut workspace = Workspace::new();
+        workspace
+            .index_file(Path::new(first_path), first_code)
+            .unwrap();
+        workspace
+            .index_file(
+                Path::new("/test/scripts/mods/second.nut"),
+                "::mods_queue(null, null, function() {\n    ::MyMod <- {};\n    ::Legends.Foo <- {};\n});\nenum Color { Blue }\nthis.local_slot <- 2;\n::MyMod.Late <- {};\n::MyMod.Arena <- {};\n",
+            )
+            .unwrap();
+
+        let config = ServerConfig {
+            shared_globals: vec!["Legends".to_string()],
+            ..ServerConfig::default()
+        };
+        let diagnostics =
+            compute_symbol_diagnostics_with_workspace(first_path, first_code, &workspace, &config)
+                .unwrap();
+        let duplicates: Vec<_> = diagnostics
+            .iter()
+            .filter(|d| d.message.contains("is also defined in"))
+            .collect();
+        assert_eq!(duplicates.len(), 2, "{duplicates:?}");
+        assert_eq!(duplicates[0].severity, Some(DiagnosticSeverity::WARNING));
+        assert_eq!(
+            duplicates[0].range,
+            Range::new(Position::new(0, 0), Position::new(0, 7))
+        );
+        assert!(
+            duplicates[0]
+                .message
+                .starts_with("'MyMod' is also defined in another file")
+        );
+        assert!(duplicates[1].message.starts_with("'Color'"));
+
+        let related = duplicates[0].related_information.as_ref().unwrap();
+        assert_eq!(related.len(), 1);
+        assert!(related[0].location.uri.path().ends_with("second.nut"));
+        assert_eq!(related[0].location.range.start, Position::new(1, 4));
+    }
+
+    #[test]
+    fn test_coded_diagnostics_link_to_rules() {
+        let code = "function f() {\n    return 1;\n    f();\n}\n";
+        let diagnostics = compute_symbol_diagnostics("test.nut", code).unwrap();
+        let unreachable = diagnostics
+            .iter()
+            .find(|d| d.message == "Unreachable code")
+            .expect("unreachable code diagnostic");
+        assert_eq!(
+            unreachable.code_description.as_ref().unwrap().href.as_str(),
+            "https://github.com/mnshdw/squirrel-lsp/blob/main/docs/rules.md#unreachable-code"
+        );
+    }
+
+    #[test]
+    fn test_shadowing_hint() {
+        let code = r#"
+            function test(_items) {
+                local item = null;
+                foreach (item in _items) {
+                    print(item);
+                }
+                try {
+                    print(item);
+                } catch (item) {
+                    print(item);
+                }
+            }
+        "#;
+        let diagnostics = SymbolResolver::new("test.nut", code)
+            .unwrap()
+            .with_shadowing_hints(true)
+            .analyze()
+            .unwrap();
+        let hints: Vec<_> = diagnostics
+            .iter()
+            .filter(|d| d.message.contains("shadows an outer declaration"))
+            .collect();
+        assert_eq!(hints.len(), 2, "Got: {:?}", diagnostics);
+        assert!(
+            hints
+                .iter()
+                .all(|d| d.severity == Some(DiagnosticSeverity::HINT))
+        );
+    }
+
+    #[test]
+    fn test_self_recursive_method_hint() {
+        let code = r#"
+            this.sword <- this.inherit("scripts/items/item", {
+                function create() {
+                    create();
+                }
+                function onUse() {
+                    return this.onUse();
+                }
+                function getName() {
+                    return base.getName();
+                }
+                function countdown(n) {
+                    if (n > 0) countdown(n - 1);
+                }
+                function retry() {
+                    log("retrying");
+                    retry();
+                }
+            });
+        "#;
+        let recursion_hints = |enabled| {
+            SymbolResolver::new("test.nut", code)
+                .unwrap()
+                .with_recursion_hints(enabled)
+                .analyze()
+                .unwrap()
+                .into_iter()
+                .filter(|d| d.message.contains("only calls itself"))
+                .map(|d| d.range.start.line)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(recursion_hints(true), vec![3, 6]);
+        assert!(recursion_hints(false).is_empty());
+    }
+
+    #[test]
+    fn test_shadowing_hint_disabled_by_default() {
+        let code = r#"
+            function test() {
+                local x = 1;
+                if (x) {
+                    local x = 2;
+                    print(x);
+                }
+            }
+        "#;
+        let diagnostics = compute_symbol_diagnostics("test.nut", code).unwrap();
+        assert!(
+            !diagnostics
+                .iter()
+                .any(|d| d.message.contains("shadows an outer declaration")),
+            "Got: {:?}",
+            diagnostics
+        );
+    }
+
+    #[test]
+    fn test_unreachable_after_return() {
+        let code = r#"
+            function test(_x) {
+                if (_x) {
+                    return 1;
+                    print(_x);
+                    print(_x);
+                }
+                print(_x);
+                return 0;
+            }
+        "#;
+        let diagnostics = compute_symbol_diagnostics("test.nut", code).unwrap();
+        let unreachable: Vec<_> = diagnostics
+            .iter()
+            .filter(|d| d.message == "Unreachable code")
+            .collect();
+        assert_eq!(unreachable.len(), 1, "Got: {:?}", diagnostics);
+        assert_eq!(unreachable[0].range.start.line, 4);
+        assert_eq!(unreachable[0].tags, Some(vec![DiagnosticTag::UNNECESSARY]));
+    }
+
+    #[test]
+    fn test_terminator_in_nested_block_does_not_leak() {
+        let code = r#"
+            function test(_items) {
+                foreach (item in _items) {
+                    if (item == null) {
+                        continue;
+                    }
+                    print(item);
+                    break;
+                }
+                return _items;
+            }
+        "#;
+        let diagnostics = compute_symbol_diagnostics("test.nut", code).unwrap();
+        assert!(
+            !diagnostics.iter().any(|d| d.message == "Unreachable code"),
+            "Got: {:?}",
+            diagnostics
+        );
+    }
+
+    #[test]
+    fn test_default_parameter_values() {
+        let code = r#"
+            local fallback = 5;
+            function test(_a, _b = fallback, _c = missing) {
+                return _a + _b + _c;
+            }
+        "#;
+        let diagnostics = compute_symbol_diagnostics("test.nut", code).unwrap();
+        let undeclared: Vec<_> = diagnostics
+            .iter()
+            .filter(|d| d.message.contains("Undeclared"))
+            .collect();
+        assert_eq!(undeclared.len(), 1, "Got: {:?}", diagnostics);
+        assert!(undeclared[0].message.contains("'missing'"));
+        assert!(
+            !diagnostics
+                .iter()
+                .any(|d| d.message.contains("Unused variable 'fallback'")),
+            "Got: {:?}",
+            diagnostics
+        );
+    }
+
+    #[test]
+    fn test_varargs_declare_vargv() {
+        let code = r#"
+            function log(_fmt, ...) {
+                foreach (arg in vargv) {
+                    print(arg);
+                }
+            }
+        "#;
+        let diagnostics = compute_symbol_diagnostics("test.nut", code).unwrap();
+        assert!(
+            !diagnostics.iter().any(|d| d.message.contains("Undeclared")),
+            "Got: {:?}",
+            diagnostics
+        );
+    }
+
+    #[test]
+    fn test_enum_and_const_resolve() {
+        let code = r#"
+            enum Direction {
+                North,
+                South = 2
+            }
+            const MaxLevel = 11;
+
+            function test() {
+                return Direction.North + MaxLevel;
+            }
+        "#;
+        let diagnostics = compute_symbol_diagnostics("test.nut", code).unwrap();
+        assert!(diagnostics.is_empty(), "Got: {:?}", diagnostics);
+    }
 }
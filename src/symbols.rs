@@ -15,18 +15,20 @@ use std::collections::HashMap;
 use tower_lsp::lsp_types::Position;
 
 #[derive(Debug, Clone)]
-#[allow(dead_code)]
 pub struct Symbol {
     pub kind: SymbolKind,
     pub defined_at: Position,
 }
 
 #[derive(Debug, Clone)]
-#[allow(dead_code)]
 pub enum SymbolKind {
     Variable,
     Function {
         params: Vec<String>,
+        /// Number of leading parameters without a default value
+        required: usize,
+        /// Accepts extra arguments through `...` (available as `vargv`)
+        vararg: bool,
     },
     Table {
         parent: Option<String>,
@@ -37,7 +39,6 @@ pub enum SymbolKind {
 pub type SymbolMap = HashMap<String, Symbol>;
 
 #[derive(Debug, Clone)]
-#[allow(dead_code)]
 pub struct Table {
     pub name: String,
     pub path: String,
@@ -47,7 +48,6 @@ pub struct Table {
 }
 
 #[derive(Debug, Clone, Default)]
-#[allow(dead_code)]
 pub struct FileSymbols {
     pub path: String,
     pub symbols: SymbolMap,
@@ -59,7 +59,10 @@ pub struct FileSymbols {
 pub fn extract_script_path(file_path: &str) -> String {
     if let Some(idx) = file_path.find("scripts/") {
         let after_scripts = &file_path[idx + 8..]; // len("scripts/") = 8
-        return after_scripts.trim_end_matches(".nut").to_string();
+        return std::path::Path::new(after_scripts)
+            .with_extension("")
+            .to_string_lossy()
+            .into_owned();
     }
 
     std::path::Path::new(file_path)
@@ -87,5 +90,9 @@ mod tests {
             extract_script_path("scripts/items/weapons/sword.nut"),
             "items/weapons/sword"
         );
+        assert_eq!(
+            extract_script_path("/mod/scripts/mods/hooks.gnut"),
+            "mods/hooks"
+        );
     }
 }
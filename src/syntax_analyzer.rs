@@ -1,10 +1,16 @@
 use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, Range};
+use tree_sitter::{Node, Tree};
 
 use crate::errors::AnalysisError;
 use crate::helpers;
 
 pub fn compute_syntax_diagnostics(text: &str) -> Result<Vec<Diagnostic>, AnalysisError> {
     let tree = helpers::parse_squirrel(text)?;
+    Ok(compute_syntax_diagnostics_with_tree(&tree, text))
+}
+
+/// Like `compute_syntax_diagnostics`, but reuses an already parsed tree.
+pub fn compute_syntax_diagnostics_with_tree(tree: &Tree, text: &str) -> Vec<Diagnostic> {
     let root = tree.root_node();
 
     let mut diags: Vec<Diagnostic> = Vec::new();
@@ -12,7 +18,10 @@ pub fn compute_syntax_diagnostics(text: &str) -> Result<Vec<Diagnostic>, Analysi
     let mut visited_children = false;
     loop {
         let node = cursor.node();
-        if node.is_error() || node.is_missing() || node.kind() == "ERROR" {
+        // Errors nested in an already reported ERROR node only repeat it,
+        // but a missing token inside one is still worth pointing at
+        let is_error = node.is_error() && !has_error_ancestor(node);
+        if is_error || node.is_missing() {
             let start = node.start_byte();
             let mut end = node.end_byte();
             if end <= start {
@@ -24,20 +33,9 @@ pub fn compute_syntax_diagnostics(text: &str) -> Result<Vec<Diagnostic>, Analysi
             );
 
             let msg = if node.is_missing() {
-                format!("Missing {}", node.kind())
+                missing_message(node)
             } else {
-                let snippet = &text[start..end];
-                let first = snippet.lines().next().unwrap_or("").trim();
-                if first.is_empty() {
-                    "Unexpected input".to_string()
-                } else {
-                    let display = if first.len() > 40 {
-                        format!("{}…", &first[..40])
-                    } else {
-                        first.to_string()
-                    };
-                    format!("Unexpected '{}'", display)
-                }
+                unexpected_message(node, text)
             };
 
             diags.push(Diagnostic {
@@ -62,5 +60,101 @@ pub fn compute_syntax_diagnostics(text: &str) -> Result<Vec<Diagnostic>, Analysi
         }
         visited_children = true;
     }
-    Ok(diags)
+    diags
+}
+
+fn has_error_ancestor(node: Node) -> bool {
+    let mut current = node.parent();
+    while let Some(parent) = current {
+        if parent.is_error() {
+            return true;
+        }
+        current = parent.parent();
+    }
+    false
+}
+
+/// Name the token tree-sitter had to invent, e.g. "Missing ')'".
+fn missing_message(node: Node) -> String {
+    if node.is_named() {
+        format!("Missing {}", node.kind().replace('_', " "))
+    } else {
+        format!("Missing '{}'", node.kind())
+    }
+}
+
+/// Describe an ERROR node by the first token it swallowed rather than the
+/// whole span, which can run over many lines.
+fn unexpected_message(node: Node, text: &str) -> String {
+    let Some(token) = first_leaf(node) else {
+        return if node.start_byte() >= text.trim_end().len() {
+            "Unexpected end of input".to_string()
+        } else {
+            "Unexpected input".to_string()
+        };
+    };
+
+    let token_text = token.utf8_text(text.as_bytes()).unwrap_or("").trim();
+    let first_line = token_text.lines().next().unwrap_or("");
+    let display = match first_line.char_indices().nth(40) {
+        Some((cut, _)) => format!("{}…", &first_line[..cut]),
+        None => first_line.to_string(),
+    };
+
+    match token.kind() {
+        _ if display.is_empty() => "Unexpected input".to_string(),
+        "identifier" => format!("Unexpected identifier '{display}'"),
+        "string" | "verbatim_string" | "char" => format!("Unexpected string {display}"),
+        "integer" | "float" => format!("Unexpected number '{display}'"),
+        _ => format!("Unexpected '{display}'"),
+    }
+}
+
+fn first_leaf(node: Node) -> Option<Node> {
+    let mut current = node;
+    // Strings are reported whole rather than by their opening quote
+    while current.child_count() > 0
+        && !matches!(current.kind(), "string" | "verbatim_string" | "char")
+    {
+        current = current.child(0)?;
+    }
+    (current.id() != node.id() || current.end_byte() > current.start_byte()).then_some(current)
+}
+
+#[cfg(test)]
+mod tests {
+    use tower_lsp::lsp_types::Position;
+
+    use super::*;
+
+    #[test]
+    fn test_missing_token_names_the_token() {
+        let diags = compute_syntax_diagnostics("local x = foo(1, 2;\n").unwrap();
+        assert_eq!(diags.len(), 1, "{diags:?}");
+        assert_eq!(diags[0].message, "Missing ')'");
+        // The invented token is empty, so the range covers the next character
+        assert_eq!(
+            diags[0].range,
+            Range::new(Position::new(0, 18), Position::new(0, 19))
+        );
+    }
+
+    #[test]
+    fn test_error_reported_once_per_node() {
+        let code = "local a = 1;\nlocal b = ) + ;\nlocal c = 3;\n";
+        let diags = compute_syntax_diagnostics(code).unwrap();
+        assert!(!diags.is_empty());
+        assert!(diags.iter().all(|d| d.range.start.line == 1), "{diags:?}");
+        assert!(
+            diags
+                .iter()
+                .all(|d| !d.message.contains('\n') && d.message.chars().count() < 80)
+        );
+    }
+
+    #[test]
+    fn test_clean_code_has_no_diagnostics() {
+        let diags = compute_syntax_diagnostics("local a = [1, 2];\n").unwrap();
+        assert!(diags.is_empty());
+    }
 }
@@ -3,14 +3,17 @@
 //! The workspace is indexed by script path (e.g., "statistics/statistics_manager"),
 //! making lookups trivial for hook validation and inheritance resolution.
 
-use std::collections::{HashMap, HashSet};
+use std::borrow::Borrow;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::path::{Path, PathBuf};
 
-use tree_sitter::Node;
+use tree_sitter::{Node, Tree};
 
-use crate::bb_support::{find_inherit_calls, get_node_text};
+use crate::bb_support::{find_hook_calls, find_inherit_calls, get_node_text};
 use crate::errors::AnalysisError;
 use crate::helpers;
+use crate::symbol_extractor::{declared_function_name, extract_symbol_from_value};
+use crate::symbols::SymbolKind;
 
 /// Information about a class member (method or field)
 #[derive(Debug, Clone)]
@@ -41,7 +44,10 @@ pub struct FileEntry {
     pub script_path: String,
     /// Name of the main definition (usually matches file stem)
     pub name: String,
-    /// For classes: the parent script path (e.g., "entity/tactical/actor")
+    /// Zero-based line of the statement defining the class or table
+    pub definition_line: u32,
+    /// For classes: the parent script path (e.g., "entity/tactical/actor"),
+    /// or the parent class name for `class Name extends Parent`
     pub parent_path: Option<String>,
     /// Resolved parent script path (normalized, after building graph)
     pub parent: Option<String>,
@@ -49,6 +55,114 @@ pub struct FileEntry {
     pub children: Vec<String>,
     /// Members (methods) defined in this file
     pub members: Vec<MemberInfo>,
+    /// Slots of the `m = { ... }` table that BB classes keep their state in
+    pub m_fields: Vec<MemberInfo>,
+}
+
+impl FileEntry {
+    /// Whether this entry comes from a bundled API stub rather than a file,
+    /// in which case it has no location and may list only some members.
+    pub fn is_stub(&self) -> bool {
+        self.file_path.as_os_str().is_empty()
+    }
+}
+
+/// A top-level class or table, indexed by its own name.
+///
+/// A file's main definition is also its `FileEntry`, but files may define
+/// further classes or tables that are only reachable by name.
+#[derive(Debug, Clone)]
+pub struct Definition {
+    pub name: String,
+    pub file_path: PathBuf,
+    pub script_path: String,
+    /// Zero-based line of the defining statement
+    pub line: u32,
+    pub members: Vec<MemberInfo>,
+}
+
+/// The statement a global is defined by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GlobalKind {
+    /// A `<-` slot, such as `::Legends.Foo <- {}`
+    Slot,
+    Class,
+    Enum,
+    Const,
+}
+
+/// A global a file defines, such as `::Legends.Foo <- {}` or `enum Color`.
+#[derive(Debug, Clone)]
+pub struct GlobalDefinition {
+    /// Dotted path without the leading `::`, e.g. "Legends.Foo"
+    pub name: String,
+    pub line: u32,
+    pub column: u32,
+    /// Column where the defined path ends, on the same line
+    pub end_column: u32,
+    /// Whether this site gives the global its contents: a table, function,
+    /// class or enum rather than a placeholder like `null`
+    pub has_body: bool,
+    /// Whether the site only runs when the slot is missing, as in
+    /// `if (!("Foo" in ::Mod)) ::Mod.Foo <- {}`
+    pub guarded: bool,
+    pub kind: GlobalKind,
+}
+
+impl GlobalDefinition {
+    /// Whether this site replaces whatever another file put in the global.
+    /// Guarded slots and placeholders such as `::Mod.Foo <- null` don't.
+    fn overwrites(&self) -> bool {
+        !self.guarded && (self.has_body || self.kind != GlobalKind::Slot)
+    }
+}
+
+/// Parameters of a function a file defines at the top level, with
+/// `function name(...)` or `name <- function (...)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionSignature {
+    pub file_path: PathBuf,
+    pub params: usize,
+    /// Parameters without a default
+    pub required: usize,
+    /// Accepts extra arguments through `...`
+    pub vararg: bool,
+}
+
+/// Where a global is first declared and where it gets its contents. The two
+/// differ when a global is forward-declared, e.g. `Foo <- null;` and filled
+/// in later, or guarded by `if (!("Foo" in ::Mod)) ::Mod.Foo <- {}`.
+#[derive(Debug)]
+pub struct GlobalSites<'a> {
+    pub declaration: (&'a Path, &'a GlobalDefinition),
+    pub definition: (&'a Path, &'a GlobalDefinition),
+}
+
+/// A global one file defines along with the other files' definitions of it.
+#[derive(Debug)]
+pub struct GlobalCollision<'a> {
+    pub global: &'a GlobalDefinition,
+    pub others: Vec<(&'a Path, &'a GlobalDefinition)>,
+}
+
+/// Names a file defines and the names it mentions, for finding definitions
+/// nothing in the workspace refers to.
+#[derive(Debug, Default)]
+struct FileReferences {
+    /// Identifier (and identifier-like string) occurrences, definitions included
+    mentions: HashMap<String, usize>,
+    /// Top-level functions and class or table methods
+    definitions: Vec<MemberInfo>,
+}
+
+/// Pattern: `class Name extends Parent { body }`
+#[derive(Debug, Clone)]
+pub struct ClassDeclaration<'tree> {
+    pub name: String,
+    /// Name of the class after `extends`, if any
+    pub parent: Option<String>,
+    pub node: Node<'tree>,
+    pub body: Node<'tree>,
 }
 
 /// The workspace indexed by script path.
@@ -59,8 +173,49 @@ pub struct FileEntry {
 pub struct Workspace {
     /// Script path -> file entry
     files: HashMap<String, FileEntry>,
+    /// Lowercased script path -> script paths, for spotting paths that only
+    /// match an indexed file by ignoring case
+    paths_by_lowercase: HashMap<String, BTreeSet<String>>,
+    /// Main definition name -> script paths, for `class Name extends Parent`
+    paths_by_name: HashMap<String, BTreeSet<String>>,
     /// Global identifiers defined across all files
     globals: HashSet<String>,
+    /// Definition name -> every class or table with that name
+    definitions: HashMap<String, Vec<Definition>>,
+    /// File -> names of the classes and tables it defines, its keys in
+    /// `definitions`
+    definition_names: HashMap<PathBuf, Vec<String>>,
+    /// Directories script paths are relative to. When empty, the path after
+    /// the first `scripts/` segment is used.
+    script_roots: Vec<PathBuf>,
+    /// Name of the function scripts use to load other files, if any
+    require_function: Option<String>,
+    /// File -> paths it passes to the require function
+    requires: HashMap<PathBuf, Vec<String>>,
+    /// File -> top-level names it defines, for files that can be required
+    file_globals: HashMap<PathBuf, HashSet<String>>,
+    /// Whether `references` is kept, which costs a walk over every
+    /// identifier of each indexed file
+    track_references: bool,
+    /// File -> what it defines and mentions
+    references: HashMap<PathBuf, FileReferences>,
+    /// Name -> mentions across all files in `references`
+    mention_totals: HashMap<String, usize>,
+    /// Name -> definitions across all files in `references`
+    definition_totals: HashMap<String, usize>,
+    /// File -> globals it defines, for spotting files that overwrite each
+    /// other's globals
+    global_definitions: HashMap<PathBuf, Vec<GlobalDefinition>>,
+    /// Global name -> files in `global_definitions` defining it
+    global_files: HashMap<String, BTreeSet<PathBuf>>,
+    /// Function name -> top-level functions of that name across files
+    functions: HashMap<String, Vec<FunctionSignature>>,
+    /// File -> names of its top-level functions, its keys in `functions`
+    function_names: HashMap<PathBuf, Vec<String>>,
+    /// Script path -> files that inherit from or hook it
+    dependents: HashMap<String, BTreeSet<PathBuf>>,
+    /// File -> script paths it inherits from or hooks, its keys in `dependents`
+    dependency_targets: HashMap<PathBuf, Vec<String>>,
 }
 
 impl Workspace {
@@ -68,6 +223,106 @@ impl Workspace {
         Self::default()
     }
 
+    /// Compute script paths relative to `roots` instead of a `scripts/` segment.
+    pub fn set_script_roots(&mut self, roots: Vec<PathBuf>) {
+        self.script_roots = roots;
+    }
+
+    /// Follow `name("path")` calls so globals of required files are known in
+    /// the requiring file. `None` turns dependency tracking off.
+    pub fn set_require_function(&mut self, name: Option<String>) {
+        self.require_function = name;
+    }
+
+    /// Keep track of which names each file defines and mentions, which
+    /// `unreferenced_definitions` needs. Takes effect for files indexed
+    /// afterwards.
+    pub fn set_track_references(&mut self, enabled: bool) {
+        self.track_references = enabled;
+    }
+
+    /// Top-level names defined by every file `file_path` requires, directly
+    /// or transitively.
+    pub fn dependency_globals(&self, file_path: &Path) -> HashSet<String> {
+        let mut globals = HashSet::new();
+        let mut visited = HashSet::from([file_path.to_path_buf()]);
+        let mut pending = vec![file_path.to_path_buf()];
+
+        while let Some(current) = pending.pop() {
+            let Some(requires) = self.requires.get(&current) else {
+                continue;
+            };
+            for required in requires {
+                let Some(dependency) = self.resolve_require(&current, required) else {
+                    continue;
+                };
+                // Cycles are common when files require each other
+                if !visited.insert(dependency.clone()) {
+                    continue;
+                }
+                if let Some(names) = self.file_globals.get(&dependency) {
+                    globals.extend(names.iter().cloned());
+                }
+                pending.push(dependency);
+            }
+        }
+
+        globals
+    }
+
+    /// Resolve a required path against the requiring file's directory, then
+    /// as a script path.
+    fn resolve_require(&self, from: &Path, required: &str) -> Option<PathBuf> {
+        let mut relative = PathBuf::new();
+        for component in from
+            .parent()
+            .unwrap_or(Path::new(""))
+            .join(required)
+            .components()
+        {
+            match component {
+                std::path::Component::ParentDir => {
+                    relative.pop();
+                },
+                std::path::Component::CurDir => {},
+                other => relative.push(other),
+            }
+        }
+        if relative.extension().is_none() {
+            relative.set_extension("nut");
+        }
+        if self.file_globals.contains_key(&relative) {
+            return Some(relative);
+        }
+
+        self.get(&normalize_script_path(required))
+            .filter(|entry| !entry.is_stub())
+            .map(|entry| entry.file_path.clone())
+    }
+
+    /// Script path of a file on disk, or an empty string when the file is
+    /// outside every script root.
+    pub fn script_path_for(&self, file_path: &Path) -> String {
+        if self.script_roots.is_empty() {
+            return extract_script_path(file_path);
+        }
+
+        self.script_roots
+            .iter()
+            .filter_map(|root| file_path.strip_prefix(root).ok())
+            // The innermost root wins when roots are nested
+            .min_by_key(|relative| relative.components().count())
+            .map(|relative| {
+                relative
+                    .with_extension("")
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/")
+            })
+            .unwrap_or_default()
+    }
+
     /// Get a file entry by script path
     pub fn get(&self, script_path: &str) -> Option<&FileEntry> {
         // Try exact match first
@@ -76,20 +331,52 @@ impl Workspace {
         }
 
         // Try with/without "scripts/" prefix
-        let normalized = script_path
-            .trim_start_matches("scripts/")
-            .trim_end_matches(".nut");
+        let normalized = normalize_script_path(script_path);
+        self.files.get(normalized.as_str())
+    }
+
+    /// Like `get`, but falls back to ignoring case, which is how the path
+    /// resolves on case-insensitive filesystems. Only meant for reporting
+    /// such paths; callers compare `script_path` to tell the match apart.
+    pub fn get_ignoring_case(&self, script_path: &str) -> Option<&FileEntry> {
+        self.get(script_path).or_else(|| {
+            let lowercase = normalize_script_path(script_path).to_ascii_lowercase();
+            let indexed = self.paths_by_lowercase.get(&lowercase)?.first()?;
+            self.files.get(indexed)
+        })
+    }
+
+    /// Add or replace the entry for `entry.script_path`, keeping the lookup
+    /// maps in step.
+    fn insert_file(&mut self, entry: FileEntry) {
+        let script_path = entry.script_path.clone();
+        self.forget_file(&script_path);
+        self.paths_by_lowercase
+            .entry(script_path.to_ascii_lowercase())
+            .or_default()
+            .insert(script_path.clone());
+        self.paths_by_name
+            .entry(entry.name.clone())
+            .or_default()
+            .insert(script_path.clone());
+        self.files.insert(script_path, entry);
+    }
 
-        self.files.get(normalized)
+    /// Remove the entry for `script_path` along with its lookup map keys.
+    fn forget_file(&mut self, script_path: &str) -> Option<FileEntry> {
+        let entry = self.files.remove(script_path)?;
+        remove_indexed(
+            &mut self.paths_by_lowercase,
+            &script_path.to_ascii_lowercase(),
+            script_path,
+        );
+        remove_indexed(&mut self.paths_by_name, &entry.name, script_path);
+        Some(entry)
     }
 
     /// Get a mutable file entry by script path
     fn get_mut(&mut self, script_path: &str) -> Option<&mut FileEntry> {
-        let normalized = script_path
-            .trim_start_matches("scripts/")
-            .trim_end_matches(".nut");
-
-        self.files.get_mut(normalized)
+        self.files.get_mut(&normalize_script_path(script_path))
     }
 
     /// Check if a script path exists in the workspace
@@ -112,8 +399,35 @@ impl Workspace {
         &self.globals
     }
 
+    /// All classes and tables defined under `name`, across files
+    pub fn definitions_named(&self, name: &str) -> &[Definition] {
+        self.definitions.get(name).map_or(&[], Vec::as_slice)
+    }
+
+    /// Definitions that are not the main definition of their file
+    pub fn secondary_definitions(&self) -> impl Iterator<Item = &Definition> {
+        self.definitions.values().flatten().filter(|def| {
+            self.files
+                .get(&def.script_path)
+                .is_none_or(|entry| entry.name != def.name)
+        })
+    }
+
     /// Get all members of a file (including inherited members)
     pub fn get_all_members(&self, script_path: &str) -> Vec<MemberInfo> {
+        self.merge_inherited(script_path, |entry| &entry.members)
+    }
+
+    /// Slots of the `m` table, including those declared by ancestors.
+    pub fn get_all_m_fields(&self, script_path: &str) -> Vec<MemberInfo> {
+        self.merge_inherited(script_path, |entry| &entry.m_fields)
+    }
+
+    fn merge_inherited(
+        &self,
+        script_path: &str,
+        select: impl Fn(&FileEntry) -> &[MemberInfo],
+    ) -> Vec<MemberInfo> {
         let mut members = Vec::new();
         let mut member_map: HashMap<String, MemberInfo> = HashMap::new();
 
@@ -128,7 +442,7 @@ impl Workspace {
         // Walk from parent to child, so child members override parent
         for path in paths_to_check.iter().rev() {
             if let Some(entry) = self.get(path) {
-                for member in &entry.members {
+                for member in select(entry) {
                     member_map.insert(member.name.clone(), member.clone());
                 }
             }
@@ -189,6 +503,18 @@ impl Workspace {
                 }
             }
         }
+        for def in self.secondary_definitions() {
+            for member in &def.members {
+                if member.name == method_name && member.member_type == MemberType::Method {
+                    results.push((
+                        &def.file_path,
+                        member.line,
+                        member.column,
+                        def.script_path.as_str(),
+                    ));
+                }
+            }
+        }
         results
     }
 
@@ -219,6 +545,142 @@ impl Workspace {
         ancestors
     }
 
+    /// Find every descendant of a class that defines `method_name` itself,
+    /// walking the children graph depth-first.
+    pub fn find_overrides(
+        &self,
+        script_path: &str,
+        method_name: &str,
+    ) -> Vec<(&FileEntry, &MemberInfo)> {
+        let mut overrides = Vec::new();
+        let mut visited = HashSet::new();
+        let mut pending: Vec<&str> = self
+            .get(script_path)
+            .map(|entry| entry.children.iter().map(String::as_str).collect())
+            .unwrap_or_default();
+
+        while let Some(child_path) = pending.pop() {
+            if !visited.insert(child_path) {
+                continue;
+            }
+            let Some(entry) = self.get(child_path) else {
+                continue;
+            };
+            if let Some(member) = entry.members.iter().find(|m| m.name == method_name) {
+                overrides.push((entry, member));
+            }
+            pending.extend(entry.children.iter().map(String::as_str));
+        }
+
+        overrides.sort_by(|(a, _), (b, _)| a.script_path.cmp(&b.script_path));
+        overrides
+    }
+
+    /// Files whose analysis depends on `file_path`: its descendants, plus the
+    /// files that inherit from or hook it or any of those descendants.
+    /// Sorted, and never includes `file_path` itself.
+    pub fn dependent_files(&self, file_path: &Path) -> Vec<PathBuf> {
+        let mut affected = BTreeSet::new();
+        let mut visited = HashSet::new();
+        let mut pending = vec![self.script_path_for(file_path)];
+
+        while let Some(script_path) = pending.pop() {
+            if !visited.insert(script_path.clone()) {
+                continue;
+            }
+            if let Some(dependents) = self.dependents.get(&script_path) {
+                affected.extend(dependents.iter().cloned());
+            }
+            if let Some(entry) = self.get(&script_path) {
+                affected.insert(entry.file_path.clone());
+                pending.extend(entry.children.iter().cloned());
+            }
+        }
+
+        affected.remove(file_path);
+        affected.into_iter().collect()
+    }
+
+    /// Check whether every link of a file's inheritance chain resolves to an
+    /// indexed file, so its member list can be trusted to be complete.
+    pub fn has_resolved_ancestry(&self, script_path: &str) -> bool {
+        let mut current = match self.get(script_path) {
+            Some(entry) => entry,
+            None => return false,
+        };
+        let mut visited = HashSet::new();
+
+        loop {
+            // Stubs don't list every member
+            if current.is_stub() {
+                return false;
+            }
+            match (&current.parent_path, &current.parent) {
+                (None, _) => return true,
+                (Some(_), Some(parent)) => {
+                    if !visited.insert(parent.clone()) {
+                        return false; // Circular chains are never complete
+                    }
+                    match self.get(parent) {
+                        Some(entry) => current = entry,
+                        None => return false,
+                    }
+                },
+                (Some(_), None) => return false,
+            }
+        }
+    }
+
+    /// Add entries for the classes described by `manifest`, one per line as
+    /// `script/path | parent/path | method method ...`. Blank lines and `#`
+    /// comments are skipped. Stub entries have no file and are replaced by
+    /// any real file indexed under the same script path.
+    pub fn load_stubs(&mut self, manifest: &str) {
+        for line in manifest.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split('|').map(str::trim);
+            let (Some(script_path), Some(parent), Some(methods)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                continue;
+            };
+            if self.files.contains_key(script_path) {
+                continue;
+            }
+
+            let member = |name: &str, member_type| MemberInfo {
+                name: name.to_string(),
+                member_type,
+                line: 0,
+                column: 0,
+            };
+            let mut members: Vec<MemberInfo> = methods
+                .split_whitespace()
+                .map(|name| member(name, MemberType::Method))
+                .collect();
+            members.push(member("m", MemberType::Field));
+
+            self.insert_file(FileEntry {
+                file_path: PathBuf::new(),
+                script_path: script_path.to_string(),
+                name: script_path
+                    .rsplit('/')
+                    .next()
+                    .unwrap_or(script_path)
+                    .to_string(),
+                definition_line: 0,
+                parent_path: (!parent.is_empty()).then(|| parent.to_string()),
+                parent: None,
+                children: Vec::new(),
+                members,
+                m_fields: Vec::new(),
+            });
+        }
+    }
+
     /// Get script path for a file entry
     fn get_script_path(&self, entry: &FileEntry) -> String {
         entry.script_path.clone()
@@ -226,17 +688,73 @@ impl Workspace {
 
     /// Index a single file into the workspace
     pub fn index_file(&mut self, file_path: &Path, content: &str) -> Result<(), AnalysisError> {
-        let script_path = extract_script_path(file_path);
-        if script_path.is_empty() {
-            return Ok(()); // Skip files not under scripts/
+        if self.script_path_for(file_path).is_empty() && self.require_function.is_none() {
+            return Ok(()); // Skip files outside the script roots
         }
 
         let tree = helpers::parse_squirrel(content)?;
+        self.index_file_with_tree(file_path, &tree, content);
+        Ok(())
+    }
+
+    /// Like `index_file`, but reuses an already parsed tree.
+    pub fn index_file_with_tree(&mut self, file_path: &Path, tree: &Tree, content: &str) {
         let root = tree.root_node();
 
+        // Files outside the script roots are still tracked so they can be required
+        if let Some(require_function) = &self.require_function {
+            let requires = find_require_calls(root, content, require_function);
+            self.requires.insert(file_path.to_path_buf(), requires);
+            self.file_globals
+                .insert(file_path.to_path_buf(), top_level_names(root, content));
+        }
+
+        let script_path = self.script_path_for(file_path);
+        if script_path.is_empty() {
+            return;
+        }
+
+        self.set_global_definitions(file_path, collect_global_definitions(root, content));
+        self.set_functions(file_path, top_level_signatures(root, content, file_path));
+        let definitions = collect_definitions(root, content, file_path, &script_path);
+        let references = self.track_references.then(|| {
+            let mut methods: Vec<MemberInfo> = definitions
+                .iter()
+                .flat_map(|def| &def.members)
+                .filter(|member| member.member_type == MemberType::Method)
+                .cloned()
+                .collect();
+            methods.extend(top_level_functions(root, content));
+            FileReferences {
+                mentions: count_mentions(root, content),
+                definitions: methods,
+            }
+        });
+        self.set_file_references(file_path, references);
+        self.set_definitions(file_path, definitions);
+
         // Try to find inherit() calls first (class definitions)
         let inherits = find_inherit_calls(root, content);
 
+        self.forget_dependencies(file_path);
+        let hooks = find_hook_calls(root, content);
+        let targets: Vec<String> = inherits
+            .iter()
+            .map(|call| call.parent_path.as_str())
+            .chain(hooks.iter().map(|hook| hook.target_path.as_str()))
+            .map(normalize_script_path)
+            .collect();
+        for target in &targets {
+            self.dependents
+                .entry(target.clone())
+                .or_default()
+                .insert(file_path.to_path_buf());
+        }
+        if !targets.is_empty() {
+            self.dependency_targets
+                .insert(file_path.to_path_buf(), targets);
+        }
+
         if let Some(inherit_call) = inherits.into_iter().next() {
             // This is a class file
             let parent_path = normalize_script_path(&inherit_call.parent_path);
@@ -245,13 +763,15 @@ impl Workspace {
                 file_path: file_path.to_path_buf(),
                 script_path: script_path.clone(),
                 name: inherit_call.class_name,
+                definition_line: definition_line(inherit_call.class_body),
                 parent_path: Some(parent_path),
                 parent: None, // Resolved later
                 children: Vec::new(),
                 members: extract_members_from_table(inherit_call.class_body, content),
+                m_fields: extract_m_fields(inherit_call.class_body, content),
             };
 
-            self.files.insert(script_path, entry);
+            self.insert_file(entry);
         } else {
             // Look for global table definition matching file name
             let file_stem = file_path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
@@ -261,20 +781,204 @@ impl Workspace {
                     file_path: file_path.to_path_buf(),
                     script_path: script_path.clone(),
                     name,
+                    definition_line: definition_line(table_node),
                     parent_path: None,
                     parent: None,
                     children: Vec::new(),
                     members: extract_members_from_table(table_node, content),
+                    m_fields: extract_m_fields(table_node, content),
+                };
+
+                self.insert_file(entry);
+            } else if let Some(class) = main_class_declaration(root, content, file_stem) {
+                // Plain Squirrel `class` syntax, for projects outside BB
+                let entry = FileEntry {
+                    file_path: file_path.to_path_buf(),
+                    script_path: script_path.clone(),
+                    name: class.name,
+                    definition_line: definition_line(class.node),
+                    parent_path: class.parent,
+                    parent: None, // Resolved later
+                    children: Vec::new(),
+                    members: extract_members_from_class(class.body, content),
+                    m_fields: extract_m_fields(class.body, content),
                 };
 
-                self.files.insert(script_path, entry);
+                self.insert_file(entry);
+            }
+        }
+
+        // Slots attached to the main definition outside its body
+        if let Some(entry) = self.files.get_mut(&script_path) {
+            for member in extract_top_level_members(root, content, &entry.name) {
+                if !entry.members.iter().any(|m| m.name == member.name) {
+                    entry.members.push(member);
+                }
             }
         }
 
         // Extract global definitions
         self.extract_globals(root, content);
+    }
 
-        Ok(())
+    /// Replace what `file_path` contributes to the reference totals.
+    fn set_file_references(&mut self, file_path: &Path, references: Option<FileReferences>) {
+        if let Some(old) = self.references.remove(file_path) {
+            for (name, count) in &old.mentions {
+                subtract_count(&mut self.mention_totals, name, *count);
+            }
+            for def in &old.definitions {
+                subtract_count(&mut self.definition_totals, &def.name, 1);
+            }
+        }
+
+        let Some(references) = references else {
+            return;
+        };
+        for (name, count) in &references.mentions {
+            *self.mention_totals.entry(name.clone()).or_default() += count;
+        }
+        for def in &references.definitions {
+            *self.definition_totals.entry(def.name.clone()).or_default() += 1;
+        }
+        self.references.insert(file_path.to_path_buf(), references);
+    }
+
+    /// Whether anything indexed from `file_path` is still held, i.e. whether
+    /// `remove_file` has something to forget.
+    pub fn has_file(&self, file_path: &Path) -> bool {
+        self.global_definitions.contains_key(file_path) || self.requires.contains_key(file_path)
+    }
+
+    /// Forget everything indexed from `file_path`, such as a file that has
+    /// grown past the size limit. Call `build_inheritance_graph` afterwards.
+    pub fn remove_file(&mut self, file_path: &Path) {
+        self.requires.remove(file_path);
+        self.file_globals.remove(file_path);
+        self.set_definitions(file_path, Vec::new());
+        self.forget_global_definitions(file_path);
+        self.set_functions(file_path, Vec::new());
+        self.set_file_references(file_path, None);
+        self.forget_dependencies(file_path);
+
+        let script_path = self.script_path_for(file_path);
+        if self
+            .files
+            .get(&script_path)
+            .is_some_and(|entry| entry.file_path == file_path)
+            && let Some(entry) = self.forget_file(&script_path)
+            && let Some(parent) = entry.parent.and_then(|parent| self.files.get_mut(&parent))
+        {
+            parent.children.retain(|child| *child != script_path);
+        }
+    }
+
+    /// Replace the classes and tables `file_path` defines.
+    fn set_definitions(&mut self, file_path: &Path, definitions: Vec<Definition>) {
+        for name in self.definition_names.remove(file_path).unwrap_or_default() {
+            if let Some(defs) = self.definitions.get_mut(&name) {
+                defs.retain(|def| def.file_path != file_path);
+                if defs.is_empty() {
+                    self.definitions.remove(&name);
+                }
+            }
+        }
+        if definitions.is_empty() {
+            return;
+        }
+
+        let mut names = Vec::new();
+        for def in definitions {
+            names.push(def.name.clone());
+            self.definitions
+                .entry(def.name.clone())
+                .or_default()
+                .push(def);
+        }
+        self.definition_names.insert(file_path.to_path_buf(), names);
+    }
+
+    /// Drop `file_path` from the dependents of whatever it inherits from or hooks.
+    fn forget_dependencies(&mut self, file_path: &Path) {
+        for target in self
+            .dependency_targets
+            .remove(file_path)
+            .unwrap_or_default()
+        {
+            remove_indexed(&mut self.dependents, &target, file_path);
+        }
+    }
+
+    fn forget_global_definitions(&mut self, file_path: &Path) {
+        if let Some(old) = self.global_definitions.remove(file_path) {
+            for global in &old {
+                remove_indexed(&mut self.global_files, &global.name, file_path);
+            }
+        }
+    }
+
+    /// Replace the globals `file_path` defines, keeping `global_files` in step.
+    fn set_global_definitions(&mut self, file_path: &Path, globals: Vec<GlobalDefinition>) {
+        self.forget_global_definitions(file_path);
+        for global in &globals {
+            self.global_files
+                .entry(global.name.clone())
+                .or_default()
+                .insert(file_path.to_path_buf());
+        }
+        self.global_definitions
+            .insert(file_path.to_path_buf(), globals);
+    }
+
+    /// Replace the top-level functions `file_path` defines.
+    fn set_functions(&mut self, file_path: &Path, functions: Vec<(String, FunctionSignature)>) {
+        for name in self.function_names.remove(file_path).unwrap_or_default() {
+            if let Some(signatures) = self.functions.get_mut(&name) {
+                signatures.retain(|signature| signature.file_path != file_path);
+                if signatures.is_empty() {
+                    self.functions.remove(&name);
+                }
+            }
+        }
+        if functions.is_empty() {
+            return;
+        }
+
+        let mut names = Vec::new();
+        for (name, signature) in functions {
+            self.functions
+                .entry(name.clone())
+                .or_default()
+                .push(signature);
+            names.push(name);
+        }
+        self.function_names.insert(file_path.to_path_buf(), names);
+    }
+
+    /// Parameters of the top-level function `name`, when exactly one site in
+    /// the workspace defines it. With several, which one wins depends on the
+    /// order files are loaded in.
+    pub fn function_signature(&self, name: &str) -> Option<&FunctionSignature> {
+        match self.functions.get(name)?.as_slice() {
+            [signature] => Some(signature),
+            _ => None,
+        }
+    }
+
+    /// Every site defining the global `name`, ordered by file and position.
+    fn sites_of(&self, name: &str) -> Vec<(&Path, &GlobalDefinition)> {
+        let Some(files) = self.global_files.get(name) else {
+            return Vec::new();
+        };
+        files
+            .iter()
+            .filter_map(|path| self.global_definitions.get_key_value(path))
+            .flat_map(|(path, defs)| {
+                defs.iter()
+                    .filter(|def| def.name == name)
+                    .map(move |def| (path.as_path(), def))
+            })
+            .collect()
     }
 
     /// Build inheritance relationships after all files are indexed
@@ -286,9 +990,7 @@ impl Workspace {
                 && let Some(parent_path) = entry.parent_path.clone()
             {
                 // Normalize and resolve parent
-                let normalized_parent = normalize_script_path(&parent_path);
-
-                if self.contains(&normalized_parent) {
+                if let Some(normalized_parent) = self.resolve_parent(&parent_path) {
                     // Update parent reference
                     if let Some(entry_mut) = self.files.get_mut(&script_path) {
                         entry_mut.parent = Some(normalized_parent.clone());
@@ -305,6 +1007,17 @@ impl Workspace {
         }
     }
 
+    /// Resolve a `parent_path` to the script path of an indexed file: either
+    /// an `inherit` script path or the name of a file's main class.
+    fn resolve_parent(&self, parent_path: &str) -> Option<String> {
+        let normalized = normalize_script_path(parent_path);
+        if self.files.contains_key(&normalized) {
+            return Some(normalized);
+        }
+
+        self.paths_by_name.get(parent_path)?.first().cloned()
+    }
+
     /// Extract global variable definitions from a file
     fn extract_globals(&mut self, root: Node, text: &str) {
         for child in root.children(&mut root.walk()) {
@@ -323,10 +1036,98 @@ impl Workspace {
                 if has_new_slot && let Some(name) = global_name {
                     self.register_global(name);
                 }
+            } else if matches!(child.kind(), "enum_declaration" | "const_declaration")
+                && let Some(ident) = child
+                    .children(&mut child.walk())
+                    .find(|c| c.kind() == "identifier")
+            {
+                self.register_global(get_node_text(ident, text).to_string());
             }
         }
     }
 
+    /// Globals defined in `file_path` that other files define as well, each
+    /// with the other definitions ordered by file. Whichever file loads last
+    /// wins, so these are usually accidental. Guarded slots and placeholders
+    /// don't overwrite anything and are left out.
+    pub fn global_collisions(&self, file_path: &Path) -> Vec<GlobalCollision<'_>> {
+        let Some(own) = self.global_definitions.get(file_path) else {
+            return Vec::new();
+        };
+
+        own.iter()
+            .filter(|global| global.overwrites())
+            .filter_map(|global| {
+                let others: Vec<(&Path, &GlobalDefinition)> = self
+                    .sites_of(&global.name)
+                    .into_iter()
+                    .filter(|(path, def)| *path != file_path && def.overwrites())
+                    .collect();
+                (!others.is_empty()).then_some(GlobalCollision { global, others })
+            })
+            .collect()
+    }
+
+    /// Every global the indexed files define, with the file defining it.
+    pub fn global_definitions(&self) -> impl Iterator<Item = (&Path, &GlobalDefinition)> {
+        self.global_definitions
+            .iter()
+            .flat_map(|(path, globals)| globals.iter().map(move |global| (path.as_path(), global)))
+    }
+
+    /// Declaration and definition sites of the global `name`, a dotted path
+    /// without the leading `::`. Files are taken in path order; the definition
+    /// is the first site with a body, or the declaration if none has one.
+    pub fn global_sites(&self, name: &str) -> Option<GlobalSites<'_>> {
+        let sites = self.sites_of(name);
+        let declaration = *sites.first()?;
+        let definition = sites
+            .iter()
+            .copied()
+            .find(|(_, def)| def.has_body)
+            .unwrap_or(declaration);
+        Some(GlobalSites {
+            declaration,
+            definition,
+        })
+    }
+
+    /// Functions and methods defined in `file_path` whose name appears
+    /// nowhere else in the workspace, not even in a string. Empty unless
+    /// references are tracked.
+    pub fn unreferenced_definitions(&self, file_path: &Path) -> Vec<&MemberInfo> {
+        let Some(file) = self.references.get(file_path) else {
+            return Vec::new();
+        };
+
+        file.definitions
+            .iter()
+            .filter(|def| {
+                let mentions = self.mention_totals.get(&def.name).copied().unwrap_or(0);
+                let definitions = self.definition_totals.get(&def.name).copied().unwrap_or(0);
+                // Every definition mentions its own name once
+                mentions <= definitions
+            })
+            .collect()
+    }
+
+    /// Indexed files in which `name` occurs as an identifier or in an
+    /// identifier-like string. Without tracked references, every indexed
+    /// file may mention it.
+    pub fn files_mentioning(&self, name: &str) -> Vec<&PathBuf> {
+        let mut files: Vec<&PathBuf> = if self.track_references {
+            self.references
+                .iter()
+                .filter(|(_, file)| file.mentions.contains_key(name))
+                .map(|(path, _)| path)
+                .collect()
+        } else {
+            self.global_definitions.keys().collect()
+        };
+        files.sort();
+        files
+    }
+
     /// Find similar script paths for "did you mean?" suggestions
     pub fn find_similar_paths(&self, target: &str) -> Vec<String> {
         let mut candidates: Vec<(String, usize)> = self
@@ -376,73 +1177,507 @@ impl Workspace {
     }
 }
 
-/// Extract script path from a file path.
+/// Lower the total for `name` by `count`, dropping it once it reaches zero.
+fn subtract_count(totals: &mut HashMap<String, usize>, name: &str, count: usize) {
+    if let Some(total) = totals.get_mut(name) {
+        *total = total.saturating_sub(count);
+        if *total == 0 {
+            totals.remove(name);
+        }
+    }
+}
+
+/// Remove `value` from the set stored under `key`, dropping the set once it
+/// is empty.
+fn remove_indexed<T, Q>(index: &mut HashMap<String, BTreeSet<T>>, key: &str, value: &Q)
+where
+    T: Borrow<Q> + Ord,
+    Q: Ord + ?Sized,
+{
+    if let Some(paths) = index.get_mut(key) {
+        paths.remove(value);
+        if paths.is_empty() {
+            index.remove(key);
+        }
+    }
+}
+
+/// Extract script path from a file path: what follows the first `scripts/`
+/// segment, without the extension.
 /// E.g., "/path/to/scripts/statistics/statistics_manager.nut" -> "statistics/statistics_manager"
 fn extract_script_path(file_path: &Path) -> String {
     let path_str = file_path.to_string_lossy();
 
     if let Some(scripts_idx) = path_str.find("scripts/") {
         let after_scripts = &path_str[scripts_idx + 8..]; // len("scripts/") = 8
-        return after_scripts.trim_end_matches(".nut").to_string();
+        return Path::new(after_scripts)
+            .with_extension("")
+            .to_string_lossy()
+            .into_owned();
     }
 
     // Not under scripts/, return empty
     String::new()
 }
 
-/// Normalize a script path (remove "scripts/" prefix and ".nut" suffix)
+/// Normalize a script path (use `/` separators, remove "scripts/" prefix
+/// and ".nut" suffix)
 fn normalize_script_path(path: &str) -> String {
-    path.trim_start_matches("scripts/")
+    path.replace('\\', "/")
+        .trim_start_matches("scripts/")
         .trim_end_matches(".nut")
         .to_string()
 }
 
-/// Find a global table definition that matches the file name.
-/// Also searches inside ERROR nodes for partial parse results.
-fn find_global_table<'tree>(
-    root: Node<'tree>,
+/// Every class (`inherit` call) and global table defined in a file.
+fn collect_definitions(
+    root: Node,
     text: &str,
-    file_stem: &str,
-) -> Option<(String, Node<'tree>)> {
-    fn search_node<'tree>(
-        node: Node<'tree>,
-        text: &str,
-        file_stem: &str,
-    ) -> Option<(String, Node<'tree>)> {
-        for child in node.children(&mut node.walk()) {
-            if child.kind() == "update_expression" {
-                let mut has_new_slot = false;
-                let mut identifier_name = None;
-                let mut table_node = None;
+    file_path: &Path,
+    script_path: &str,
+) -> Vec<Definition> {
+    let classes = find_inherit_calls(root, text)
+        .into_iter()
+        .map(|call| Definition {
+            name: call.class_name,
+            file_path: file_path.to_path_buf(),
+            script_path: script_path.to_string(),
+            line: definition_line(call.class_body),
+            members: extract_members_from_table(call.class_body, text),
+        });
+    let tables = find_global_tables(root, text)
+        .into_iter()
+        .map(|(name, table)| Definition {
+            name,
+            file_path: file_path.to_path_buf(),
+            script_path: script_path.to_string(),
+            line: definition_line(table),
+            members: extract_members_from_table(table, text),
+        });
+    let declarations = find_class_declarations(root, text)
+        .into_iter()
+        .map(|class| Definition {
+            name: class.name,
+            file_path: file_path.to_path_buf(),
+            script_path: script_path.to_string(),
+            line: definition_line(class.node),
+            members: extract_members_from_class(class.body, text),
+        });
+    classes.chain(tables).chain(declarations).collect()
+}
 
-                for n in child.children(&mut child.walk()) {
-                    match n.kind() {
-                        "<-" => has_new_slot = true,
-                        "identifier" | "deref_expression" if identifier_name.is_none() => {
-                            identifier_name = helpers::extract_identifier_name(n, text);
-                        },
-                        "table" => table_node = Some(n),
-                        _ => {},
-                    }
-                }
+/// How often each identifier occurs in a file. String literals that look
+/// like identifiers count too, since Squirrel can look up slots by name.
+fn count_mentions(root: Node, text: &str) -> HashMap<String, usize> {
+    let mut mentions = HashMap::new();
+    let mut pending = vec![root];
+    while let Some(node) = pending.pop() {
+        let name = match node.kind() {
+            "identifier" => Some(get_node_text(node, text).to_string()),
+            "string" => Some(helpers::extract_string_content(node, text)).filter(|content| {
+                content.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                    && content
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '_')
+            }),
+            _ => None,
+        };
+        if let Some(name) = name {
+            *mentions.entry(name).or_insert(0) += 1;
+        }
+        pending.extend(node.children(&mut node.walk()));
+    }
+    mentions
+}
 
-                if has_new_slot
-                    && let Some(name) = identifier_name
-                    && let Some(table) = table_node
-                    && name == file_stem
-                {
-                    return Some((name, table));
+/// Top-level `function name()` and `name <- function ...` definitions.
+fn top_level_functions(root: Node, text: &str) -> Vec<MemberInfo> {
+    let mut functions = Vec::new();
+    for child in root.children(&mut root.walk()) {
+        let name_node = match child.kind() {
+            "function_declaration" => child
+                .children(&mut child.walk())
+                .find(|c| c.kind() == "identifier"),
+            "update_expression" => {
+                let is_function = child
+                    .children(&mut child.walk())
+                    .skip_while(|c| c.kind() != "<-")
+                    .find(|c| c.is_named())
+                    .is_some_and(|value| {
+                        matches!(
+                            value.kind(),
+                            "function_declaration" | "lambda_expression" | "anonymous_function"
+                        )
+                    });
+                child
+                    .named_child(0)
+                    .filter(|_| is_function)
+                    .and_then(helpers::find_last_identifier)
+            },
+            _ => None,
+        };
+        if let Some(name_node) = name_node {
+            let start = name_node.start_position();
+            functions.push(MemberInfo {
+                name: get_node_text(name_node, text).to_string(),
+                member_type: MemberType::Method,
+                line: start.row as u32,
+                column: start.column as u32,
+            });
+        }
+    }
+    functions
+}
+
+/// Paths passed as the first argument to `require_function("...")` calls.
+fn find_require_calls(root: Node, text: &str, require_function: &str) -> Vec<String> {
+    let mut paths = Vec::new();
+    let mut pending = vec![root];
+    while let Some(node) = pending.pop() {
+        if node.kind() == "call_expression"
+            && let Some(callee) = node.named_child(0)
+            && class_reference_name(callee, text).as_deref() == Some(require_function)
+            && let Some(path) = node
+                .children(&mut node.walk())
+                .filter(|c| c.kind() == "call_args")
+                .flat_map(|args| args.named_children(&mut args.walk()).collect::<Vec<_>>())
+                .next()
+                .filter(|arg| arg.kind() == "string")
+        {
+            paths.push(helpers::extract_string_content(path, text));
+        }
+        pending.extend(node.children(&mut node.walk()));
+    }
+    paths
+}
+
+/// Names a file defines at the top level: slots, functions, classes, enums
+/// and constants.
+fn top_level_names(root: Node, text: &str) -> HashSet<String> {
+    let mut names = HashSet::new();
+    for child in root.children(&mut root.walk()) {
+        let name_node = match child.kind() {
+            "update_expression" if child.children(&mut child.walk()).any(|c| c.kind() == "<-") => {
+                child.named_child(0)
+            },
+            "function_declaration"
+            | "class_declaration"
+            | "enum_declaration"
+            | "const_declaration" => child
+                .children(&mut child.walk())
+                .find(|c| c.kind() == "identifier"),
+            _ => None,
+        };
+        if let Some(name) = name_node.and_then(|n| class_reference_name(n, text)) {
+            names.insert(name);
+        }
+    }
+    names
+}
+
+/// Globals a file defines: top-level slots, classes, enums and constants,
+/// plus `::path <- value` slots anywhere, such as inside a mod's queued
+/// setup function. Slots on `this` or a local are not globals.
+fn collect_global_definitions(root: Node, text: &str) -> Vec<GlobalDefinition> {
+    let mut globals = Vec::new();
+    let mut pending = vec![root];
+    while let Some(node) = pending.pop() {
+        let top_level = node.parent().is_some_and(|parent| parent.id() == root.id());
+        let (target, has_body, kind) = match node.kind() {
+            "update_expression" if node.children(&mut node.walk()).any(|c| c.kind() == "<-") => (
+                node.named_child(0)
+                    .filter(|target| top_level || target.kind() != "identifier"),
+                node.named_child(1).is_some_and(is_global_body),
+                GlobalKind::Slot,
+            ),
+            "class_declaration" | "enum_declaration" | "const_declaration" if top_level => (
+                node.children(&mut node.walk())
+                    .find(|c| matches!(c.kind(), "identifier" | "global_variable")),
+                node.kind() != "const_declaration",
+                match node.kind() {
+                    "class_declaration" => GlobalKind::Class,
+                    "enum_declaration" => GlobalKind::Enum,
+                    _ => GlobalKind::Const,
+                },
+            ),
+            _ => (None, false, GlobalKind::Slot),
+        };
+        if let Some(target) = target
+            && let Some(name) = global_path(target, text)
+        {
+            let start = target.start_position();
+            let end = target.end_position();
+            globals.push(GlobalDefinition {
+                guarded: is_guarded_slot(node, &name, text),
+                name,
+                line: start.row as u32,
+                column: start.column as u32,
+                end_column: if end.row == start.row {
+                    end.column as u32
+                } else {
+                    start.column as u32
+                },
+                has_body,
+                kind,
+            });
+        }
+        pending.extend(node.children(&mut node.walk()));
+    }
+    globals.sort_by_key(|global| (global.line, global.column));
+    globals
+}
+
+/// Top-level functions a file defines under a bare global name, with
+/// `function name(...)`, `name <- function (...)` or `::name <- function (...)`.
+fn top_level_signatures(
+    root: Node,
+    text: &str,
+    file_path: &Path,
+) -> Vec<(String, FunctionSignature)> {
+    let mut signatures = Vec::new();
+    for child in root.children(&mut root.walk()) {
+        let (name, function) = match child.kind() {
+            "function_declaration" => (
+                declared_function_name(child).map(|name| get_node_text(name, text).to_string()),
+                child,
+            ),
+            "update_expression" if child.children(&mut child.walk()).any(|c| c.kind() == "<-") => {
+                let Some(value) = child.named_child(1) else {
+                    continue;
+                };
+                (
+                    child
+                        .named_child(0)
+                        .and_then(|target| global_path(target, text))
+                        .filter(|name| !name.contains('.')),
+                    value,
+                )
+            },
+            _ => continue,
+        };
+        if let Some(name) = name
+            && let SymbolKind::Function {
+                params,
+                required,
+                vararg,
+            } = extract_symbol_from_value(function, text)
+        {
+            signatures.push((
+                name,
+                FunctionSignature {
+                    file_path: file_path.to_path_buf(),
+                    params: params.len(),
+                    required,
+                    vararg,
+                },
+            ));
+        }
+    }
+    signatures
+}
+
+/// Whether `node` only runs when the slot `name` is missing: in the body of
+/// an `if` testing `!("slot" in table)`, as in
+/// `if (!("Arena" in ::Legends)) ::Legends.Arena <- {}`, or in the `else`
+/// of one testing `"slot" in table`.
+fn is_guarded_slot(node: Node, name: &str, text: &str) -> bool {
+    let Some((table, slot)) = name.rsplit_once('.') else {
+        return false;
+    };
+    let mut current = node;
+    while let Some(parent) = current.parent() {
+        if parent.kind() == "if_statement" {
+            let mut children = parent
+                .children(&mut parent.walk())
+                .collect::<Vec<_>>()
+                .into_iter();
+            let present = children
+                .by_ref()
+                .take_while(|child| child.kind() != ")")
+                .find(|child| child.is_named())
+                .and_then(|condition| slot_test(condition, table, slot, text));
+            let body = children.by_ref().find(|child| child.is_named());
+            let else_branch = children.find(|child| child.is_named());
+            let guarded_branch = match present {
+                Some(false) => body,
+                Some(true) => else_branch,
+                None => None,
+            };
+            if guarded_branch.is_some_and(|branch| branch.id() == current.id()) {
+                return true;
+            }
+        }
+        current = parent;
+    }
+    false
+}
+
+/// `Some(true)` when `condition` is `"slot" in table`, `Some(false)` when it
+/// is the negation `!("slot" in table)`, and `None` for anything else.
+fn slot_test(condition: Node, table: &str, slot: &str, text: &str) -> Option<bool> {
+    if condition
+        .children(&mut condition.walk())
+        .any(|c| c.kind() == "in")
+    {
+        let is_test = condition
+            .named_child(0)
+            .is_some_and(|key| get_node_text(key, text).trim_matches('"') == slot)
+            && condition
+                .named_child(1)
+                .and_then(|object| global_path(object, text))
+                .is_some_and(|path| path == table);
+        return is_test.then_some(true);
+    }
+
+    // Look through parentheses and `!`
+    let negated = condition.child(0).is_some_and(|c| c.kind() == "!");
+    let mut cursor = condition.walk();
+    let mut operands = condition.named_children(&mut cursor);
+    let (Some(inner), None) = (operands.next(), operands.next()) else {
+        return None;
+    };
+    slot_test(inner, table, slot, text).map(|present| present != negated)
+}
+
+/// Whether a slot's value gives a global its contents: a table or function,
+/// or a call that builds one from a table such as `this.inherit(path, {})`.
+fn is_global_body(value: Node) -> bool {
+    match value.kind() {
+        "table" | "anonymous_function" | "lambda_expression" => true,
+        "call_expression" => value
+            .children(&mut value.walk())
+            .filter(|child| child.kind() == "call_args")
+            .any(|args| {
+                args.children(&mut args.walk())
+                    .any(|arg| arg.kind() == "table")
+            }),
+        _ => false,
+    }
+}
+
+/// Dotted name of a root table path such as `::Legends.Foo`, or the name
+/// itself for a bare identifier. Paths through `this` or a local are `None`.
+pub(crate) fn global_path(node: Node, text: &str) -> Option<String> {
+    match node.kind() {
+        "identifier" => Some(get_node_text(node, text).to_string()),
+        "global_variable" => class_reference_name(node, text),
+        "deref_expression" => {
+            let object = node.named_child(0)?;
+            let member = node
+                .named_child(1)
+                .filter(|member| member.kind() == "identifier")?;
+            if object.kind() == "identifier" {
+                return None;
+            }
+            Some(format!(
+                "{}.{}",
+                global_path(object, text)?,
+                get_node_text(member, text)
+            ))
+        },
+        _ => None,
+    }
+}
+
+/// Every top-level `class` declaration, in source order. Also searches
+/// inside ERROR nodes for partial parse results.
+pub fn find_class_declarations<'tree>(
+    root: Node<'tree>,
+    text: &str,
+) -> Vec<ClassDeclaration<'tree>> {
+    let mut results = Vec::new();
+    for child in root.children(&mut root.walk()) {
+        match child.kind() {
+            "class_declaration" => results.extend(parse_class_declaration(child, text)),
+            "ERROR" => results.extend(find_class_declarations(child, text)),
+            _ => {},
+        }
+    }
+    results
+}
+
+fn parse_class_declaration<'tree>(
+    node: Node<'tree>,
+    text: &str,
+) -> Option<ClassDeclaration<'tree>> {
+    let mut name = None;
+    let mut parent = None;
+    let mut body = None;
+    let mut after_extends = false;
+
+    for child in node.children(&mut node.walk()) {
+        match child.kind() {
+            "class_body" => body = Some(child),
+            "extends" => {
+                after_extends = true;
+                // Some grammar versions nest the parent inside the clause
+                if let Some(inner) = child.named_child(0) {
+                    parent = class_reference_name(inner, text);
                 }
-            } else if child.kind() == "ERROR" {
-                // Search inside ERROR nodes for partial parse results (BB syntax extensions)
-                if let Some(result) = search_node(child, text, file_stem) {
-                    return Some(result);
+            },
+            _ if child.is_named() => {
+                let reference = class_reference_name(child, text);
+                if after_extends {
+                    parent = parent.or(reference);
+                } else {
+                    name = name.or(reference);
                 }
-            }
+            },
+            _ => {},
         }
-        None
     }
 
+    Some(ClassDeclaration {
+        name: name?,
+        parent,
+        node,
+        body: body?,
+    })
+}
+
+/// Name of a class referenced as `Name`, `ns.Name` or `::Name`.
+fn class_reference_name(node: Node, text: &str) -> Option<String> {
+    if node.kind() == "global_variable" {
+        return node
+            .children(&mut node.walk())
+            .find(|c| c.kind() == "identifier")
+            .map(|ident| get_node_text(ident, text).to_string());
+    }
+    helpers::extract_identifier_name(node, text)
+}
+
+/// The class a file defines: the one named after the file, or else the first.
+fn main_class_declaration<'tree>(
+    root: Node<'tree>,
+    text: &str,
+    file_stem: &str,
+) -> Option<ClassDeclaration<'tree>> {
+    let mut classes = find_class_declarations(root, text);
+    let index = classes
+        .iter()
+        .position(|class| class.name == file_stem)
+        .unwrap_or(0);
+    (index < classes.len()).then(|| classes.swap_remove(index))
+}
+
+/// Line of the `name <- ...` statement that owns a class body or table.
+fn definition_line(body: Node) -> u32 {
+    let mut current = body;
+    while let Some(parent) = current.parent() {
+        if parent.kind() == "update_expression" {
+            return parent.start_position().row as u32;
+        }
+        current = parent;
+    }
+    body.start_position().row as u32
+}
+
+/// Find a global table definition that matches the file name.
+fn find_global_table<'tree>(
+    root: Node<'tree>,
+    text: &str,
+    file_stem: &str,
+) -> Option<(String, Node<'tree>)> {
     // Also check if root itself contains the pattern (for ERROR root nodes)
     if root.kind() == "ERROR" {
         // Look for identifier <- table pattern directly in ERROR children
@@ -476,7 +1711,48 @@ fn find_global_table<'tree>(
         }
     }
 
-    search_node(root, text, file_stem)
+    find_global_tables(root, text)
+        .into_iter()
+        .find(|(name, _)| name == file_stem)
+}
+
+/// Every `name <- { ... }` table at the top level, in source order.
+/// Also searches inside ERROR nodes for partial parse results.
+fn find_global_tables<'tree>(root: Node<'tree>, text: &str) -> Vec<(String, Node<'tree>)> {
+    fn search_node<'tree>(node: Node<'tree>, text: &str, results: &mut Vec<(String, Node<'tree>)>) {
+        for child in node.children(&mut node.walk()) {
+            if child.kind() == "update_expression" {
+                let mut has_new_slot = false;
+                let mut identifier_name = None;
+                let mut table_node = None;
+
+                for n in child.children(&mut child.walk()) {
+                    match n.kind() {
+                        "<-" => has_new_slot = true,
+                        "identifier" | "deref_expression" if identifier_name.is_none() => {
+                            identifier_name = helpers::extract_identifier_name(n, text);
+                        },
+                        "table" => table_node = Some(n),
+                        _ => {},
+                    }
+                }
+
+                if has_new_slot
+                    && let Some(name) = identifier_name
+                    && let Some(table) = table_node
+                {
+                    results.push((name, table));
+                }
+            } else if child.kind() == "ERROR" {
+                // Search inside ERROR nodes for partial parse results (BB syntax extensions)
+                search_node(child, text, results);
+            }
+        }
+    }
+
+    let mut results = Vec::new();
+    search_node(root, text, &mut results);
+    results
 }
 
 /// Extract members from a table node
@@ -510,15 +1786,13 @@ fn extract_members_from_table(node: Node, text: &str) -> Vec<MemberInfo> {
                 }
             },
             "table_slot" => {
-                let mut key_node = None;
+                let key_node = helpers::table_slot_key(child);
                 let mut value_node = None;
                 let mut has_function_decl = false;
 
                 for slot_child in child.children(&mut child.walk()) {
                     match slot_child.kind() {
-                        "identifier" if key_node.is_none() => {
-                            key_node = Some(slot_child);
-                        },
+                        _ if Some(slot_child) == key_node => {},
                         "function_declaration" => {
                             has_function_decl = true;
                             // Extract function name
@@ -563,7 +1837,7 @@ fn extract_members_from_table(node: Node, text: &str) -> Vec<MemberInfo> {
 
                     let start = key.start_position();
                     members.push(MemberInfo {
-                        name: get_node_text(key, text).to_string(),
+                        name: helpers::extract_string_content(key, text),
                         member_type: if is_function {
                             MemberType::Method
                         } else {
@@ -583,8 +1857,129 @@ fn extract_members_from_table(node: Node, text: &str) -> Vec<MemberInfo> {
     members
 }
 
+/// Slots of the `m = { ... }` table declared in a class or table body.
+fn extract_m_fields(body: Node, text: &str) -> Vec<MemberInfo> {
+    for child in body.children(&mut body.walk()) {
+        if matches!(child.kind(), "function_declaration" | "table") {
+            continue;
+        }
+        if !matches!(child.kind(), "table_slot" | "class_member") {
+            let nested = extract_m_fields(child, text);
+            if !nested.is_empty() {
+                return nested;
+            }
+            continue;
+        }
+
+        let mut cursor = child.walk();
+        let mut children = child.children(&mut cursor);
+        let is_m = children
+            .find(|c| c.kind() == "identifier")
+            .is_some_and(|key| get_node_text(key, text) == "m");
+        if is_m && let Some(table) = children.find(|c| c.kind() == "table") {
+            return extract_members_from_table(table, text);
+        }
+    }
+
+    Vec::new()
+}
+
+/// Extract methods and fields from a `class_body` node
+fn extract_members_from_class(body: Node, text: &str) -> Vec<MemberInfo> {
+    let mut members = Vec::new();
+
+    for member in body.children(&mut body.walk()) {
+        if member.kind() != "class_member" {
+            continue;
+        }
+        let mut name_node = None;
+        let mut member_type = MemberType::Field;
+        for child in member.children(&mut member.walk()) {
+            match child.kind() {
+                "identifier" if name_node.is_none() => name_node = Some(child),
+                "function_declaration" => {
+                    member_type = MemberType::Method;
+                    if name_node.is_none() {
+                        name_node = child.child_by_field_name("name").or_else(|| {
+                            child
+                                .children(&mut child.walk())
+                                .find(|c| c.kind() == "identifier")
+                        });
+                    }
+                },
+                "lambda_expression" | "anonymous_function" => member_type = MemberType::Method,
+                _ => {},
+            }
+        }
+
+        if let Some(name_node) = name_node {
+            let start = name_node.start_position();
+            members.push(MemberInfo {
+                name: get_node_text(name_node, text).to_string(),
+                member_type,
+                line: start.row as u32,
+                column: start.column as u32,
+            });
+        }
+    }
+
+    members
+}
+
+/// Collect top-level `this.<name> <- ...` and `<main>.<name> <- ...` slots
+/// whose value is a function or table.
+fn extract_top_level_members(root: Node, text: &str, main_name: &str) -> Vec<MemberInfo> {
+    let mut members = Vec::new();
+
+    for child in root.children(&mut root.walk()) {
+        if child.kind() != "update_expression"
+            || !child.children(&mut child.walk()).any(|c| c.kind() == "<-")
+        {
+            continue;
+        }
+        let Some(target) = child.child(0).filter(|c| c.kind() == "deref_expression") else {
+            continue;
+        };
+        let Some(value) = child
+            .children(&mut child.walk())
+            .skip_while(|c| c.kind() != "<-")
+            .find(|c| c.is_named())
+        else {
+            continue;
+        };
+        let member_type = match value.kind() {
+            "function_declaration" | "lambda_expression" | "anonymous_function" => {
+                MemberType::Method
+            },
+            "table" => MemberType::Field,
+            _ => continue,
+        };
+
+        let target_text = get_node_text(target, text);
+        let segments: Vec<&str> = target_text.split('.').map(str::trim).collect();
+        let member = match segments.as_slice() {
+            ["this", main, member] | [main, member] if *main == main_name => *member,
+            ["this", member] if *member != main_name => *member,
+            _ => continue,
+        };
+
+        let Some(name_node) = helpers::find_last_identifier(target) else {
+            continue;
+        };
+        let start = name_node.start_position();
+        members.push(MemberInfo {
+            name: member.to_string(),
+            member_type,
+            line: start.row as u32,
+            column: start.column as u32,
+        });
+    }
+
+    members
+}
+
 /// Simple Levenshtein distance for suggestions
-fn levenshtein_distance(s1: &str, s2: &str) -> usize {
+pub(crate) fn levenshtein_distance(s1: &str, s2: &str) -> usize {
     let len1 = s1.chars().count();
     let len2 = s2.chars().count();
     let mut matrix = vec![vec![0; len2 + 1]; len1 + 1];
@@ -626,6 +2021,10 @@ mod tests {
             extract_script_path(Path::new("scripts/entity/tactical/actor.nut")),
             "entity/tactical/actor"
         );
+        assert_eq!(
+            extract_script_path(Path::new("/mod/scripts/mods/hooks.gnut")),
+            "mods/hooks"
+        );
         assert_eq!(extract_script_path(Path::new("/some/other/path.nut")), "");
     }
 
@@ -643,6 +2042,33 @@ mod tests {
             normalize_script_path("scripts/entity/tactical/actor.nut"),
             "entity/tactical/actor"
         );
+        assert_eq!(
+            normalize_script_path("scripts\\entity\\tactical\\actor"),
+            "entity/tactical/actor"
+        );
+    }
+
+    #[test]
+    fn test_escaped_parent_path_resolves() {
+        let mut workspace = Workspace::new();
+        workspace
+            .index_file(
+                Path::new("/test/scripts/entity/tactical/actor.nut"),
+                "this.actor <- this.inherit(\"scripts/entity/base\", {\n    function onDeath() {}\n});\n",
+            )
+            .unwrap();
+        // The source spells the path with escaped backslashes
+        workspace
+            .index_file(
+                Path::new("/test/scripts/entity/tactical/human.nut"),
+                r#"this.human <- this.inherit("scripts\\entity\\tactical\\actor", {});"#,
+            )
+            .unwrap();
+        workspace.build_inheritance_graph();
+
+        let actor = workspace.get("entity/tactical/actor").unwrap();
+        assert_eq!(actor.children, vec!["entity/tactical/human"]);
+        assert!(workspace.has_member("entity/tactical/human", "onDeath"));
     }
 
     #[test]
@@ -736,6 +2162,593 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_lookups_are_case_sensitive() {
+        let mut workspace = Workspace::new();
+        workspace
+            .index_file(
+                Path::new("/path/to/scripts/entity/tactical/actor.nut"),
+                r#"this.actor <- this.inherit("scripts/entity/base", {});"#,
+            )
+            .expect("Should parse");
+        workspace
+            .index_file(
+                Path::new("/path/to/scripts/entity/tactical/human.nut"),
+                r#"this.human <- this.inherit("scripts/entity/Tactical/actor", {});"#,
+            )
+            .expect("Should parse");
+        workspace.build_inheritance_graph();
+
+        assert!(!workspace.contains("entity/Tactical/actor"));
+        assert_eq!(
+            workspace
+                .get_ignoring_case("scripts/entity/Tactical/actor")
+                .map(|entry| entry.script_path.as_str()),
+            Some("entity/tactical/actor")
+        );
+        // A wrong-case parent is reported, not silently linked
+        assert_eq!(workspace.get("entity/tactical/human").unwrap().parent, None);
+        assert!(
+            workspace
+                .get("entity/tactical/actor")
+                .unwrap()
+                .children
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_function_valued_slots_are_methods() {
+        let mut workspace = Workspace::new();
+        let content = r#"
+            this.slash <- this.inherit("scripts/skills/skill", {
+                onUse = function(_user, _target) {},
+                getCost = @() 4,
+                Damage = 10
+            });
+        "#;
+        workspace
+            .index_file(Path::new("/path/to/scripts/skills/slash.nut"), content)
+            .expect("Should parse");
+
+        let slash = workspace.get("skills/slash").unwrap();
+        let kind_of = |name: &str| {
+            slash
+                .members
+                .iter()
+                .find(|m| m.name == name)
+                .map(|m| m.member_type.clone())
+        };
+        assert_eq!(kind_of("onUse"), Some(MemberType::Method));
+        assert_eq!(kind_of("getCost"), Some(MemberType::Method));
+        assert_eq!(kind_of("Damage"), Some(MemberType::Field));
+        assert_eq!(
+            workspace.find_method_definition("skills/slash", "onUse"),
+            Some((&PathBuf::from("/path/to/scripts/skills/slash.nut"), 2, 16))
+        );
+    }
+
+    #[test]
+    fn test_string_keyed_slots() {
+        let mut workspace = Workspace::new();
+        let content = r#"
+            this.shield <- this.inherit("scripts/skills/skill", {
+                "onUpdate" = function(_properties) {},
+                "Armor" = 20,
+                [::Const.Key] = 5
+            });
+        "#;
+        workspace
+            .index_file(Path::new("/path/to/scripts/skills/shield.nut"), content)
+            .expect("Should parse");
+
+        let shield = workspace.get("skills/shield").unwrap();
+        let kind_of = |name: &str| {
+            shield
+                .members
+                .iter()
+                .find(|m| m.name == name)
+                .map(|m| m.member_type.clone())
+        };
+        assert_eq!(kind_of("onUpdate"), Some(MemberType::Method));
+        assert_eq!(kind_of("Armor"), Some(MemberType::Field));
+        assert!(
+            !shield.members.iter().any(|m| m.name.contains('"')),
+            "Got: {:?}",
+            shield.members
+        );
+    }
+
+    #[test]
+    fn test_index_top_level_this_members() {
+        let mut workspace = Workspace::new();
+        let content = r#"
+this.knight <- this.inherit("scripts/entity/tactical/actor", {
+    function onInit() {}
+});
+this.knight.onCombatStarted <- function() {};
+this.getBanner <- function() { return 1; };
+this.knight.Defaults <- { Hitpoints = 100 };
+this.knight.Level <- 3;
+"#;
+        workspace
+            .index_file(
+                Path::new("/path/to/scripts/entity/tactical/knight.nut"),
+                content,
+            )
+            .unwrap();
+
+        let entry = workspace.get("entity/tactical/knight").unwrap();
+        let find = |name: &str| entry.members.iter().find(|m| m.name == name);
+        assert!(find("onInit").is_some());
+        assert_eq!(
+            find("onCombatStarted").map(|m| m.member_type.clone()),
+            Some(MemberType::Method)
+        );
+        assert_eq!(find("onCombatStarted").unwrap().line, 4);
+        assert_eq!(
+            find("getBanner").map(|m| m.member_type.clone()),
+            Some(MemberType::Method)
+        );
+        assert_eq!(
+            find("Defaults").map(|m| m.member_type.clone()),
+            Some(MemberType::Field)
+        );
+        assert!(find("Level").is_none());
+        assert!(find("knight").is_none());
+    }
+
+    #[test]
+    fn test_script_roots_replace_scripts_segment() {
+        let mut workspace = Workspace::new();
+        assert_eq!(
+            workspace.script_path_for(Path::new("/mod/scripts/skills/skill.nut")),
+            "skills/skill"
+        );
+
+        workspace.set_script_roots(vec![
+            PathBuf::from("/mod/src"),
+            PathBuf::from("/mod/src/vendor"),
+        ]);
+        assert_eq!(
+            workspace.script_path_for(Path::new("/mod/src/skills/skill.nut")),
+            "skills/skill"
+        );
+        assert_eq!(
+            workspace.script_path_for(Path::new("/mod/src/vendor/lib.nut")),
+            "lib"
+        );
+        assert_eq!(
+            workspace.script_path_for(Path::new("/mod/scripts/skills/skill.nut")),
+            ""
+        );
+
+        workspace
+            .index_file(Path::new("/mod/src/skills/skill.nut"), "skill <- {};")
+            .unwrap();
+        assert!(workspace.contains("skills/skill"));
+    }
+
+    #[test]
+    fn test_dependency_globals_follow_requires() {
+        let mut workspace = Workspace::new();
+        workspace.set_require_function(Some("require".to_string()));
+        workspace
+            .index_file(
+                Path::new("/mod/lib/math.nut"),
+                "require(\"util\");\nfunction clamp(v) { return v; }\nPI <- 3.14;\n",
+            )
+            .unwrap();
+        workspace
+            .index_file(
+                Path::new("/mod/lib/util.nut"),
+                "require(\"math.nut\");\nclass Helper {}\n",
+            )
+            .unwrap();
+        workspace
+            .index_file(
+                Path::new("/mod/scripts/main.nut"),
+                "require(\"../lib/math\");\nmain <- {};\n",
+            )
+            .unwrap();
+
+        let globals = workspace.dependency_globals(Path::new("/mod/scripts/main.nut"));
+        let mut names: Vec<&str> = globals.iter().map(String::as_str).collect();
+        names.sort_unstable();
+        assert_eq!(names, vec!["Helper", "PI", "clamp"]);
+
+        // The cycle between math and util terminates
+        assert!(
+            workspace
+                .dependency_globals(Path::new("/mod/lib/util.nut"))
+                .contains("clamp")
+        );
+        assert!(!workspace.contains("math"));
+    }
+
+    #[test]
+    fn test_index_class_declarations_with_extends() {
+        let mut workspace = Workspace::new();
+        workspace
+            .index_file(
+                Path::new("/mod/scripts/shapes/shape.nut"),
+                "class Shape {\n    sides = 0;\n    static MaxSides = 12;\n    function area() { return 0; }\n}\n",
+            )
+            .unwrap();
+        workspace
+            .index_file(
+                Path::new("/mod/scripts/shapes/square.nut"),
+                "class Square extends Shape {\n    function area() { return 4; }\n    function corners() { return this.sides; }\n}\n",
+            )
+            .unwrap();
+        workspace.build_inheritance_graph();
+
+        let square = workspace.get("shapes/square").unwrap();
+        assert_eq!(square.name, "Square");
+        assert_eq!(square.parent_path.as_deref(), Some("Shape"));
+        assert_eq!(square.parent.as_deref(), Some("shapes/shape"));
+        assert_eq!(
+            workspace.get("shapes/shape").unwrap().children,
+            vec!["shapes/square"]
+        );
+
+        let shape = workspace.get("shapes/shape").unwrap();
+        let sides = shape.members.iter().find(|m| m.name == "sides").unwrap();
+        assert_eq!(sides.member_type, MemberType::Field);
+        assert!(workspace.has_member("shapes/square", "sides"));
+        assert!(workspace.has_member("shapes/square", "MaxSides"));
+        assert!(workspace.has_resolved_ancestry("shapes/square"));
+        assert_eq!(workspace.definitions_named("Square").len(), 1);
+    }
+
+    #[test]
+    fn test_collect_global_definitions() {
+        let code = "::Mod <- {};\n::Mod.Config <- { A = 1 };\nhelper <- {};\nclass Widget {}\nthis.slot <- 1;\nlocal t = {};\nt.x <- 1;\nfunction setup() {\n    ::Mod.Late <- 1;\n    inner <- 2;\n}\n";
+        let tree = helpers::parse_squirrel(code).unwrap();
+        let globals = collect_global_definitions(tree.root_node(), code);
+        let names: Vec<_> = globals.iter().map(|g| g.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["Mod", "Mod.Config", "helper", "Widget", "Mod.Late"]
+        );
+        assert_eq!(
+            (globals[1].line, globals[1].column, globals[1].end_column),
+            (1, 0, 12)
+        );
+        assert!(!globals[1].guarded);
+    }
+
+    #[test]
+    fn test_guarded_global_slots() {
+        let code = "if (!(\"Arena\" in ::Legends)) ::Legends.Arena <- {};
+if (!(\"Arena\" in ::Legends)) ::Legends.Other <- {};
+if (\"Arena\" in ::Legends) {} else {
+    ::Legends.Arena <- {};
+}
+if (\"Arena\" in ::Legends) ::Legends.Arena <- {};
+if (!!(\"Arena\" in ::Legends)) ::Legends.Arena <- {};
+";
+        let tree = helpers::parse_squirrel(code).unwrap();
+        let guarded: Vec<_> = collect_global_definitions(tree.root_node(), code)
+            .iter()
+            .map(|g| (g.name.clone(), g.guarded))
+            .collect();
+        assert_eq!(
+            guarded,
+            vec![
+                ("Legends.Arena".to_string(), true),
+                ("Legends.Other".to_string(), false),
+                ("Legends.Arena".to_string(), true),
+                ("Legends.Arena".to_string(), false),
+                ("Legends.Arena".to_string(), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_global_sites_prefer_body_for_definition() {
+        let mut workspace = Workspace::new();
+        workspace
+            .index_file(Path::new("/mod/scripts/a.nut"), "::Mod.Registry <- null;\n")
+            .unwrap();
+        workspace
+            .index_file(
+                Path::new("/mod/scripts/b.nut"),
+                "::Mod.Registry <- null;\n::Mod.Registry <- {\n    Items = []\n};\n",
+            )
+            .unwrap();
+
+        let sites = workspace.global_sites("Mod.Registry").unwrap();
+        assert_eq!(sites.declaration.0, Path::new("/mod/scripts/a.nut"));
+        assert_eq!(sites.definition.0, Path::new("/mod/scripts/b.nut"));
+        assert_eq!(sites.definition.1.line, 1);
+        assert!(workspace.global_sites("Mod.Missing").is_none());
+    }
+
+    #[test]
+    fn test_dependent_files() {
+        let mut workspace = Workspace::new();
+        let files = [
+            (
+                "/mod/scripts/skills/skill.nut",
+                "this.skill <- {\n    function onUse() {}\n};\n",
+            ),
+            (
+                "/mod/scripts/skills/slash.nut",
+                "this.slash <- this.inherit(\"scripts/skills/skill\", {});\n",
+            ),
+            (
+                "/mod/scripts/skills/heavy_slash.nut",
+                "this.heavy_slash <- this.inherit(\"scripts/skills/slash\", {});\n",
+            ),
+            (
+                "/mod/scripts/mods/slash_hook.nut",
+                "::mods_hookExactClass(\"skills/slash\", function(o) {});\n",
+            ),
+            (
+                "/mod/scripts/mods/unrelated.nut",
+                "::mods_hookExactClass(\"items/sword\", function(o) {});\n",
+            ),
+        ];
+        for (path, content) in files {
+            workspace.index_file(Path::new(path), content).unwrap();
+        }
+        workspace.build_inheritance_graph();
+
+        assert_eq!(
+            workspace.dependent_files(Path::new("/mod/scripts/skills/skill.nut")),
+            vec![
+                PathBuf::from("/mod/scripts/mods/slash_hook.nut"),
+                PathBuf::from("/mod/scripts/skills/heavy_slash.nut"),
+                PathBuf::from("/mod/scripts/skills/slash.nut"),
+            ]
+        );
+        assert!(
+            workspace
+                .dependent_files(Path::new("/mod/scripts/skills/heavy_slash.nut"))
+                .is_empty()
+        );
+
+        // Dropping the hook from a re-indexed file drops the dependency
+        workspace
+            .index_file(Path::new("/mod/scripts/mods/slash_hook.nut"), "")
+            .unwrap();
+        assert_eq!(
+            workspace.dependent_files(Path::new("/mod/scripts/skills/slash.nut")),
+            vec![PathBuf::from("/mod/scripts/skills/heavy_slash.nut")]
+        );
+        workspace
+            .index_file(Path::new("/mod/scripts/mods/unrelated.nut"), "")
+            .unwrap();
+        assert!(!workspace.dependents.contains_key("items/sword"));
+    }
+
+    #[test]
+    fn test_remove_file() {
+        let mut workspace = Workspace::new();
+        let skill = Path::new("/mod/scripts/skills/skill.nut");
+        let slash = Path::new("/mod/scripts/skills/slash.nut");
+        workspace
+            .index_file(skill, "this.skill <- {\n    function onUse() {}\n};\n")
+            .unwrap();
+        workspace
+            .index_file(
+                slash,
+                "this.slash <- this.inherit(\"scripts/skills/skill\", {});\n::Mod.Slash <- {};\n",
+            )
+            .unwrap();
+        workspace.build_inheritance_graph();
+
+        workspace.remove_file(slash);
+        workspace.build_inheritance_graph();
+        assert!(!workspace.contains("skills/slash"));
+        assert!(workspace.get("skills/skill").unwrap().children.is_empty());
+        assert!(workspace.global_sites("Mod.Slash").is_none());
+        assert!(workspace.dependent_files(skill).is_empty());
+    }
+
+    #[test]
+    fn test_function_signatures() {
+        let mut workspace = Workspace::new();
+        let utils = Path::new("/mod/scripts/utils.nut");
+        workspace
+            .index_file(
+                utils,
+                "function clamp(value, low, high = 1) {}\n::pad <- function (text, ...) {};\nthis.hidden <- function () {};\n",
+            )
+            .unwrap();
+        workspace
+            .index_file(
+                Path::new("/mod/scripts/other.nut"),
+                "function pad(text) {}\n",
+            )
+            .unwrap();
+
+        assert_eq!(
+            workspace.function_signature("clamp"),
+            Some(&FunctionSignature {
+                file_path: utils.to_path_buf(),
+                params: 3,
+                required: 2,
+                vararg: false,
+            })
+        );
+        // Defined twice, so it's unclear which one a call reaches
+        assert!(workspace.function_signature("pad").is_none());
+        assert!(workspace.function_signature("hidden").is_none());
+
+        workspace.remove_file(Path::new("/mod/scripts/other.nut"));
+        assert!(workspace.function_signature("pad").unwrap().vararg);
+    }
+
+    #[test]
+    fn test_load_stubs() {
+        let mut workspace = Workspace::new();
+        workspace.load_stubs(
+            "# comment\nentity/tactical/entity | | getID getName\nentity/tactical/actor | entity/tactical/entity | kill\n",
+        );
+        workspace
+            .index_file(
+                Path::new("/mod/scripts/entity/tactical/actor.nut"),
+                "this.actor <- this.inherit(\"scripts/entity/tactical/entity\", {\n    function kill() {}\n    function isAlive() {}\n});\n",
+            )
+            .unwrap();
+        workspace
+            .index_file(
+                Path::new("/mod/scripts/entity/tactical/human.nut"),
+                "this.human <- this.inherit(\"scripts/entity/tactical/actor\", {});\n",
+            )
+            .unwrap();
+        workspace.build_inheritance_graph();
+
+        let entity = workspace.get("entity/tactical/entity").unwrap();
+        assert!(entity.is_stub());
+        assert_eq!(entity.children, vec!["entity/tactical/actor"]);
+        // The real file replaced its stub
+        assert!(!workspace.get("entity/tactical/actor").unwrap().is_stub());
+        assert!(workspace.has_member("entity/tactical/human", "getName"));
+        assert!(workspace.has_member("entity/tactical/human", "isAlive"));
+        // A stub ancestor may be missing members
+        assert!(!workspace.has_resolved_ancestry("entity/tactical/human"));
+    }
+
+    #[test]
+    fn test_index_every_definition_by_name() {
+        let mut workspace = Workspace::new();
+        let path = Path::new("/path/to/scripts/config/tables.nut");
+        let content = r#"
+tables <- {
+    function main() {}
+};
+Helpers <- {
+    function clamp(_v) {}
+};
+this.extra <- this.inherit("scripts/entity/tactical/actor", {
+    function onInit() {}
+});
+"#;
+        workspace.index_file(path, content).unwrap();
+
+        assert_eq!(workspace.get("config/tables").unwrap().name, "extra");
+        let helpers = workspace.definitions_named("Helpers");
+        assert_eq!(helpers.len(), 1);
+        assert_eq!(helpers[0].line, 4);
+        assert_eq!(helpers[0].script_path, "config/tables");
+        assert!(helpers[0].members.iter().any(|m| m.name == "clamp"));
+        assert_eq!(workspace.definitions_named("tables").len(), 1);
+
+        let secondary: Vec<&str> = {
+            let mut names: Vec<&str> = workspace
+                .secondary_definitions()
+                .map(|d| d.name.as_str())
+                .collect();
+            names.sort_unstable();
+            names
+        };
+        assert_eq!(secondary, vec!["Helpers", "tables"]);
+        assert_eq!(workspace.find_method_anywhere("clamp").len(), 1);
+
+        // Re-indexing drops definitions that are gone from the file
+        workspace.index_file(path, "tables <- {};").unwrap();
+        assert!(workspace.definitions_named("Helpers").is_empty());
+    }
+
+    #[test]
+    fn test_enum_and_const_globals() {
+        let mut workspace = Workspace::new();
+        workspace
+            .index_file(
+                Path::new("/path/to/scripts/config/directions.nut"),
+                "enum Direction { North, South }\nconst MaxLevel = 11;\n",
+            )
+            .unwrap();
+
+        assert!(workspace.globals().contains("Direction"));
+        assert!(workspace.globals().contains("MaxLevel"));
+        assert!(!workspace.globals().contains("North"));
+    }
+
+    #[test]
+    fn test_find_overrides() {
+        let mut workspace = Workspace::new();
+        let files = [
+            (
+                "actor",
+                r#"this.actor <- this.inherit("scripts/entity/tactical/base", {
+                    function onDeath() {}
+                });"#,
+            ),
+            (
+                "human",
+                r#"this.human <- this.inherit("scripts/entity/tactical/actor", {
+                    function onTurnStart() {}
+                });"#,
+            ),
+            (
+                "knight",
+                r#"this.knight <- this.inherit("scripts/entity/tactical/human", {
+                    function onDeath() {}
+                });"#,
+            ),
+            (
+                "zombie",
+                r#"this.zombie <- this.inherit("scripts/entity/tactical/actor", {
+                    function onDeath() {}
+                });"#,
+            ),
+        ];
+        for (name, content) in files {
+            let path = format!("/path/to/scripts/entity/tactical/{name}.nut");
+            workspace.index_file(Path::new(&path), content).unwrap();
+        }
+        workspace.build_inheritance_graph();
+
+        let overrides: Vec<&str> = workspace
+            .find_overrides("entity/tactical/actor", "onDeath")
+            .into_iter()
+            .map(|(entry, _)| entry.name.as_str())
+            .collect();
+        assert_eq!(overrides, vec!["knight", "zombie"]);
+        assert!(
+            workspace
+                .find_overrides("entity/tactical/knight", "onDeath")
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_definition_line() {
+        let mut workspace = Workspace::new();
+        let knight_content = r#"// Knight
+// Heavily armored melee unit
+
+this.knight <- this.inherit("scripts/entity/tactical/actor", {
+    function onInit() {}
+});
+"#;
+        workspace
+            .index_file(
+                Path::new("/path/to/scripts/entity/tactical/knight.nut"),
+                knight_content,
+            )
+            .unwrap();
+        workspace
+            .index_file(
+                Path::new("/path/to/scripts/config/strings.nut"),
+                "\nstrings <- {\n    Title = \"Battle\"\n};\n",
+            )
+            .unwrap();
+
+        assert_eq!(
+            workspace
+                .get("entity/tactical/knight")
+                .unwrap()
+                .definition_line,
+            3
+        );
+        assert_eq!(workspace.get("config/strings").unwrap().definition_line, 1);
+    }
+
     #[test]
     fn test_has_method_with_inheritance() {
         let mut workspace = Workspace::new();
@@ -79,17 +79,14 @@ this.legends_rangers_scenario <- this.inherit("scripts/scenarios/world/starting_
 			local x = this.Math.rand(5, this.Const.World.Settings.SizeX - 5);
 			local y = this.Math.rand(5, this.Const.World.Settings.SizeY - 5);
 
-			if (!this.World.isValidTileSquare(x, y)) {
-			} else {
+			if (!this.World.isValidTileSquare(x, y)) {} else {
 				local tile = this.World.getTileSquare(x, y);
 
-				if (tile.IsOccupied) {
-				} else if (tile.Type != this.Const.World.TerrainType.Forest
+				if (tile.IsOccupied) {} else if (tile.Type != this.Const.World.TerrainType.Forest
 					&& tile.Type != this.Const.World.TerrainType.SnowyForest
 					&& tile.Type != this.Const.World.TerrainType.LeaveForest
 					&& tile.Type != this.Const.World.TerrainType.AutumnForest)
-				{
-				} else {
+				{} else {
 					local next = true;
 
 					foreach (s in settlements) {
@@ -106,8 +103,7 @@ this.legends_rangers_scenario <- this.inherit("scripts/scenarios/world/starting_
 						}
 					}
 
-					if (next) {
-					} else {
+					if (next) {} else {
 						spawnTile = tile;
 						break;
 					}
@@ -0,0 +1,11 @@
+switch (type) {
+	case 0:
+	case 1:
+		local base = 10;
+		local bonus = 2;
+
+		return base + bonus;
+
+	default:
+		return 0;
+}
@@ -0,0 +1,11 @@
+foreach (i, item in items) {
+	foreach (key, value in item.Table) {
+		if ("x" in value && key in lookup) {
+			print(i);
+		}
+	}
+}
+local has = "x" in t;
+foreach (entry in list) {
+	print(entry);
+}
@@ -0,0 +1,10 @@
+function foo() {}
+if (x) {} else {
+	bar();
+}
+try {
+	baz();
+} catch (e) {}
+while (running) {}
+local handler = function() {};
+class Empty {}
@@ -0,0 +1,16 @@
+local objects = [
+	{
+		a = 1
+	},
+	{
+		b = 2
+	},
+];
+local mixed = [1, {
+	a = 1
+	},
+];
+local plain = [1, {
+	a = 1
+	}
+];
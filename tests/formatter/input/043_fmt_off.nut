@@ -0,0 +1,11 @@
+function lookup() {
+	// fmt: off
+	local table = [
+		[ 1,  2,   3 ],  // small
+		[ 10, 20,  30 ], // large
+	];
+	// fmt: on
+	local   x=1;
+	return table[x];
+}
+local  y = lookup();
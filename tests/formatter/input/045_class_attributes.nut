@@ -0,0 +1,11 @@
+class Widget </ version = 2 /> {
+	</ label="Name",  hidden = false />
+	name = null;
+
+	</ min = -1, max = 10 />	size = 0;
+
+	</ doc = "Resize the widget" />
+	function resize(value) {
+		size = value;
+	}
+}
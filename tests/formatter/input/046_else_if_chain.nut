@@ -0,0 +1,52 @@
+function pick(a, b, c) {
+	if (a)
+		return 1;
+	else if (b)
+		return 2;
+	else if (c)
+		return 3;
+	else
+		return 4;
+}
+
+function pickBraced(a, b, c) {
+	if (a) {
+		return 1;
+	} else if (b) {
+		return 2;
+	} else if (c) {
+		return 3;
+	} else {
+		return 4;
+	}
+}
+
+function nested(a, b) {
+	if (a)
+		if (b)
+			x();
+		else
+			y();
+	else
+		z();
+	if (a)
+		x(); // only a
+	else if (b)
+		y();
+	if (a)
+		foreach (item in b) {
+			x(item);
+		}
+	done();
+}
+
+function nestedBraced(a, b) {
+	if (a)
+		if (b) {
+			x();
+		} else {
+			y();
+		}
+	else
+		z();
+}
@@ -0,0 +1,11 @@
+local a = ! x;
+local b = !isAlive();
+local c = ! (a || b);
+local d = - x;
+local e = -( a + b );
+local f = - -x;
+local g = + +x;
+local h = ! !x;
+local i = -this.getValue();
+foo(!x, - 1);
+return -1;
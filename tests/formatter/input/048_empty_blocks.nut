@@ -0,0 +1,17 @@
+function foo() {
+}
+if (x) {
+}
+else {
+	bar();
+}
+try {
+	baz();
+} catch (e) {
+}
+while (running) {
+}
+local handler = function() {
+};
+class Empty {
+}
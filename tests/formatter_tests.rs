@@ -2,7 +2,10 @@ use std::fs;
 use std::path::{Path, PathBuf};
 
 use pretty_assertions::assert_eq;
-use squirrel_lsp::formatter::{FormatOptions, IndentStyle, format_document};
+use squirrel_lsp::config::Dialect;
+use squirrel_lsp::formatter::{
+    BraceStyle, FormatOptions, IndentStyle, LineEnding, debug_tokens, format_document,
+};
 
 #[test]
 fn test_formatter() {
@@ -33,14 +36,378 @@ fn test_formatter() {
         let expected = fs::read_to_string(&expected_path)
             .unwrap_or_else(|_| panic!("failed to read expected: {:?}", expected_path));
 
-        let options = if input.lines().any(|l| l.starts_with('\t')) {
-            FormatOptions::with_indent(IndentStyle::Tabs)
-        } else {
-            FormatOptions::default()
-        };
-
-        let output = format_document(&input, &options)
+        let output = format_document(&input, &fixture_options(&input))
             .unwrap_or_else(|e| panic!("formatting failed for {}: {}", file_name, e));
         assert_eq!(output, expected, "mismatch for case: {}", file_name);
     }
 }
+
+/// The cases in `expected_next_line` are inputs from `input` formatted with
+/// `BraceStyle::NextLine`.
+#[test]
+fn test_formatter_next_line() {
+    let base = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("formatter");
+    let expected_dir = base.join("expected_next_line");
+
+    let mut files: Vec<PathBuf> = fs::read_dir(&expected_dir)
+        .expect("failed to read tests/expected_next_line directory")
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "nut"))
+        .collect();
+    files.sort();
+
+    for expected_path in files {
+        let expected = fs::read_to_string(&expected_path)
+            .unwrap_or_else(|_| panic!("failed to read expected: {:?}", expected_path));
+        let file_name = expected_path.file_name().unwrap_or_default();
+        let input_path = base.join("input").join(file_name);
+        let input = fs::read_to_string(&input_path)
+            .unwrap_or_else(|_| panic!("failed to read input: {:?}", input_path));
+
+        let options = FormatOptions {
+            brace_style: BraceStyle::NextLine,
+            ..fixture_options(&input)
+        };
+        let output = format_document(&input, &options)
+            .unwrap_or_else(|e| panic!("formatting failed for {:?}: {}", file_name, e));
+        assert_eq!(output, expected, "mismatch for case: {:?}", file_name);
+    }
+}
+
+/// Fixtures indented with tabs are formatted with tabs, the rest with the
+/// default options.
+fn fixture_options(input: &str) -> FormatOptions {
+    if input.lines().any(|l| l.starts_with('\t')) {
+        FormatOptions::with_indent(IndentStyle::Tabs)
+    } else {
+        FormatOptions::default()
+    }
+}
+
+#[test]
+fn test_quirrel_operators() {
+    let options = FormatOptions {
+        dialect: Dialect::Quirrel,
+        ..FormatOptions::default()
+    };
+
+    let output = format_document("local name = unit ?. getName()??\"none\";\n", &options)
+        .expect("formatting failed");
+    assert_eq!(output, "local name = unit?.getName() ?? \"none\";\n");
+}
+
+#[test]
+fn test_crlf_round_trip() {
+    let options = FormatOptions::default();
+    let input = "// Header\r\nlocal a=1;\r\n\r\nfunction f() {\r\nreturn a;\r\n}\r\n";
+
+    let output = format_document(input, &options).expect("formatting failed");
+    assert_eq!(
+        output,
+        "// Header\r\nlocal a = 1;\r\n\r\nfunction f() {\r\n\treturn a;\r\n}\r\n"
+    );
+    assert_eq!(format_document(&output, &options).unwrap(), output);
+}
+
+#[test]
+fn test_forced_line_ending() {
+    let options = FormatOptions {
+        line_ending: LineEnding::Lf,
+        ..FormatOptions::default()
+    };
+    let output = format_document("local a = 1;\r\nlocal b = 2;\r\n", &options).unwrap();
+    assert_eq!(output, "local a = 1;\nlocal b = 2;\n");
+
+    let options = FormatOptions {
+        line_ending: LineEnding::Crlf,
+        ..FormatOptions::default()
+    };
+    let output = format_document("local a = 1;\nlocal b = 2;\n", &options).unwrap();
+    assert_eq!(output, "local a = 1;\r\nlocal b = 2;\r\n");
+}
+
+#[test]
+fn test_line_endings_inside_verbatim_strings_are_kept() {
+    let input = "local a = @\"one\r\ntwo\";\nlocal b = @\"three\nfour\";\n";
+
+    let options = FormatOptions {
+        line_ending: LineEnding::Lf,
+        ..FormatOptions::default()
+    };
+    assert_eq!(format_document(input, &options).unwrap(), input);
+
+    let options = FormatOptions {
+        line_ending: LineEnding::Crlf,
+        ..FormatOptions::default()
+    };
+    assert_eq!(
+        format_document(input, &options).unwrap(),
+        "local a = @\"one\r\ntwo\";\r\nlocal b = @\"three\nfour\";\r\n"
+    );
+}
+
+#[test]
+fn test_switch_case_body_blank_line_limit() {
+    let options = FormatOptions {
+        max_consecutive_blank_lines: 2,
+        ..FormatOptions::with_indent(IndentStyle::Tabs)
+    };
+    let input = "switch (x) {\n\tcase 1:\n\t\tfoo();\n\n\n\n\t\tbar();\n\t\tbreak;\n}\n";
+
+    let output = format_document(input, &options).expect("formatting failed");
+    assert_eq!(
+        output,
+        "switch (x) {\n\tcase 1:\n\t\tfoo();\n\n\n\t\tbar();\n\t\tbreak;\n}\n"
+    );
+}
+
+#[test]
+fn test_align_trailing_comments() {
+    let options = FormatOptions {
+        align_trailing_comments: true,
+        ..FormatOptions::with_indent(IndentStyle::Spaces(4))
+    };
+    let input = r#"local hp = 10; // health
+local fatigueRecovery = 3; // per turn
+local name = "a // b"; // not split inside strings
+
+// standalone comment
+local x = 1; // alone
+local y = 2;
+"#;
+
+    let output = format_document(input, &options).expect("formatting failed");
+    assert_eq!(
+        output,
+        r#"local hp = 10;             // health
+local fatigueRecovery = 3; // per turn
+local name = "a // b";     // not split inside strings
+
+// standalone comment
+local x = 1; // alone
+local y = 2;
+"#
+    );
+}
+
+#[test]
+fn test_align_trailing_comments_skips_multiline_strings_and_comments() {
+    let options = FormatOptions {
+        align_trailing_comments: true,
+        ..FormatOptions::with_indent(IndentStyle::Spaces(4))
+    };
+    let input = r#"local p = 1; // one
+local path = @"C:\mods\"; // windows
+local text = @"first // line
+x; // still text
+""quoted"" // more"; // closes
+local a = 1; /* block
+b = 2; // inside */
+local c = 3; // after
+local dd = 4; // aligned
+"#;
+
+    let output = format_document(input, &options).expect("formatting failed");
+    assert_eq!(
+        output,
+        r#"local p = 1;              // one
+local path = @"C:\mods\"; // windows
+local text = @"first // line
+x; // still text
+""quoted"" // more"; // closes
+local a = 1; /* block
+b = 2; // inside */
+local c = 3;  // after
+local dd = 4; // aligned
+"#
+    );
+}
+
+#[test]
+fn test_brace_style_same_line() {
+    let options = FormatOptions::with_indent(IndentStyle::Spaces(4));
+    let input = "function foo(a)\n{\n    if (a)\n    {\n        return 1;\n    }\n    else\n    {\n        return 2;\n    }\n}\n";
+
+    let output = format_document(input, &options).expect("formatting failed");
+    assert_eq!(
+        output,
+        "function foo(a) {\n    if (a) {\n        return 1;\n    } else {\n        return 2;\n    }\n}\n"
+    );
+}
+
+#[test]
+fn test_brace_style_next_line() {
+    let options = FormatOptions {
+        brace_style: BraceStyle::NextLine,
+        ..FormatOptions::with_indent(IndentStyle::Spaces(4))
+    };
+    let input = "function foo(a) {\n    if (a) {\n        return 1;\n    } else {\n        return 2;\n    }\n}\n";
+
+    let output = format_document(input, &options).expect("formatting failed");
+    assert_eq!(
+        output,
+        "function foo(a)\n{\n    if (a)\n    {\n        return 1;\n    }\n    else\n    {\n        return 2;\n    }\n}\n"
+    );
+}
+
+#[test]
+fn test_multiline_verbatim_string_untouched() {
+    let options = FormatOptions::with_indent(IndentStyle::Tabs);
+    let input = "local s = @\"first line\n    indented   \n\ttab\n\"\"quoted\"\"\n\n\nlast\";\nfunction f() {\nlocal t = @\"a\n  b  \";\n}\n";
+
+    let output = format_document(input, &options).expect("formatting failed");
+    assert_eq!(
+        output,
+        "local s = @\"first line\n    indented   \n\ttab\n\"\"quoted\"\"\n\n\nlast\";\nfunction f() {\n\tlocal t = @\"a\n  b  \";\n}\n"
+    );
+}
+
+#[test]
+fn test_collapse_short_if() {
+    let options = FormatOptions {
+        collapse_short_if: true,
+        max_width: 40,
+        ..FormatOptions::with_indent(IndentStyle::Tabs)
+    };
+    let input = "function f(a) {\nif (a == null) return;\nif (a) this.doSomethingRatherLong(a, a);\nif (a) return 1;\nelse return 2;\n}\n";
+
+    let output = format_document(input, &options).expect("formatting failed");
+    assert_eq!(
+        output,
+        "function f(a) {\n\tif (a == null) return;\n\tif (a) {\n\t\tthis.doSomethingRatherLong(a, a);\n\t}\n\tif (a) {\n\t\treturn 1;\n\t} else {\n\t\treturn 2;\n\t}\n}\n"
+    );
+
+    let expanded =
+        format_document("if (a) return;\n", &FormatOptions::default()).expect("formatting failed");
+    assert_eq!(expanded, "if (a) {\n\treturn;\n}\n");
+}
+
+#[test]
+fn test_reindent_block_comments() {
+    let options = FormatOptions {
+        reindent_block_comments: true,
+        ..FormatOptions::default()
+    };
+    let input = "function f() {\n\tif (a) {\n\t\t/**\n  * Explain.\n      Keep this.\n    */\n\t\tg();\n\t}\n}\n";
+
+    let output = format_document(input, &options).expect("formatting failed");
+    assert_eq!(
+        output,
+        "function f() {\n\tif (a) {\n\t\t/**\n\t\t * Explain.\n      Keep this.\n\t\t */\n\t\tg();\n\t}\n}\n"
+    );
+    let verbatim = format_document(input, &FormatOptions::default()).expect("formatting failed");
+    assert_eq!(verbatim, input);
+}
+
+#[test]
+fn test_respaced_attributes() {
+    let options = FormatOptions {
+        preserve_attributes: false,
+        ..FormatOptions::default()
+    };
+    let input = "class Widget {\n\t</ label=\"Name\",min=-1,range=[0,1]/>\n\tsize = 0;\n}\n";
+
+    let output = format_document(input, &options).expect("formatting failed");
+    assert_eq!(
+        output,
+        "class Widget {\n\t</ label = \"Name\", min = -1, range = [0, 1] />\n\tsize = 0;\n}\n"
+    );
+    let preserved = format_document(input, &FormatOptions::default()).expect("formatting failed");
+    assert_eq!(preserved, input);
+}
+
+#[test]
+fn test_chain_break_width() {
+    let options = FormatOptions {
+        chain_break_width: Some(60),
+        ..FormatOptions::default()
+    };
+    let input = "local names = ::World.getPlayerRoster().getAll().filter(isAlive).map(getName);\nlocal n = roster.getAll().len();\n";
+
+    let output = format_document(input, &options).expect("formatting failed");
+    assert_eq!(
+        output,
+        "local names = ::World\n\t.getPlayerRoster()\n\t.getAll()\n\t.filter(isAlive)\n\t.map(getName);\nlocal n = roster.getAll().len();\n"
+    );
+    let reformatted = format_document(&output, &options).expect("formatting failed");
+    assert_eq!(reformatted, output);
+
+    let inline = format_document(input, &FormatOptions::default()).expect("formatting failed");
+    assert_eq!(inline, input);
+}
+
+#[test]
+fn test_numeric_literals_stay_intact() {
+    let literals = ["0x1F", "0XFF", "017", "1.5", "1.5e3", "2.0E-4", "1e10"];
+    for literal in literals {
+        let input = format!("local n = -{literal} + {literal}*2;\n");
+        let output = format_document(&input, &FormatOptions::default()).expect("formatting failed");
+        assert_eq!(output, format!("local n = -{literal} + {literal} * 2;\n"));
+    }
+
+    let input = "x=a.len()+0x10;y=arr[1.5e3];\n";
+    let output = format_document(input, &FormatOptions::default()).expect("formatting failed");
+    assert_eq!(output, "x = a.len() + 0x10;\ny = arr[1.5e3];\n");
+}
+
+#[test]
+fn test_fmt_off_without_on_runs_to_end() {
+    let input = "local  a=1;\n// fmt: off\nlocal  b   =  2;\nlocal c=[1,\n  2];\n";
+
+    let output = format_document(input, &FormatOptions::default()).expect("formatting failed");
+    assert_eq!(
+        output,
+        "local a = 1;\n// fmt: off\nlocal  b   =  2;\nlocal c=[1,\n  2];\n"
+    );
+}
+
+#[test]
+fn test_no_logical_break_inside_subscript() {
+    let options = FormatOptions {
+        max_width: 60,
+        ..FormatOptions::default()
+    };
+    let input = "local value = lookupTable[isEnabled && hasPermission && !isLocked && isReady];\n";
+
+    let output = format_document(input, &options).expect("formatting failed");
+    assert_eq!(output, input);
+}
+
+#[test]
+fn test_debug_tokens() {
+    let tokens = debug_tokens("local x = 1;\n// done\n").expect("tokenizing failed");
+    assert_eq!(
+        tokens,
+        "Keyword\t\"local\"\nIdentifier\t\"x\"\nSymbol\t\"=\"\nNumber\t\"1\"\nSymbol\t\";\"\nComment\t\"// done\"\tnewline\n"
+    );
+}
+
+#[test]
+fn test_continuation_indent() {
+    let options = FormatOptions {
+        continuation_indent: 2,
+        ..FormatOptions::default()
+    };
+    let input = "function test(_e)\n{\n\tif ((_e.isDying() || _e.isAlive()) && _e.getFaction() != this.Const.Faction.Player && _e.getFaction() != this.Const.Faction.PlayerAnimals)\n\t{\n\t\treturn 1;\n\t}\n}\n";
+
+    let output = format_document(input, &options).expect("formatting failed");
+    assert_eq!(
+        output,
+        "function test(_e) {\n\tif ((_e.isDying() || _e.isAlive())\n\t\t\t&& _e.getFaction() != this.Const.Faction.Player\n\t\t\t&& _e.getFaction() != this.Const.Faction.PlayerAnimals)\n\t{\n\t\treturn 1;\n\t}\n}\n"
+    );
+
+    let options = FormatOptions {
+        chain_break_width: Some(60),
+        ..options
+    };
+    let input = "local names = ::World.getPlayerRoster().getAll().filter(isAlive).map(getName);\nlocal n = 1;\n";
+
+    let output = format_document(input, &options).expect("formatting failed");
+    assert_eq!(
+        output,
+        "local names = ::World\n\t\t.getPlayerRoster()\n\t\t.getAll()\n\t\t.filter(isAlive)\n\t\t.map(getName);\nlocal n = 1;\n"
+    );
+}